@@ -1,17 +1,89 @@
+use std::{fs, sync::Arc};
+
 use clap::Parser;
 
-use converter::kql_to_sql;
+use converter::{
+    dialect_by_name, kql_to_sql, kql_to_sql_with_catalog, Dialect, MemoryCatalog, Strict,
+};
 
 #[derive(Parser, Debug)]
 struct Arguments {
     input: String,
+    /// Schema catalog of the tables, as a JSON or YAML file
+    #[clap(long)]
+    catalog: Option<String>,
+    /// SQL file of CREATE TABLE statements to add to the schema catalog
+    #[clap(long)]
+    schema: Option<String>,
+    /// SQL dialect to produce: singlestore, postgres, sqlite or ansi
+    #[clap(long, default_value = "singlestore")]
+    dialect: String,
+    /// Quote every identifier, rather than only those which need it
+    #[clap(long)]
+    strict_quoting: bool,
 }
 
 fn main() {
-    let Arguments { input: kql } = Arguments::parse();
+    let Arguments {
+        input: kql,
+        catalog,
+        schema,
+        dialect,
+        strict_quoting,
+    } = Arguments::parse();
+
+    let dialect = match dialect_by_name(&dialect) {
+        Some(dialect) => dialect,
+        None => {
+            println!("Errors:\nUnknown SQL dialect '{}'", dialect);
+            return;
+        }
+    };
+    let strict = Strict(&*dialect);
+    let dialect: &dyn Dialect = if strict_quoting { &strict } else { &*dialect };
+
+    let catalog = match load_catalogs(catalog, schema) {
+        Ok(catalog) => catalog,
+        Err(error) => {
+            println!("Errors:\n{}", error);
+            return;
+        }
+    };
+
     println!("KQL: {}", kql);
-    match kql_to_sql("input.kql".into(), kql) {
+    let result = match catalog {
+        Some(catalog) => {
+            kql_to_sql_with_catalog("input.kql".into(), kql, Arc::new(catalog), dialect)
+        }
+        None => kql_to_sql("input.kql".into(), kql, dialect),
+    };
+    match result {
         Ok(sql) => println!("SQL:\n{}", sql),
         Err(error) => println!("Errors:\n{}", error),
     }
 }
+
+fn load_catalogs(
+    catalog: Option<String>,
+    schema: Option<String>,
+) -> Result<Option<MemoryCatalog>, String> {
+    let mut catalog = catalog.map(|path| load_catalog(&path)).transpose()?;
+    if let Some(path) = schema {
+        let text = fs::read_to_string(&path).map_err(|error| format!("{}: {}", path, error))?;
+        catalog
+            .get_or_insert_with(MemoryCatalog::new)
+            .add_ddl(&text)
+            .map_err(|error| format!("{}: {}", path, error))?;
+    }
+    Ok(catalog)
+}
+
+fn load_catalog(path: &str) -> Result<MemoryCatalog, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let catalog = if path.ends_with(".json") {
+        MemoryCatalog::from_json(&text)
+    } else {
+        MemoryCatalog::from_yaml(&text)
+    };
+    catalog.map_err(|error| format!("{}: {}", path, error))
+}
@@ -0,0 +1,226 @@
+use kql_parser::{
+    ast as kast,
+    spans::{MBox, Span, M},
+};
+use sql_emitter::ast as sast;
+
+use crate::{merger::Merger, ConverterError};
+
+/// The KQL aggregation functions which can be translated to SQL
+pub const AGGREGATES: &[&str] = &[
+    "arg_max",
+    "arg_min",
+    "avg",
+    "count",
+    "countif",
+    "dcount",
+    "make_list",
+    "make_set",
+    "max",
+    "min",
+    "percentile",
+    "stdev",
+    "sum",
+    "sumif",
+    "variance",
+];
+
+pub fn is_aggregate(name: &str) -> bool {
+    AGGREGATES.contains(&name)
+}
+
+/// `arg_max` and `arg_min` select whole rows rather than computing a value,
+/// so they are translated by the merger instead of `to_aggregate`.
+pub fn is_arg_extremum(name: &str) -> bool {
+    name == "arg_max" || name == "arg_min"
+}
+
+/// Whether an expression calls an aggregation function anywhere within it
+pub fn contains_aggregate(expr: &kast::Expression) -> bool {
+    match expr {
+        kast::Expression::Identifier { .. }
+        | kast::Expression::Literal { .. }
+        | kast::Expression::Wildcard => false,
+        kast::Expression::FuncCall { name, args, .. } => {
+            is_aggregate(&name.value) || args.iter().any(|arg| contains_aggregate(&arg.value))
+        }
+        kast::Expression::BinaryOp { left, right, .. } => {
+            contains_aggregate(&left.value) || contains_aggregate(&right.value)
+        }
+        kast::Expression::UnaryOp { expr, .. } => contains_aggregate(&expr.value),
+        kast::Expression::List { items, .. } => {
+            items.iter().any(|item| contains_aggregate(&item.value))
+        }
+        kast::Expression::Range { start, end, .. } => {
+            contains_aggregate(&start.value) || contains_aggregate(&end.value)
+        }
+    }
+}
+
+/// Translates a call to a KQL aggregation function into its SQL equivalent.
+pub fn to_aggregate(
+    merger: &Merger,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    let aggregate = match name.value.as_str() {
+        "count" => {
+            check_arity(merger, &name, &args, 0, 0, span)?;
+            aggregate(sast::AggregateFunction::Count, false, vec![])
+        }
+        "countif" => {
+            check_arity(merger, &name, &args, 1, 1, span)?;
+            let mut args = args.into_iter();
+            let condition = merger.to_search_condition(args.next().unwrap())?;
+            let one = Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::Integer(1),
+            });
+            aggregate(
+                sast::AggregateFunction::Count,
+                false,
+                vec![conditional(condition, one)],
+            )
+        }
+        "sumif" => {
+            check_arity(merger, &name, &args, 2, 2, span)?;
+            let mut args = args.into_iter();
+            let value = merger.to_value_expression(args.next().unwrap())?;
+            let condition = merger.to_search_condition(args.next().unwrap())?;
+            aggregate(
+                sast::AggregateFunction::Sum,
+                false,
+                vec![conditional(condition, value)],
+            )
+        }
+        "sum" => simple(merger, sast::AggregateFunction::Sum, name, args, span)?,
+        "avg" => simple(merger, sast::AggregateFunction::Avg, name, args, span)?,
+        "min" => simple(merger, sast::AggregateFunction::Min, name, args, span)?,
+        "max" => simple(merger, sast::AggregateFunction::Max, name, args, span)?,
+        "stdev" => simple(
+            merger,
+            sast::AggregateFunction::StddevSamp,
+            name,
+            args,
+            span,
+        )?,
+        "variance" => simple(merger, sast::AggregateFunction::VarSamp, name, args, span)?,
+        "make_list" => simple(merger, sast::AggregateFunction::JsonAgg, name, args, span)?,
+        "make_set" => {
+            check_arity(merger, &name, &args, 1, 1, span)?;
+            let args = to_value_expressions(merger, args)?;
+            aggregate(sast::AggregateFunction::JsonAgg, true, args)
+        }
+        "dcount" => {
+            // The optional accuracy argument has no meaning for an exact count
+            check_arity(merger, &name, &args, 1, 2, span)?;
+            let value = merger.to_value_expression(args.into_iter().next().unwrap())?;
+            aggregate(sast::AggregateFunction::Count, true, vec![value])
+        }
+        "percentile" => {
+            check_arity(merger, &name, &args, 2, 2, span)?;
+            let mut args = args.into_iter();
+            let value = merger.to_value_expression(args.next().unwrap())?;
+            let percentage = args.next().unwrap();
+            let fraction = match *percentage.value {
+                kast::Expression::Literal {
+                    value: kast::Literal::Int(Some(v)),
+                } => v as f64 / 100.0,
+                kast::Expression::Literal {
+                    value: kast::Literal::Long(Some(v)),
+                } => v as f64 / 100.0,
+                kast::Expression::Literal {
+                    value: kast::Literal::Real(Some(v)),
+                } => v / 100.0,
+                _ => {
+                    return Err(merger
+                        .invalid_argument(percentage.span, "percentage must be a number literal"))
+                }
+            };
+            Box::new(sast::ValueExpression::Aggregate {
+                func: sast::AggregateFunction::PercentileDisc,
+                distinct: false,
+                args: vec![Box::new(sast::ValueExpression::Literal {
+                    value: sast::Literal::Real(fraction),
+                })],
+                within_group: Some(sast::OrderByClause {
+                    specs: vec![sast::SortSpecification {
+                        value,
+                        order: sast::SortOrder::Ascending,
+                        nulls: None,
+                    }],
+                }),
+            })
+        }
+        "arg_max" | "arg_min" => {
+            return merger.not_yet_implemented(span, "arg_max and arg_min within an expression")
+        }
+        _ => return Err(merger.unknown_aggregate(name)),
+    };
+
+    Ok(aggregate)
+}
+
+fn aggregate(
+    func: sast::AggregateFunction,
+    distinct: bool,
+    args: Vec<Box<sast::ValueExpression>>,
+) -> Box<sast::ValueExpression> {
+    Box::new(sast::ValueExpression::Aggregate {
+        func,
+        distinct,
+        args,
+        within_group: None,
+    })
+}
+
+/// A single argument aggregation which maps directly onto a SQL function
+fn simple(
+    merger: &Merger,
+    func: sast::AggregateFunction,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, 1, 1, span)?;
+    let args = to_value_expressions(merger, args)?;
+    Ok(aggregate(func, false, args))
+}
+
+/// `CASE WHEN condition THEN value END`, which is NULL (and so skipped) otherwise
+fn conditional(
+    condition: Box<sast::SearchCondition>,
+    value: Box<sast::ValueExpression>,
+) -> Box<sast::ValueExpression> {
+    Box::new(sast::ValueExpression::Case {
+        branches: vec![sast::WhenClause {
+            condition,
+            result: value,
+        }],
+        else_: None,
+    })
+}
+
+fn to_value_expressions(
+    merger: &Merger,
+    args: Vec<MBox<kast::Expression>>,
+) -> Result<Vec<Box<sast::ValueExpression>>, ConverterError> {
+    args.into_iter()
+        .map(|arg| merger.to_value_expression(arg))
+        .collect()
+}
+
+pub fn check_arity(
+    merger: &Merger,
+    name: &M<String>,
+    args: &[MBox<kast::Expression>],
+    min: usize,
+    max: usize,
+    span: Span,
+) -> Result<(), ConverterError> {
+    if args.len() < min || args.len() > max {
+        Err(merger.wrong_argument_count(span, &name.value, min, max))
+    } else {
+        Ok(())
+    }
+}
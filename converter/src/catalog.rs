@@ -0,0 +1,213 @@
+use std::fmt;
+
+use miette::Diagnostic;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::ddl;
+
+/// The tables of a database and their columns, which the converter needs when a query
+/// depends on columns it does not name (e.g. aligning the columns of a union)
+pub trait Catalog {
+    /// The names of every table
+    fn table_names(&self) -> Vec<String>;
+
+    /// The columns of a table in order, or nothing when there is no such table
+    fn columns(&self, table: &str) -> Option<Vec<Column>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: ScalarType,
+}
+
+impl Column {
+    pub fn new(name: &str, data_type: ScalarType) -> Self {
+        Column {
+            name: name.to_string(),
+            data_type,
+        }
+    }
+}
+
+/// The KQL scalar data types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ScalarType {
+    Bool,
+    Int,
+    Long,
+    Real,
+    Decimal,
+    String,
+    DateTime,
+    Timespan,
+    Dynamic,
+    Guid,
+}
+
+impl ScalarType {
+    /// Looks up a type by its KQL name or one of its aliases (e.g. `double` for `real`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let data_type = match name {
+            "bool" | "boolean" => ScalarType::Bool,
+            "int" => ScalarType::Int,
+            "long" => ScalarType::Long,
+            "real" | "double" => ScalarType::Real,
+            "decimal" => ScalarType::Decimal,
+            "string" => ScalarType::String,
+            "datetime" | "date" => ScalarType::DateTime,
+            "timespan" | "time" => ScalarType::Timespan,
+            "dynamic" => ScalarType::Dynamic,
+            "guid" | "uuid" | "uniqueid" => ScalarType::Guid,
+            _ => return None,
+        };
+        Some(data_type)
+    }
+}
+
+impl TryFrom<String> for ScalarType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        ScalarType::from_name(&name).ok_or_else(|| format!("unknown data type '{}'", name))
+    }
+}
+
+impl fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarType::Bool => write!(f, "bool"),
+            ScalarType::Int => write!(f, "int"),
+            ScalarType::Long => write!(f, "long"),
+            ScalarType::Real => write!(f, "real"),
+            ScalarType::Decimal => write!(f, "decimal"),
+            ScalarType::String => write!(f, "string"),
+            ScalarType::DateTime => write!(f, "datetime"),
+            ScalarType::Timespan => write!(f, "timespan"),
+            ScalarType::Dynamic => write!(f, "dynamic"),
+            ScalarType::Guid => write!(f, "guid"),
+        }
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum CatalogError {
+    #[diagnostic(help(
+        "List the tables under `tables`, each with a `name` and `columns` of `name` and `type`"
+    ))]
+    #[error("Invalid JSON schema catalog: {0}")]
+    Json(#[from] serde_json::Error),
+    #[diagnostic(help(
+        "List the tables under `tables`, each with a `name` and `columns` of `name` and `type`"
+    ))]
+    #[error("Invalid YAML schema catalog: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[diagnostic(help(
+        "Provide CREATE TABLE statements, such as the output of SHOW CREATE TABLE"
+    ))]
+    #[error("Invalid SQL schema at line {line}: {message}")]
+    Ddl { line: usize, message: String },
+}
+
+/// A catalog held in memory, which can be loaded from JSON or YAML such as
+///
+/// ```yaml
+/// tables:
+///   - name: Events
+///     columns:
+///       - { name: Timestamp, type: datetime }
+///       - { name: Name, type: string }
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct MemoryCatalog {
+    tables: Vec<Table>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+}
+
+impl MemoryCatalog {
+    pub fn new() -> Self {
+        MemoryCatalog::default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, CatalogError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, CatalogError> {
+        Ok(serde_yaml::from_str(text)?)
+    }
+
+    /// Loads the tables of SQL `CREATE TABLE` statements, mapping their column types onto
+    /// KQL types (e.g. `BIGINT` to `long` and `JSON` to `dynamic`)
+    pub fn from_ddl(text: &str) -> Result<Self, CatalogError> {
+        let mut catalog = MemoryCatalog::new();
+        catalog.add_ddl(text)?;
+        Ok(catalog)
+    }
+
+    /// Adds the tables of SQL `CREATE TABLE` statements
+    pub fn add_ddl(&mut self, text: &str) -> Result<(), CatalogError> {
+        for (name, columns) in ddl::parse_tables(text)? {
+            self.add_table(&name, columns);
+        }
+        Ok(())
+    }
+
+    /// Adds a table, replacing any table with the same name
+    pub fn add_table(&mut self, name: &str, columns: Vec<Column>) {
+        match self.tables.iter_mut().find(|table| table.name == name) {
+            Some(table) => table.columns = columns,
+            None => self.tables.push(Table {
+                name: name.to_string(),
+                columns,
+            }),
+        }
+    }
+}
+
+impl Catalog for MemoryCatalog {
+    fn table_names(&self) -> Vec<String> {
+        self.tables.iter().map(|table| table.name.clone()).collect()
+    }
+
+    fn columns(&self, table: &str) -> Option<Vec<Column>> {
+        self.tables
+            .iter()
+            .find(|candidate| candidate.name == table)
+            .map(|table| table.columns.clone())
+    }
+}
+
+/// Whether a name matches a pattern in which `*` stands for any text (e.g. `Events*`)
+pub fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let middle: Vec<&str> = parts.collect();
+    let last = match middle.split_last() {
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(index) => rest = &rest[index + part.len()..],
+                    None => return false,
+                }
+            }
+            last
+        }
+        // no wildcard at all
+        None => return rest.is_empty(),
+    };
+    rest.ends_with(last)
+}
@@ -0,0 +1,270 @@
+use crate::catalog::{CatalogError, Column, ScalarType};
+
+/// Words which start a key or constraint rather than a column in a table definition
+const KEY_WORDS: &[&str] = &[
+    "CHECK",
+    "CONSTRAINT",
+    "FOREIGN",
+    "FULLTEXT",
+    "INDEX",
+    "KEY",
+    "PRIMARY",
+    "SHARD",
+    "SORT",
+    "SPATIAL",
+    "UNIQUE",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A keyword or an unquoted name
+    Word(String),
+    /// A name in backquotes or double quotes
+    Quoted(String),
+    /// A string in single quotes, which only appears in options
+    String,
+    Number(String),
+    Symbol(char),
+}
+
+/// Reads the tables of `CREATE TABLE` statements, such as the output of `SHOW CREATE TABLE`.
+/// Other statements are skipped.
+pub fn parse_tables(text: &str) -> Result<Vec<(String, Vec<Column>)>, CatalogError> {
+    let tokens = tokenize(text)?;
+    let mut tables = Vec::new();
+    for statement in tokens.split(|(token, _)| *token == Token::Symbol(';')) {
+        if let Some(table) = parse_create_table(statement)? {
+            tables.push(table);
+        }
+    }
+    Ok(tables)
+}
+
+/// Tokens paired with their line numbers
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, CatalogError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let start = line;
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => skip_line(&mut chars, &mut line),
+            '-' if chars.peek() == Some(&'-') => skip_line(&mut chars, &mut line),
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            previous = c;
+                        }
+                        None => return Err(ddl_error(start, "unterminated comment")),
+                    }
+                }
+            }
+            '`' | '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        // a doubled quote stands for the quote itself
+                        Some(quote) if quote == c && chars.peek() == Some(&c) => {
+                            chars.next();
+                            value.push(c);
+                        }
+                        Some(quote) if quote == c => break,
+                        Some('\\') if c == '\'' => {
+                            chars.next();
+                        }
+                        Some(other) => {
+                            if other == '\n' {
+                                line += 1;
+                            }
+                            value.push(other);
+                        }
+                        None => return Err(ddl_error(start, "unterminated quote")),
+                    }
+                }
+                let token = if c == '\'' {
+                    Token::String
+                } else {
+                    Token::Quoted(value)
+                };
+                tokens.push((token, start));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let mut word = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_' || next == '$') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                let token = if c.is_ascii_digit() {
+                    Token::Number(word)
+                } else {
+                    Token::Word(word)
+                };
+                tokens.push((token, start));
+            }
+            c => tokens.push((Token::Symbol(c), start)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn skip_line(chars: &mut impl Iterator<Item = char>, line: &mut usize) {
+    if chars.any(|c| c == '\n') {
+        *line += 1;
+    }
+}
+
+fn ddl_error(line: usize, message: &str) -> CatalogError {
+    CatalogError::Ddl {
+        line,
+        message: message.to_string(),
+    }
+}
+
+fn is_word(token: Option<&(Token, usize)>, keyword: &str) -> bool {
+    matches!(token, Some((Token::Word(word), _)) if word.eq_ignore_ascii_case(keyword))
+}
+
+fn name(token: Option<&(Token, usize)>) -> Option<String> {
+    match token {
+        Some((Token::Word(name) | Token::Quoted(name), _)) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Parses `CREATE [modifiers] TABLE [IF NOT EXISTS] [db.]name (definitions) [options]`
+fn parse_create_table(
+    statement: &[(Token, usize)],
+) -> Result<Option<(String, Vec<Column>)>, CatalogError> {
+    if !is_word(statement.first(), "CREATE") {
+        return Ok(None);
+    }
+    // modifiers such as ROWSTORE, REFERENCE or TEMPORARY come before TABLE
+    let table_index = statement
+        .iter()
+        .take_while(|(token, _)| matches!(token, Token::Word(_)))
+        .position(
+            |(token, _)| matches!(token, Token::Word(word) if word.eq_ignore_ascii_case("TABLE")),
+        );
+    let mut tokens = match table_index {
+        Some(index) => statement[index + 1..].iter().peekable(),
+        None => return Ok(None),
+    };
+    let line = statement[0].1;
+
+    if is_word(tokens.peek().copied(), "IF") {
+        tokens.next();
+        tokens.next();
+        tokens.next();
+    }
+    let mut table = name(tokens.next()).ok_or_else(|| ddl_error(line, "expected a table name"))?;
+    while tokens.peek().map(|(token, _)| token) == Some(&Token::Symbol('.')) {
+        tokens.next();
+        table = name(tokens.next()).ok_or_else(|| ddl_error(line, "expected a table name"))?;
+    }
+    if tokens.next().map(|(token, _)| token) != Some(&Token::Symbol('(')) {
+        let message = format!("expected the column definitions of '{}'", table);
+        return Err(ddl_error(line, &message));
+    }
+
+    // split the definitions at the commas between them
+    let mut definitions = vec![Vec::new()];
+    let mut depth = 0;
+    for token in tokens.by_ref() {
+        match token.0 {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') if depth == 0 => break,
+            Token::Symbol(')') => depth -= 1,
+            Token::Symbol(',') if depth == 0 => {
+                definitions.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        definitions.last_mut().unwrap().push(token);
+    }
+
+    let mut columns = Vec::new();
+    for definition in definitions {
+        if let Some(column) = parse_column(&definition, line)? {
+            columns.push(column);
+        }
+    }
+    Ok(Some((table, columns)))
+}
+
+/// Parses `name type [(size)] [attributes]`, or `name AS expression PERSISTED type`
+/// for a computed column. Keys and constraints are skipped.
+fn parse_column(
+    definition: &[&(Token, usize)],
+    line: usize,
+) -> Result<Option<Column>, CatalogError> {
+    let column = match definition.first() {
+        Some((Token::Word(word), _)) if KEY_WORDS.contains(&word.to_uppercase().as_str()) => {
+            return Ok(None)
+        }
+        Some(token) => {
+            name(Some(token)).ok_or_else(|| ddl_error(token.1, "expected a column name"))?
+        }
+        None => return Err(ddl_error(line, "empty column definition")),
+    };
+
+    let mut rest = definition[1..].iter().copied();
+    let mut data_type = rest.next();
+    if is_word(data_type, "AS") {
+        data_type = rest
+            .find(|token| is_word(Some(*token), "PERSISTED"))
+            .and(rest.next());
+    }
+    let (type_name, line) = match data_type {
+        Some((Token::Word(type_name), line)) => (type_name.to_lowercase(), *line),
+        _ => {
+            let message = format!("expected the type of column '{}'", column);
+            return Err(ddl_error(line, &message));
+        }
+    };
+    let size = match (rest.next(), rest.next()) {
+        (Some((Token::Symbol('('), _)), Some((Token::Number(size), _))) => Some(size.as_str()),
+        _ => None,
+    };
+
+    match to_scalar_type(&type_name, size) {
+        Some(data_type) => Ok(Some(Column::new(&column, data_type))),
+        None => {
+            let message = format!("unsupported type '{}' of column '{}'", type_name, column);
+            Err(ddl_error(line, &message))
+        }
+    }
+}
+
+/// The KQL type of a SQL column type, with its size if it has one
+fn to_scalar_type(type_name: &str, size: Option<&str>) -> Option<ScalarType> {
+    let data_type = match (type_name, size) {
+        // BOOL columns are shown as TINYINT(1)
+        ("bool" | "boolean", _) | ("tinyint", Some("1")) => ScalarType::Bool,
+        ("tinyint" | "smallint" | "mediumint" | "int" | "integer", _) => ScalarType::Int,
+        ("bigint" | "bit", _) => ScalarType::Long,
+        ("float" | "double" | "real", _) => ScalarType::Real,
+        ("decimal" | "dec" | "numeric" | "fixed", _) => ScalarType::Decimal,
+        ("char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext", _)
+        | ("binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob", _)
+        | ("enum" | "set" | "geography" | "geographypoint", _) => ScalarType::String,
+        ("date" | "datetime" | "timestamp", _) => ScalarType::DateTime,
+        ("time", _) => ScalarType::Timespan,
+        ("json", _) => ScalarType::Dynamic,
+        _ => return None,
+    };
+    Some(data_type)
+}
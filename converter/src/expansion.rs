@@ -0,0 +1,551 @@
+use std::{collections::HashMap, rc::Rc, sync::Arc};
+
+use kql_parser::{
+    ast as kast,
+    spans::{MBox, Span, M},
+};
+use miette::NamedSource;
+
+use crate::{merger::Merger, ConverterError};
+
+/// Names bound by let statements and function parameters
+#[derive(Clone, Default)]
+pub struct Bindings {
+    /// Scalar values, which are inlined wherever their names are used
+    scalars: HashMap<String, MBox<kast::Expression>>,
+    /// Tables, along with the names they are read by in SQL
+    tables: HashMap<String, String>,
+    /// User-defined functions, which are expanded wherever they are called
+    functions: HashMap<String, Rc<Closure>>,
+}
+
+/// A function along with the names bound where it was defined
+struct Closure {
+    function: kast::Function,
+    bindings: Bindings,
+}
+
+impl Bindings {
+    /// Forgets a name before it is rebound
+    fn unbind(&mut self, name: &str) {
+        self.scalars.remove(name);
+        self.tables.remove(name);
+        self.functions.remove(name);
+    }
+}
+
+/// Expands let-bound names and user-defined functions, leaving queries which only read
+/// tables and call built-in functions.
+///
+/// Expansion is hygienic: arguments are expanded where the function is called, then
+/// substituted for the parameters all at once without being expanded again, so the
+/// caller's columns are never captured by the function's parameters or local lets.
+/// Tables defined inside functions, including the input of `invoke`, get fresh names.
+pub struct Expander {
+    src: Arc<NamedSource>,
+    /// Tables defined by tabular lets, in the order they must be defined
+    pub tables: Vec<(String, kast::Query)>,
+    /// The number of fresh table names handed out
+    fresh_names: usize,
+}
+
+impl Expander {
+    pub fn new(src: Arc<NamedSource>) -> Self {
+        Expander {
+            src,
+            tables: vec![],
+            fresh_names: 0,
+        }
+    }
+
+    /// Binds the value of a let statement. Tables defined by `local` lets, those inside
+    /// function bodies, get fresh names.
+    pub fn bind(
+        &mut self,
+        bindings: &mut Bindings,
+        name: M<String>,
+        value: kast::LetValue,
+        local: bool,
+    ) -> Result<(), ConverterError> {
+        let query = match value {
+            kast::LetValue::Scalar(expr) => {
+                if let Some(table) = table_name(bindings, &expr) {
+                    // an alias of another table
+                    bindings.unbind(&name.value);
+                    bindings.tables.insert(name.value, table.value);
+                    return Ok(());
+                }
+                let expr = self.expression(bindings, expr)?;
+                bindings.unbind(&name.value);
+                bindings.scalars.insert(name.value, expr);
+                return Ok(());
+            }
+            kast::LetValue::Tabular(query) => self.query(bindings, query)?,
+            kast::LetValue::Function(function) => {
+                let closure = Closure {
+                    function,
+                    bindings: bindings.clone(),
+                };
+                bindings.unbind(&name.value);
+                bindings.functions.insert(name.value, Rc::new(closure));
+                return Ok(());
+            }
+        };
+
+        let table = if local {
+            self.fresh_name(&name.value)
+        } else {
+            name.value.clone()
+        };
+        bindings.unbind(&name.value);
+        bindings.tables.insert(name.value, table.clone());
+        self.tables.push((table, query));
+        Ok(())
+    }
+
+    pub fn query(
+        &mut self,
+        bindings: &Bindings,
+        query: kast::Query,
+    ) -> Result<kast::Query, ConverterError> {
+        let source = match query.source {
+            kast::Source::Table(table) => kast::Source::Table(rename_table(bindings, table)),
+            kast::Source::Union {
+                union_kwd,
+                params,
+                tables,
+            } => kast::Source::Union {
+                union_kwd,
+                params,
+                tables: self.queries(bindings, tables)?,
+            },
+        };
+        let mut result = kast::Query {
+            source,
+            operators: vec![],
+        };
+
+        for (name, operator) in query.operators {
+            match operator {
+                kast::TabularOperator::Invoke {
+                    name: function,
+                    args,
+                } => result = self.invoke(bindings, result, function, args)?,
+                operator => {
+                    let operator = self.operator(bindings, operator)?;
+                    result.operators.push((name, operator));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn queries(
+        &mut self,
+        bindings: &Bindings,
+        queries: Vec<kast::Query>,
+    ) -> Result<Vec<kast::Query>, ConverterError> {
+        queries
+            .into_iter()
+            .map(|query| self.query(bindings, query))
+            .collect()
+    }
+
+    fn operator(
+        &mut self,
+        bindings: &Bindings,
+        operator: kast::TabularOperator,
+    ) -> Result<kast::TabularOperator, ConverterError> {
+        let operator = match operator {
+            kast::TabularOperator::Extend { columns } => kast::TabularOperator::Extend {
+                columns: self.columns(bindings, columns)?,
+            },
+            kast::TabularOperator::Join {
+                params,
+                right_table,
+                attributes,
+            } => kast::TabularOperator::Join {
+                params,
+                right_table: Box::new(self.query(bindings, *right_table)?),
+                attributes,
+            },
+            kast::TabularOperator::Project { columns } => kast::TabularOperator::Project {
+                columns: self.columns(bindings, columns)?,
+            },
+            kast::TabularOperator::Summarize {
+                result_columns,
+                by_kwd,
+                grouping_columns,
+            } => kast::TabularOperator::Summarize {
+                result_columns: self.columns(bindings, result_columns)?,
+                by_kwd,
+                grouping_columns: self.columns(bindings, grouping_columns)?,
+            },
+            kast::TabularOperator::Top {
+                limit,
+                by_kwd,
+                expr,
+                order,
+                nulls,
+            } => kast::TabularOperator::Top {
+                limit,
+                by_kwd,
+                expr: self.expression(bindings, expr)?,
+                order,
+                nulls,
+            },
+            kast::TabularOperator::Union { params, tables } => kast::TabularOperator::Union {
+                params,
+                tables: self.queries(bindings, tables)?,
+            },
+            kast::TabularOperator::Where { expr } => kast::TabularOperator::Where {
+                expr: self.expression(bindings, expr)?,
+            },
+            kast::TabularOperator::Count
+            | kast::TabularOperator::Distinct { .. }
+            | kast::TabularOperator::Invoke { .. }
+            | kast::TabularOperator::Limit { .. }
+            | kast::TabularOperator::ProjectAway { .. }
+            | kast::TabularOperator::ProjectKeep { .. }
+            | kast::TabularOperator::ProjectRename { .. }
+            | kast::TabularOperator::ProjectReorder { .. }
+            | kast::TabularOperator::Sort { .. } => operator,
+        };
+        Ok(operator)
+    }
+
+    /// A column named after a scalar without an expression (e.g. `project threshold`)
+    /// takes its value from the scalar
+    fn columns(
+        &mut self,
+        bindings: &Bindings,
+        columns: Vec<kast::ColumnDefinition>,
+    ) -> Result<Vec<kast::ColumnDefinition>, ConverterError> {
+        columns
+            .into_iter()
+            .map(|column| {
+                let expr = match column.expr {
+                    Some(expr) => Some(self.expression(bindings, expr)?),
+                    None => bindings.scalars.get(&column.column.value).cloned(),
+                };
+                Ok(kast::ColumnDefinition {
+                    column: column.column,
+                    expr,
+                })
+            })
+            .collect()
+    }
+
+    pub fn expression(
+        &mut self,
+        bindings: &Bindings,
+        expr: MBox<kast::Expression>,
+    ) -> Result<MBox<kast::Expression>, ConverterError> {
+        let span = expr.span;
+        let value = match *expr.value {
+            kast::Expression::Identifier { name } => match bindings.scalars.get(&name.value) {
+                Some(value) => return Ok(value.clone()),
+                None => kast::Expression::Identifier { name },
+            },
+            kast::Expression::FuncCall {
+                name,
+                open_paren_sym,
+                args,
+                close_paren_sym,
+            } => match bindings.functions.get(&name.value).cloned() {
+                Some(closure) => return self.call(bindings, &closure, name, args, span),
+                None => kast::Expression::FuncCall {
+                    name,
+                    open_paren_sym,
+                    args: self.expressions(bindings, args)?,
+                    close_paren_sym,
+                },
+            },
+            kast::Expression::BinaryOp { left, op, right } => kast::Expression::BinaryOp {
+                left: self.expression(bindings, left)?,
+                op,
+                right: self.expression(bindings, right)?,
+            },
+            kast::Expression::UnaryOp { op, expr } => kast::Expression::UnaryOp {
+                op,
+                expr: self.expression(bindings, expr)?,
+            },
+            kast::Expression::List {
+                open_paren_sym,
+                items,
+                close_paren_sym,
+            } => kast::Expression::List {
+                open_paren_sym,
+                items: self.expressions(bindings, items)?,
+                close_paren_sym,
+            },
+            kast::Expression::Range {
+                start,
+                dot_dot_sym,
+                end,
+            } => kast::Expression::Range {
+                start: self.expression(bindings, start)?,
+                dot_dot_sym,
+                end: self.expression(bindings, end)?,
+            },
+            leaf @ (kast::Expression::Literal { .. } | kast::Expression::Wildcard) => leaf,
+        };
+        Ok(MBox::new(value, span))
+    }
+
+    fn expressions(
+        &mut self,
+        bindings: &Bindings,
+        exprs: Vec<MBox<kast::Expression>>,
+    ) -> Result<Vec<MBox<kast::Expression>>, ConverterError> {
+        exprs
+            .into_iter()
+            .map(|expr| self.expression(bindings, expr))
+            .collect()
+    }
+
+    /// Expands a call to a scalar function
+    fn call(
+        &mut self,
+        bindings: &Bindings,
+        closure: &Closure,
+        name: M<String>,
+        args: Vec<MBox<kast::Expression>>,
+        span: Span,
+    ) -> Result<MBox<kast::Expression>, ConverterError> {
+        let params = &closure.function.params;
+        let mut body_bindings =
+            self.bind_arguments(bindings, closure, params, args, &name, span)?;
+        match self.bind_body(&mut body_bindings, &closure.function.body)? {
+            kast::LetValue::Scalar(expr) => self.expression(&body_bindings, expr),
+            _ => Err(self.wrong_function_kind(name, "scalar")),
+        }
+    }
+
+    /// Expands `input | invoke name(args)`, where the input is the first argument
+    fn invoke(
+        &mut self,
+        bindings: &Bindings,
+        input: kast::Query,
+        name: M<String>,
+        args: Vec<MBox<kast::Expression>>,
+    ) -> Result<kast::Query, ConverterError> {
+        let closure = match bindings.functions.get(&name.value).cloned() {
+            Some(closure) => closure,
+            None => {
+                let known: Vec<&str> = bindings.functions.keys().map(String::as_str).collect();
+                return Err(ConverterError::UnknownFunction {
+                    src: self.src.clone(),
+                    help: Merger::suggest(&name.value, &known, "function"),
+                    span: name.span,
+                    name: name.value,
+                });
+            }
+        };
+        let (input_param, params) = match closure.function.params.split_first() {
+            Some((
+                param @ kast::Parameter {
+                    kind: kast::ParameterKind::Tabular { .. },
+                    ..
+                },
+                params,
+            )) => (param, params),
+            _ => {
+                return Err(ConverterError::InvalidArgument {
+                    src: self.src.clone(),
+                    span: name.span,
+                    message: String::from(
+                        "invoke needs a function whose first parameter is a table",
+                    ),
+                })
+            }
+        };
+
+        let span = name.span.clone();
+        let mut body_bindings =
+            self.bind_arguments(bindings, &closure, params, args, &name, span)?;
+
+        // the input is defined before the body, whose local tables may read it
+        let table = self.fresh_name(&input_param.name.value);
+        let input_index = self.tables.len();
+        self.tables.push((table.clone(), input));
+        body_bindings.unbind(&input_param.name.value);
+        body_bindings
+            .tables
+            .insert(input_param.name.value.clone(), table.clone());
+
+        let body = match self.bind_body(&mut body_bindings, &closure.function.body)? {
+            kast::LetValue::Tabular(query) => self.query(&body_bindings, query)?,
+            kast::LetValue::Scalar(expr) => match table_name(&body_bindings, &expr) {
+                Some(table) => kast::Query {
+                    source: kast::Source::Table(table),
+                    operators: vec![],
+                },
+                None => return Err(self.wrong_function_kind(name, "tabular")),
+            },
+            kast::LetValue::Function(_) => return Err(self.wrong_function_kind(name, "tabular")),
+        };
+
+        // when the body only pipes the input through operators, they are applied to it
+        // directly rather than to a common table expression
+        let reads_input = |query: &kast::Query| reads_table(query, &table);
+        let pipes_input = matches!(body.table(), Some(source) if source.value == table)
+            && !body
+                .operators
+                .iter()
+                .any(|(_, operator)| operator_reads_table(operator, &table))
+            && !self.tables[input_index + 1..]
+                .iter()
+                .any(|(_, query)| reads_input(query));
+        if pipes_input {
+            let (_, mut input) = self.tables.remove(input_index);
+            input.operators.extend(body.operators);
+            Ok(input)
+        } else {
+            Ok(body)
+        }
+    }
+
+    /// Binds the parameters of a function to the arguments of a call, or their defaults
+    fn bind_arguments(
+        &mut self,
+        bindings: &Bindings,
+        closure: &Closure,
+        params: &[kast::Parameter],
+        args: Vec<MBox<kast::Expression>>,
+        name: &M<String>,
+        span: Span,
+    ) -> Result<Bindings, ConverterError> {
+        let required = params
+            .iter()
+            .filter(|param| param.default.is_none())
+            .count();
+        if args.len() < required || args.len() > params.len() {
+            let expected = if required == params.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, params.len())
+            };
+            return Err(ConverterError::WrongArgumentCount {
+                src: self.src.clone(),
+                span,
+                name: name.value.clone(),
+                expected,
+            });
+        }
+
+        let mut body_bindings = closure.bindings.clone();
+        let mut args = args.into_iter();
+        for param in params {
+            // defaults are expanded where the function was defined
+            let (arg, scope) = match (args.next(), &param.default) {
+                (Some(arg), _) => (arg, bindings),
+                (None, Some(default)) => (default.clone(), &closure.bindings),
+                (None, None) => return Err(self.missing_argument(name, param)),
+            };
+
+            let param_name = param.name.value.clone();
+            match &param.kind {
+                kast::ParameterKind::Scalar { .. } => {
+                    let value = self.expression(scope, arg)?;
+                    body_bindings.unbind(&param_name);
+                    body_bindings.scalars.insert(param_name, value);
+                }
+                kast::ParameterKind::Tabular { .. } => {
+                    let table = match table_name(scope, &arg) {
+                        Some(table) => table,
+                        None => {
+                            return Err(ConverterError::InvalidArgument {
+                                src: self.src.clone(),
+                                span: arg.span,
+                                message: format!("'{}' must be a table name", param_name),
+                            })
+                        }
+                    };
+                    body_bindings.unbind(&param_name);
+                    body_bindings.tables.insert(param_name, table.value);
+                }
+            }
+        }
+
+        Ok(body_bindings)
+    }
+
+    /// Binds the local lets of a function body, returning the value of the body
+    fn bind_body(
+        &mut self,
+        bindings: &mut Bindings,
+        body: &kast::FunctionBody,
+    ) -> Result<kast::LetValue, ConverterError> {
+        for statement in body.statements.iter().cloned() {
+            if let kast::Statement::Let { name, value, .. } = statement {
+                self.bind(bindings, name, value, true)?;
+            }
+        }
+        Ok(*body.value.clone())
+    }
+
+    /// A name for a table which cannot clash with the tables of the database
+    fn fresh_name(&mut self, name: &str) -> String {
+        self.fresh_names += 1;
+        format!("__{}_{}", name, self.fresh_names)
+    }
+
+    fn wrong_function_kind(&self, name: M<String>, expected: &str) -> ConverterError {
+        ConverterError::WrongFunctionKind {
+            src: self.src.clone(),
+            span: name.span,
+            name: name.value,
+            expected: expected.to_string(),
+        }
+    }
+
+    fn missing_argument(&self, name: &M<String>, param: &kast::Parameter) -> ConverterError {
+        ConverterError::InvalidArgument {
+            src: self.src.clone(),
+            span: name.span.clone(),
+            message: format!("missing a value for '{}'", param.name.value),
+        }
+    }
+}
+
+/// The table named by an expression, which is any name not bound to a scalar
+fn table_name(bindings: &Bindings, expr: &MBox<kast::Expression>) -> Option<M<String>> {
+    match &*expr.value {
+        kast::Expression::Identifier { name } if !bindings.scalars.contains_key(&name.value) => {
+            Some(rename_table(bindings, name.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn rename_table(bindings: &Bindings, table: M<String>) -> M<String> {
+    match bindings.tables.get(&table.value) {
+        Some(name) => M::new(name.clone(), table.span),
+        None => table,
+    }
+}
+
+fn reads_table(query: &kast::Query, table: &str) -> bool {
+    let source_reads_table = match &query.source {
+        kast::Source::Table(source) => source.value == table,
+        kast::Source::Union { tables, .. } => tables.iter().any(|query| reads_table(query, table)),
+    };
+    source_reads_table
+        || query
+            .operators
+            .iter()
+            .any(|(_, operator)| operator_reads_table(operator, table))
+}
+
+/// Whether an operator reads another table, as joins and unions do
+fn operator_reads_table(operator: &kast::TabularOperator, table: &str) -> bool {
+    match operator {
+        kast::TabularOperator::Join { right_table, .. } => reads_table(right_table, table),
+        kast::TabularOperator::Union { tables, .. } => {
+            tables.iter().any(|query| reads_table(query, table))
+        }
+        _ => false,
+    }
+}
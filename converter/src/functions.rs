@@ -0,0 +1,402 @@
+use kql_parser::{
+    ast as kast,
+    spans::{MBox, Span, M},
+};
+use sql_emitter::ast as sast;
+
+use crate::{aggregates::check_arity, catalog::ScalarType, merger::Merger, typing, ConverterError};
+
+/// Converts an argument, either as a plain value or as part of an aggregation
+pub type Convert =
+    fn(&Merger, MBox<kast::Expression>) -> Result<Box<sast::ValueExpression>, ConverterError>;
+
+/// The KQL scalar functions which can be translated to SQL
+pub const FUNCTIONS: &[&str] = &[
+    "abs",
+    "ago",
+    "bin",
+    "case",
+    "ceiling",
+    "coalesce",
+    "exp",
+    "extract",
+    "floor",
+    "iff",
+    "iif",
+    "indexof",
+    "isempty",
+    "isnotempty",
+    "isnotnull",
+    "isnull",
+    "log",
+    "log10",
+    "max_of",
+    "min_of",
+    "now",
+    "parse_json",
+    "pow",
+    "replace_string",
+    "reverse",
+    "round",
+    "sign",
+    "split",
+    "sqrt",
+    "strcat",
+    "strlen",
+    "substring",
+    "tobool",
+    "toboolean",
+    "todatetime",
+    "todecimal",
+    "todouble",
+    "todynamic",
+    "toint",
+    "tolong",
+    "tolower",
+    "toreal",
+    "tostring",
+    "toupper",
+];
+
+pub fn is_scalar_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+/// Translates a call to a KQL scalar function into its SQL equivalent.
+pub fn to_scalar_function(
+    merger: &Merger,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    let function = match name.value.as_str() {
+        "strlen" => rename(merger, "CHAR_LENGTH", name, args, (1, 1), span, convert)?,
+        "tolower" => rename(merger, "LOWER", name, args, (1, 1), span, convert)?,
+        "toupper" => rename(merger, "UPPER", name, args, (1, 1), span, convert)?,
+        "reverse" => rename(merger, "REVERSE", name, args, (1, 1), span, convert)?,
+        "strcat" => rename(merger, "CONCAT", name, args, (1, 64), span, convert)?,
+        "replace_string" => rename(merger, "REPLACE", name, args, (3, 3), span, convert)?,
+        "abs" => rename(merger, "ABS", name, args, (1, 1), span, convert)?,
+        "ceiling" => rename(merger, "CEILING", name, args, (1, 1), span, convert)?,
+        "exp" => rename(merger, "EXP", name, args, (1, 1), span, convert)?,
+        "log" => rename(merger, "LN", name, args, (1, 1), span, convert)?,
+        "log10" => rename(merger, "LOG10", name, args, (1, 1), span, convert)?,
+        "pow" => rename(merger, "POWER", name, args, (2, 2), span, convert)?,
+        "round" => rename(merger, "ROUND", name, args, (1, 2), span, convert)?,
+        "sign" => rename(merger, "SIGN", name, args, (1, 1), span, convert)?,
+        "sqrt" => rename(merger, "SQRT", name, args, (1, 1), span, convert)?,
+        "max_of" => rename(merger, "GREATEST", name, args, (2, 64), span, convert)?,
+        "min_of" => rename(merger, "LEAST", name, args, (2, 64), span, convert)?,
+        "toint" => to_integer(merger, ScalarType::Int, name, args, span, convert)?,
+        "tolong" => to_integer(merger, ScalarType::Long, name, args, span, convert)?,
+        "toreal" | "todouble" => cast(merger, ScalarType::Real, name, args, span, convert)?,
+        "todecimal" => cast(merger, ScalarType::Decimal, name, args, span, convert)?,
+        "tostring" => cast(merger, ScalarType::String, name, args, span, convert)?,
+        "tobool" | "toboolean" => cast(merger, ScalarType::Bool, name, args, span, convert)?,
+        "todatetime" => cast(merger, ScalarType::DateTime, name, args, span, convert)?,
+        "parse_json" | "todynamic" => cast(merger, ScalarType::Dynamic, name, args, span, convert)?,
+        "substring" => {
+            // SQL strings are indexed from 1 rather than 0
+            check_arity(merger, &name, &args, 2, 3, span)?;
+            let mut args = args.into_iter();
+            let mut sql_args = vec![convert(merger, args.next().unwrap())?];
+            sql_args.push(plus_one(merger, args.next().unwrap(), convert)?);
+            if let Some(length) = args.next() {
+                sql_args.push(convert(merger, length)?);
+            }
+            func("SUBSTRING", sql_args)
+        }
+        "indexof" => {
+            // LOCATE is 1-based and returns 0 when not found, where indexof returns -1
+            check_arity(merger, &name, &args, 2, 2, span)?;
+            let mut args = args.into_iter();
+            let source = convert(merger, args.next().unwrap())?;
+            let lookup = convert(merger, args.next().unwrap())?;
+            Box::new(sast::ValueExpression::ArithmeticExpr {
+                left: func("LOCATE", vec![lookup, source]),
+                op: sast::ArithmeticOperator::Sub,
+                right: integer(1),
+            })
+        }
+        "split" => {
+            check_arity(merger, &name, &args, 2, 3, span)?;
+            if args.len() == 3 {
+                return merger.not_yet_implemented(span, "split with an index");
+            }
+            let args = to_value_expressions(merger, args, convert)?;
+            func("SPLIT", args)
+        }
+        "extract" => {
+            // extract(regex, captureGroup, source) only supports the whole match
+            check_arity(merger, &name, &args, 3, 3, span)?;
+            let mut args = args.into_iter();
+            let regex = convert(merger, args.next().unwrap())?;
+            let group = args.next().unwrap();
+            match *group.value {
+                kast::Expression::Literal {
+                    value: kast::Literal::Int(Some(0)) | kast::Literal::Long(Some(0)),
+                } => {}
+                _ => return merger.not_yet_implemented(group.span, "extract of a capture group"),
+            }
+            let source = convert(merger, args.next().unwrap())?;
+            func("REGEXP_SUBSTR", vec![source, regex])
+        }
+        "bin" | "floor" => to_bin(merger, name, args, span, convert)?,
+        "ago" | "now" => to_relative_time(merger, name, args, span, convert)?,
+        "coalesce" => to_coalesce(merger, name, args, span, convert)?,
+        "iff" | "iif" | "case" => merger.to_case(name, args, span)?,
+        "isnull" | "isnotnull" | "isempty" | "isnotempty" => {
+            let condition = merger.to_null_check(name, args, span)?;
+            Box::new(sast::ValueExpression::Condition { condition })
+        }
+        _ => return Err(merger.unknown_function(name)),
+    };
+
+    Ok(function)
+}
+
+/// A function which maps directly onto a SQL function with the same arguments
+fn rename(
+    merger: &Merger,
+    sql_name: &str,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    (min, max): (usize, usize),
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, min, max, span)?;
+    let args = to_value_expressions(merger, args, convert)?;
+    Ok(func(sql_name, args))
+}
+
+/// A conversion function, `CAST(value AS data_type)`, which leaves values that
+/// already have the type unchanged
+fn cast(
+    merger: &Merger,
+    data_type: ScalarType,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, 1, 1, span)?;
+    let arg = args.into_iter().next().unwrap();
+    if merger.type_of(&arg) == Some(data_type) {
+        return convert(merger, arg);
+    }
+    let value = convert(merger, arg)?;
+    Ok(Box::new(sast::ValueExpression::Cast {
+        value,
+        data_type: typing::to_data_type(data_type),
+    }))
+}
+
+/// `toint` and `tolong` truncate fractions, where a cast may round them
+fn to_integer(
+    merger: &Merger,
+    data_type: ScalarType,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, 1, 1, span)?;
+    let arg = args.into_iter().next().unwrap();
+    let value = match merger.type_of(&arg) {
+        Some(found) if found == data_type => return convert(merger, arg),
+        None | Some(ScalarType::Real) | Some(ScalarType::Decimal) => {
+            func("TRUNCATE", vec![convert(merger, arg)?, integer(0)])
+        }
+        Some(_) => convert(merger, arg)?,
+    };
+    Ok(Box::new(sast::ValueExpression::Cast {
+        value,
+        data_type: typing::to_data_type(data_type),
+    }))
+}
+
+/// `bin(value, size)` rounds down to a multiple of size: `FLOOR(value / size) * size`.
+/// Datetimes are rounded as seconds since the epoch.
+fn to_bin(
+    merger: &Merger,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, 2, 2, span)?;
+    let mut args = args.into_iter();
+    let value = args.next().unwrap();
+    let size = args.next().unwrap();
+    if let kast::Expression::Literal {
+        value: kast::Literal::Timespan(ticks),
+    } = &*size.value
+    {
+        let seconds = match ticks {
+            Some(ticks) if ticks % 10_000_000 == 0 => ticks / 10_000_000,
+            _ => return merger.not_yet_implemented(span, "bin with a sub-second timespan size"),
+        };
+        match merger.type_of(&value) {
+            None | Some(ScalarType::DateTime) => {}
+            Some(ScalarType::Timespan) => {
+                return merger.not_yet_implemented(span, "bin of a timespan");
+            }
+            Some(found) => return Err(merger.unexpected_type(value.span, "datetime", found)),
+        }
+        let epoch = func("UNIX_TIMESTAMP", vec![convert(merger, value)?]);
+        let floor = floor_to_multiple(epoch, integer(seconds), integer(seconds));
+        return Ok(func("FROM_UNIXTIME", vec![floor]));
+    }
+
+    let value = convert(merger, value)?;
+    let divisor = convert(merger, size.clone())?;
+    Ok(floor_to_multiple(value, divisor, convert(merger, size)?))
+}
+
+/// `FLOOR(value / size) * size`, given the size twice
+fn floor_to_multiple(
+    value: Box<sast::ValueExpression>,
+    size: Box<sast::ValueExpression>,
+    multiplier: Box<sast::ValueExpression>,
+) -> Box<sast::ValueExpression> {
+    let floor = func(
+        "FLOOR",
+        vec![Box::new(sast::ValueExpression::ArithmeticExpr {
+            left: value,
+            op: sast::ArithmeticOperator::Div,
+            right: size,
+        })],
+    );
+    Box::new(sast::ValueExpression::ArithmeticExpr {
+        left: floor,
+        op: sast::ArithmeticOperator::Mul,
+        right: multiplier,
+    })
+}
+
+/// `ago(offset)` is `CURRENT_TIMESTAMP - offset` and `now([offset])` is
+/// `CURRENT_TIMESTAMP + offset`
+fn to_relative_time(
+    merger: &Merger,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    let (min, op) = match name.value.as_str() {
+        "ago" => (1, sast::ArithmeticOperator::Sub),
+        _ => (0, sast::ArithmeticOperator::Add),
+    };
+    check_arity(merger, &name, &args, min, 1, span)?;
+
+    let now = Box::new(sast::ValueExpression::CurrentTimestamp);
+    match args.into_iter().next() {
+        Some(offset) => Ok(Box::new(sast::ValueExpression::ArithmeticExpr {
+            left: now,
+            op,
+            right: convert(merger, offset)?,
+        })),
+        None => Ok(now),
+    }
+}
+
+/// `coalesce(a, b, ...)` takes the first value which is not NULL, or for strings
+/// the first which is not empty either
+fn to_coalesce(
+    merger: &Merger,
+    name: M<String>,
+    args: Vec<MBox<kast::Expression>>,
+    span: Span,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    check_arity(merger, &name, &args, 2, 64, span)?;
+    let strings = args
+        .iter()
+        .any(|arg| merger.type_of(arg) == Some(ScalarType::String));
+    let args = args
+        .into_iter()
+        .map(|arg| {
+            let may_be_empty = match &*arg.value {
+                kast::Expression::Literal {
+                    value: kast::Literal::String(text),
+                } => text.is_empty(),
+                _ => matches!(merger.type_of(&arg), None | Some(ScalarType::String)),
+            };
+            let value = convert(merger, arg)?;
+            if strings && may_be_empty {
+                Ok(func("NULLIF", vec![value, string("")]))
+            } else {
+                Ok(value)
+            }
+        })
+        .collect::<Result<Vec<Box<sast::ValueExpression>>, ConverterError>>()?;
+    Ok(func("COALESCE", args))
+}
+
+/// Shifts a 0-based start index to a 1-based one, folding integer literals.
+/// Negative literals are rejected, and other negative values start at the beginning.
+fn plus_one(
+    merger: &Merger,
+    index: MBox<kast::Expression>,
+    convert: Convert,
+) -> Result<Box<sast::ValueExpression>, ConverterError> {
+    match integer_literal(&index.value) {
+        Some(v) if v < 0 => {
+            Err(merger.invalid_argument(index.span, "the index cannot be negative"))
+        }
+        Some(v) => match v.checked_add(1) {
+            Some(v) => Ok(integer(v)),
+            None => Err(merger.invalid_argument(index.span, "the index is too large")),
+        },
+        None => Ok(Box::new(sast::ValueExpression::ArithmeticExpr {
+            left: func("GREATEST", vec![convert(merger, index)?, integer(0)]),
+            op: sast::ArithmeticOperator::Add,
+            right: integer(1),
+        })),
+    }
+}
+
+/// The value of an integer literal, which may be negated
+fn integer_literal(expr: &kast::Expression) -> Option<i64> {
+    match expr {
+        kast::Expression::Literal {
+            value: kast::Literal::Int(Some(v)),
+        } => Some(*v as i64),
+        kast::Expression::Literal {
+            value: kast::Literal::Long(Some(v)),
+        } => Some(*v),
+        kast::Expression::UnaryOp { op, expr } if op.value == kast::UnaryOp::Neg => {
+            integer_literal(&expr.value)?.checked_neg()
+        }
+        _ => None,
+    }
+}
+
+fn func(name: &str, args: Vec<Box<sast::ValueExpression>>) -> Box<sast::ValueExpression> {
+    Box::new(sast::ValueExpression::FuncCall {
+        name: String::from(name),
+        args,
+    })
+}
+
+fn integer(value: i64) -> Box<sast::ValueExpression> {
+    Box::new(sast::ValueExpression::Literal {
+        value: sast::Literal::Integer(value),
+    })
+}
+
+fn string(value: &str) -> Box<sast::ValueExpression> {
+    Box::new(sast::ValueExpression::Literal {
+        value: sast::Literal::String(String::from(value)),
+    })
+}
+
+fn to_value_expressions(
+    merger: &Merger,
+    args: Vec<MBox<kast::Expression>>,
+    convert: Convert,
+) -> Result<Vec<Box<sast::ValueExpression>>, ConverterError> {
+    args.into_iter().map(|arg| convert(merger, arg)).collect()
+}
@@ -9,10 +9,52 @@ use std::{fmt::Write, sync::Arc};
 use miette::{Diagnostic, NamedSource};
 use thiserror::Error;
 
+/// KQL aggregation function catalog
+mod aggregates;
+/// Schema catalogs describing the tables of a database
+pub mod catalog;
+/// Parsing of SQL table definitions into schema catalogs
+mod ddl;
+/// Expansion of let-bound names and user-defined functions
+mod expansion;
+/// KQL scalar function catalog
+mod functions;
 /// AST to AST conversion code
 mod merger;
+/// Type inference of KQL expressions
+mod typing;
+/// Checks of the tables and columns a query reads
+mod validation;
 
-pub fn kql_to_sql(source_name: String, kql: String) -> Result<String, String> {
+pub use catalog::{Catalog, CatalogError, Column, MemoryCatalog, ScalarType};
+pub use sql_emitter::{
+    dialect_by_name, Ansi, Dialect, FunctionSyntax, PostgreSql, SingleStore, Sqlite, Strict,
+};
+
+pub fn kql_to_sql(
+    source_name: String,
+    kql: String,
+    dialect: &dyn Dialect,
+) -> Result<String, String> {
+    translate(source_name, kql, None, dialect)
+}
+
+/// Translates with the help of a catalog of the tables which the query reads
+pub fn kql_to_sql_with_catalog(
+    source_name: String,
+    kql: String,
+    catalog: Arc<dyn Catalog>,
+    dialect: &dyn Dialect,
+) -> Result<String, String> {
+    translate(source_name, kql, Some(catalog), dialect)
+}
+
+fn translate(
+    source_name: String,
+    kql: String,
+    catalog: Option<Arc<dyn Catalog>>,
+    dialect: &dyn Dialect,
+) -> Result<String, String> {
     let src = Arc::new(NamedSource::new(source_name, kql.clone()));
 
     let kql_ast = match parse(src.clone(), kql) {
@@ -24,35 +66,128 @@ pub fn kql_to_sql(source_name: String, kql: String) -> Result<String, String> {
             }
             return Err(output);
         }
-        Err(KqlError::Parser { error }) => {
-            return Err(format!("{:?}", Report::new(error)));
+        Err(KqlError::Parser { errors, .. }) => {
+            let mut output = String::new();
+            for error in errors {
+                write!(output, "{:?}\n\n", Report::new(error)).unwrap();
+            }
+            return Err(output);
         }
     };
 
-    let sql_ast = match convert(src, kql_ast) {
+    let sql_ast = match convert(src, kql_ast, catalog) {
         Ok(result) => result,
         Err(error) => return Err(format!("{:?}", Report::new(error))),
     };
 
-    emit(&sql_ast)
+    emit(&sql_ast, dialect)
 }
 
+/// Converts the last query of a script. Scalar lets and user-defined functions are
+/// expanded where they are used, and tabular lets become common table expressions.
+/// With a catalog, the tables read must exist and `*` is expanded where columns must be
+/// listed. Columns are checked wherever the input columns are known, and expressions
+/// wherever the types of the columns they read are known.
 pub fn convert(
     src: Arc<NamedSource>,
-    query: kast::Query,
+    script: kast::Script,
+    catalog: Option<Arc<dyn Catalog>>,
 ) -> Result<sast::SelectStatement, ConverterError> {
-    let mut merger = merger::Merger::new(src);
-    let mut head = sast::SelectStatement::simple(query.table.value);
+    let mut expander = expansion::Expander::new(src.clone());
+    let mut bindings = expansion::Bindings::default();
+    let mut result = None;
+    let mut last_span = Span::from((0, 0));
+
+    for statement in script.statements {
+        match statement {
+            kast::Statement::Let { name, value, .. } => {
+                last_span = name.span.clone();
+                expander.bind(&mut bindings, name, value, false)?;
+            }
+            kast::Statement::Query(query) => {
+                if result.is_some() {
+                    return Err(ConverterError::NotImplemented {
+                        src,
+                        span: query.source_span(),
+                        feature: String::from("Multiple queries"),
+                    });
+                }
+                last_span = query.source_span();
+                result = Some(expander.query(&bindings, query)?);
+            }
+        }
+    }
+
+    let query = match result {
+        Some(query) => query,
+        None => {
+            return Err(ConverterError::MissingQuery {
+                src,
+                span: last_span,
+            })
+        }
+    };
+    if let Some(catalog) = &catalog {
+        // let statements define tables in order, for the queries which follow
+        let mut known = catalog.table_names();
+        for (name, table_query) in &expander.tables {
+            validation::check_tables(&src, &known, table_query)?;
+            known.push(name.clone());
+        }
+        validation::check_tables(&src, &known, &query)?;
+    }
+
+    let (mut head, _) = convert_query(src.clone(), catalog.clone(), query)?;
+    head.with = expander
+        .tables
+        .into_iter()
+        .map(|(name, query)| {
+            Ok(sast::CommonTableExpression {
+                name,
+                query: convert_query(src.clone(), catalog.clone(), query)?.0,
+            })
+        })
+        .collect::<Result<Vec<sast::CommonTableExpression>, ConverterError>>()?;
+
+    Ok(head)
+}
+
+/// Converts a query, returning the known types of the columns it produces
+fn convert_query(
+    src: Arc<NamedSource>,
+    catalog: Option<Arc<dyn Catalog>>,
+    query: kast::Query,
+) -> Result<(sast::SelectStatement, typing::Types), ConverterError> {
+    let mut merger = merger::Merger::new(src, catalog);
+    let (mut merger, mut head) = match query.source {
+        kast::Source::Table(table) => {
+            merger.read_table(&table.value);
+            (merger, sast::SelectStatement::simple(table.value))
+        }
+        kast::Source::Union {
+            union_kwd,
+            params,
+            tables,
+        } => merger.merge_union(None, union_kwd, params, tables)?,
+    };
 
     for (name, operator) in query.operators {
         (merger, head) = merger.merge_operator(head, name, operator)?;
     }
 
-    Ok(head)
+    Ok((head, merger.types().clone()))
 }
 
 #[derive(Error, Debug, Diagnostic)]
 pub enum ConverterError {
+    #[diagnostic()]
+    #[error("Expected an aggregation function")]
+    ExpectedAggregate {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Not an aggregation")]
+        span: Span,
+    },
     #[diagnostic()]
     #[error("Expression cannot be interpreted as a condition")]
     ExpressionNotCondition {
@@ -61,6 +196,93 @@ pub enum ConverterError {
         #[label("Non-condition expression")]
         span: Span,
     },
+    #[diagnostic(help("Use project to list the columns of both tables before joining"))]
+    #[error("Cannot determine the columns of the {side} side of the join")]
+    UnknownJoinColumns {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Columns of this table are unknown")]
+        span: Span,
+        side: String,
+    },
+    #[diagnostic(help(
+        "Use project to list the columns of every table, or provide a schema catalog"
+    ))]
+    #[error("Cannot determine the columns of a table in the union")]
+    UnknownUnionColumns {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Columns of this table are unknown")]
+        span: Span,
+    },
+    #[diagnostic(help("Use project to list the columns first, or provide a schema catalog"))]
+    #[error("Cannot determine the input columns of {operator}")]
+    UnknownInputColumns {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Columns before this operator are unknown")]
+        span: Span,
+        operator: String,
+    },
+    #[diagnostic(help("{help}"))]
+    #[error("Cannot resolve the wildcard '{pattern}'")]
+    UnresolvedWildcard {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Wildcard")]
+        span: Span,
+        pattern: String,
+        help: String,
+    },
+    #[diagnostic(help("{help}"))]
+    #[error("Cannot apply '{operator}' to {left} and {right}")]
+    InvalidOperandTypes {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Incompatible operand types")]
+        span: Span,
+        operator: String,
+        left: String,
+        right: String,
+        help: String,
+    },
+    #[diagnostic(help("Convert the value with a function such as tobool, tolong or tostring"))]
+    #[error("Expected a {expected} value, found {found}")]
+    UnexpectedType {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Wrong type")]
+        span: Span,
+        expected: String,
+        found: String,
+    },
+    #[diagnostic(help(
+        "Lists only follow in, has_any and has_all, and ranges only follow between"
+    ))]
+    #[error("List or range used as a value")]
+    MisplacedList {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Not a value")]
+        span: Span,
+    },
+    #[diagnostic(help("End the script with a query, such as `Events | take 10`"))]
+    #[error("No query to convert")]
+    MissingQuery {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Expected a query after this")]
+        span: Span,
+    },
+    #[diagnostic()]
+    #[error("Invalid argument: {message}")]
+    InvalidArgument {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Here")]
+        span: Span,
+        message: String,
+    },
     #[diagnostic()]
     #[error("{feature} not yet implemented")]
     NotImplemented {
@@ -70,4 +292,66 @@ pub enum ConverterError {
         span: Span,
         feature: String,
     },
+    #[diagnostic(help("{help}"))]
+    #[error("Unknown aggregation function '{name}'")]
+    UnknownAggregate {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("No SQL equivalent for this aggregation")]
+        span: Span,
+        name: String,
+        help: String,
+    },
+    #[diagnostic(help("{help}"))]
+    #[error("Unknown column '{name}'")]
+    UnknownColumn {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Not an input column")]
+        span: Span,
+        name: String,
+        help: String,
+    },
+    #[diagnostic(help("{help}"))]
+    #[error("Unknown table '{name}'")]
+    UnknownTable {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Not in the schema catalog")]
+        span: Span,
+        name: String,
+        help: String,
+    },
+    #[diagnostic(help("{help}"))]
+    #[error("Unknown function '{name}'")]
+    UnknownFunction {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("No SQL equivalent for this function")]
+        span: Span,
+        name: String,
+        help: String,
+    },
+    #[diagnostic(help(
+        "Scalar functions are called in expressions, and tabular functions with invoke"
+    ))]
+    #[error("Function '{name}' does not return a {expected} value")]
+    WrongFunctionKind {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Called here")]
+        span: Span,
+        name: String,
+        expected: String,
+    },
+    #[diagnostic()]
+    #[error("Function '{name}' expects {expected} arguments")]
+    WrongArgumentCount {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Wrong number of arguments")]
+        span: Span,
+        name: String,
+        expected: String,
+    },
 }
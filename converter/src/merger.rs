@@ -1,6 +1,7 @@
 use kql_parser::{
     ast::{self as kast, ColumnDefinition},
     spans::{MBox, Span, M},
+    suggestions::closest,
 };
 use sql_emitter::ast as sast;
 
@@ -8,12 +9,30 @@ use std::sync::Arc;
 
 use miette::NamedSource;
 
-use crate::ConverterError;
+use crate::{
+    aggregates,
+    catalog::{matches_wildcard, Catalog, ScalarType},
+    functions,
+    typing::{self, Types},
+    validation, ConverterError,
+};
+
+/// Name of the helper column used to pick rows for `arg_max`, `arg_min`, and `innerunique` joins
+const ROW_NUMBER_COLUMN: &str = "__row_number";
+/// Aliases of the two tables of a join
+const LEFT_TABLE: &str = "__left";
+const RIGHT_TABLE: &str = "__right";
+/// Alias of the tables of a union, which is required of derived tables
+const UNION_TABLE: &str = "__union";
+/// Name of the column produced by the `count` operator
+const COUNT_COLUMN: &str = "Count";
 
-#[derive(Debug)]
 pub struct Merger {
     src: Arc<NamedSource>,
+    catalog: Option<Arc<dyn Catalog>>,
     columns: ColumnsState,
+    /// The known types of the current columns
+    types: Types,
 }
 
 #[derive(PartialEq, Default, Debug)]
@@ -30,13 +49,38 @@ enum ColumnsState {
 }
 
 impl Merger {
-    pub fn new(src: Arc<NamedSource>) -> Self {
+    pub fn new(src: Arc<NamedSource>, catalog: Option<Arc<dyn Catalog>>) -> Self {
         Merger {
             src,
+            catalog,
             columns: ColumnsState::Unmodified,
+            types: Types::new(),
         }
     }
 
+    /// Starts from a table, whose column types come from the catalog
+    pub fn read_table(&mut self, table: &str) {
+        let columns = self
+            .catalog
+            .as_ref()
+            .and_then(|catalog| catalog.columns(table))
+            .unwrap_or_default();
+        self.types = columns
+            .into_iter()
+            .map(|column| (column.name, column.data_type))
+            .collect();
+    }
+
+    pub fn types(&self) -> &Types {
+        &self.types
+    }
+
+    /// The type of an expression over the current columns, if known.
+    /// Type errors have already been reported when the operator was checked.
+    pub fn type_of(&self, expr: &MBox<kast::Expression>) -> Option<ScalarType> {
+        typing::infer(self, &self.types, expr).ok().flatten()
+    }
+
     fn reset_columns(&mut self) {
         self.columns = ColumnsState::default();
     }
@@ -48,7 +92,7 @@ impl Merger {
         }
     }
 
-    fn not_yet_implemented<T>(&self, span: Span, feature: &str) -> Result<T, ConverterError> {
+    pub fn not_yet_implemented<T>(&self, span: Span, feature: &str) -> Result<T, ConverterError> {
         Err(ConverterError::NotImplemented {
             src: self.src.clone(),
             feature: feature.to_string(),
@@ -56,19 +100,247 @@ impl Merger {
         })
     }
 
+    fn expected_aggregate(&self, span: Span) -> ConverterError {
+        ConverterError::ExpectedAggregate {
+            src: self.src.clone(),
+            span,
+        }
+    }
+
+    pub fn unknown_aggregate(&self, name: M<String>) -> ConverterError {
+        ConverterError::UnknownAggregate {
+            src: self.src.clone(),
+            help: Merger::suggest(&name.value, aggregates::AGGREGATES, "aggregation"),
+            span: name.span,
+            name: name.value,
+        }
+    }
+
+    pub fn unknown_function(&self, name: M<String>) -> ConverterError {
+        ConverterError::UnknownFunction {
+            src: self.src.clone(),
+            help: Merger::suggest(&name.value, functions::FUNCTIONS, "function"),
+            span: name.span,
+            name: name.value,
+        }
+    }
+
+    /// Help text naming the known function closest to a misspelled one
+    pub fn suggest(name: &str, known: &[&str], kind: &str) -> String {
+        match closest(name, known.iter().copied()) {
+            Some(known) => format!("did you mean '{}'?", known),
+            None => format!("no {} with a similar name can be translated", kind),
+        }
+    }
+
+    pub fn unknown_column(&self, name: M<String>, input: &[String]) -> ConverterError {
+        let help = match closest(&name.value, input.iter().map(String::as_str)) {
+            Some(known) => format!("did you mean '{}'?", known),
+            None => format!("the input columns are {}", input.join(", ")),
+        };
+        ConverterError::UnknownColumn {
+            src: self.src.clone(),
+            span: name.span,
+            name: name.value,
+            help,
+        }
+    }
+
+    fn unknown_join_columns(&self, span: Span, side: &str) -> ConverterError {
+        ConverterError::UnknownJoinColumns {
+            src: self.src.clone(),
+            span,
+            side: side.to_string(),
+        }
+    }
+
+    fn unknown_union_columns(&self, span: Span) -> ConverterError {
+        ConverterError::UnknownUnionColumns {
+            src: self.src.clone(),
+            span,
+        }
+    }
+
+    fn unresolved_wildcard(&self, pattern: M<String>, help: &str) -> ConverterError {
+        ConverterError::UnresolvedWildcard {
+            src: self.src.clone(),
+            span: pattern.span,
+            pattern: pattern.value,
+            help: help.to_string(),
+        }
+    }
+
+    fn unknown_input_columns(&self, operator: M<String>) -> ConverterError {
+        ConverterError::UnknownInputColumns {
+            src: self.src.clone(),
+            span: operator.span,
+            operator: operator.value,
+        }
+    }
+
+    pub fn invalid_operand_types(
+        &self,
+        span: Span,
+        operator: &str,
+        left: ScalarType,
+        right: ScalarType,
+    ) -> ConverterError {
+        let help = match (operator, left, right) {
+            ("+", ScalarType::String, ScalarType::String) => "Use strcat to concatenate strings",
+            _ => "Convert the operands with functions such as tolong, todouble or todatetime",
+        };
+        ConverterError::InvalidOperandTypes {
+            src: self.src.clone(),
+            span,
+            operator: operator.to_string(),
+            left: left.to_string(),
+            right: right.to_string(),
+            help: help.to_string(),
+        }
+    }
+
+    pub fn unexpected_type(&self, span: Span, expected: &str, found: ScalarType) -> ConverterError {
+        ConverterError::UnexpectedType {
+            src: self.src.clone(),
+            span,
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
+
+    fn misplaced_wildcard(&self, span: Span) -> ConverterError {
+        self.invalid_argument(span, "'*' may only be passed to arg_max and arg_min")
+    }
+
+    fn misplaced_list(&self, span: Span) -> ConverterError {
+        ConverterError::MisplacedList {
+            src: self.src.clone(),
+            span,
+        }
+    }
+
+    pub fn invalid_argument(&self, span: Span, message: &str) -> ConverterError {
+        ConverterError::InvalidArgument {
+            src: self.src.clone(),
+            span,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn wrong_argument_count(
+        &self,
+        span: Span,
+        name: &str,
+        min: usize,
+        max: usize,
+    ) -> ConverterError {
+        let expected = if min == max {
+            min.to_string()
+        } else {
+            format!("{} to {}", min, max)
+        };
+        ConverterError::WrongArgumentCount {
+            src: self.src.clone(),
+            span,
+            name: name.to_string(),
+            expected,
+        }
+    }
+
     /// Takes a SELECT statement and either modifies it to include the provided operator
     /// or creates a new SELECT statement wrapping the old one which does.
     pub fn merge_operator(
+        self,
+        head: sast::SelectStatement,
+        name: M<String>,
+        operator: kast::TabularOperator,
+    ) -> Result<(Self, sast::SelectStatement), ConverterError> {
+        let input = self.known_columns(&head);
+        if let Some(input) = &input {
+            validation::check_operator(&self, input, &operator)?;
+        }
+        let output_types = typing::output_types(&self, &self.types, &operator)?;
+
+        let (mut merger, head) = self.merge_checked_operator(head, input, name, operator)?;
+        if let Some(types) = output_types {
+            merger.types = types;
+        }
+        Ok((merger, head))
+    }
+
+    fn merge_checked_operator(
         mut self,
         mut head: sast::SelectStatement,
+        input: Option<Vec<String>>,
         name: M<String>,
         operator: kast::TabularOperator,
     ) -> Result<(Self, sast::SelectStatement), ConverterError> {
+        // Checked by merge_operator, which also computes the output types
         match operator {
-            kast::TabularOperator::Count => self.not_yet_implemented(name.span, "count operator"),
+            kast::TabularOperator::Count => {
+                if self.needs_wrapping_for_aggregation(&head) {
+                    head = sast::SelectStatement::simple_wrapping(head);
+                }
+
+                head.select = sast::SelectList {
+                    wildcard: false,
+                    columns: vec![sast::SelectColumn {
+                        value: Box::new(sast::ValueExpression::Aggregate {
+                            func: sast::AggregateFunction::Count,
+                            distinct: false,
+                            args: vec![],
+                            within_group: None,
+                        }),
+                        alias: Some(COUNT_COLUMN.to_string()),
+                    }],
+                };
+                self.columns = ColumnsState::Modified {
+                    retained: vec![],
+                    modified: vec![COUNT_COLUMN.to_string()],
+                };
+                Ok((self, head))
+            }
 
             kast::TabularOperator::Distinct { columns } => {
-                self.not_yet_implemented(name.span, "distinct operator")
+                let needs_wrapping = head.modifier.is_some()
+                    || head.group_by.is_some()
+                    || head.order_by.is_some()
+                    || head.limit.is_some();
+
+                match columns {
+                    kast::Columns::Wildcard(_) => {
+                        // The new columns of a modified head are computed from its source
+                        // row alone, so they never affect which rows are distinct
+                        if needs_wrapping {
+                            self.columns = ColumnsState::Unmodified;
+                            head = sast::SelectStatement::simple_wrapping(head);
+                        }
+                    }
+                    kast::Columns::Explicit(names) => {
+                        if needs_wrapping || matches!(self.columns, ColumnsState::Modified { .. }) {
+                            head = sast::SelectStatement::simple_wrapping(head);
+                        }
+
+                        self.columns = ColumnsState::Limited {
+                            columns: names.iter().map(|name| name.value.clone()).collect(),
+                        };
+                        head.select = sast::SelectList {
+                            wildcard: false,
+                            columns: names
+                                .into_iter()
+                                .map(|name| sast::SelectColumn {
+                                    value: Box::new(sast::ValueExpression::Column {
+                                        name: name.value,
+                                    }),
+                                    alias: None,
+                                })
+                                .collect(),
+                        };
+                    }
+                }
+
+                head.modifier = Some(sast::Modifier::Distinct);
+                Ok((self, head))
             }
 
             kast::TabularOperator::Extend { columns } => {
@@ -78,21 +350,55 @@ impl Merger {
 
                 self.columns = Merger::get_column_state(&columns);
 
-                let sql_columns: Vec<sast::SelectColumn> = columns
+                let names: Vec<String> =
+                    columns.iter().map(|col| col.column.value.clone()).collect();
+                let mut sql_columns: Vec<Option<sast::SelectColumn>> = columns
                     .into_iter()
-                    .map(|col| self.to_select_column(col))
-                    .collect::<Result<Vec<sast::SelectColumn>, ConverterError>>()?;
+                    .map(|col| self.to_select_column(col).map(Some))
+                    .collect::<Result<Vec<Option<sast::SelectColumn>>, ConverterError>>()?;
 
-                head.select.columns.extend(sql_columns);
+                // A new column replacing an input column takes its place, so the wildcard
+                // is expanded rather than selecting the same name twice
+                match input {
+                    Some(input)
+                        if head.select.wildcard
+                            && head.select.columns.is_empty()
+                            && names.iter().any(|name| input.contains(name)) =>
+                    {
+                        let mut select: Vec<sast::SelectColumn> = input
+                            .iter()
+                            .map(
+                                |column| match names.iter().rposition(|name| name == column) {
+                                    Some(index) => sql_columns[index].take().unwrap(),
+                                    None => Merger::column(column),
+                                },
+                            )
+                            .collect();
+                        select.extend(sql_columns.into_iter().flatten());
+                        head.select = sast::SelectList {
+                            wildcard: false,
+                            columns: select,
+                        };
+                    }
+                    _ => head
+                        .select
+                        .columns
+                        .extend(sql_columns.into_iter().flatten()),
+                }
 
                 Ok((self, head))
             }
 
+            // functions are expanded before merging
+            kast::TabularOperator::Invoke { name, .. } => {
+                self.not_yet_implemented(name.span, "invoke of an unexpanded function")
+            }
+
             kast::TabularOperator::Join {
                 params,
                 right_table,
                 attributes,
-            } => self.not_yet_implemented(name.span, "join operator"),
+            } => self.merge_join(head, name, params, right_table, attributes),
 
             kast::TabularOperator::Limit { limit } => {
                 if head.limit.is_some() {
@@ -112,7 +418,7 @@ impl Merger {
                     .map(|col| self.to_select_column(col))
                     .collect::<Result<Vec<sast::SelectColumn>, ConverterError>>()?;
 
-                if self.columns == ColumnsState::Unmodified {
+                if self.columns == ColumnsState::Unmodified && head.modifier.is_none() {
                     // Update head
                     head.select.wildcard = false;
                     head.select.columns.extend(new_columns);
@@ -130,27 +436,185 @@ impl Merger {
                     Ok((self, new_head))
                 }
             }
+            kast::TabularOperator::ProjectAway { columns } => {
+                let input = self.input_columns(&head, name, columns.iter())?;
+                let mut removed = Vec::new();
+                for pattern in &columns {
+                    removed.extend(Merger::matching_columns(&input, &pattern.value));
+                }
+                let kept = input
+                    .into_iter()
+                    .filter(|column| !removed.contains(column))
+                    .map(|column| (column, None));
+                Ok(self.select_input_columns(head, kept.collect()))
+            }
+
+            kast::TabularOperator::ProjectKeep { columns } => {
+                let input = self.input_columns(&head, name, columns.iter())?;
+                let mut retained = Vec::new();
+                for pattern in &columns {
+                    retained.extend(Merger::matching_columns(&input, &pattern.value));
+                }
+                let kept = input
+                    .into_iter()
+                    .filter(|column| retained.contains(column))
+                    .map(|column| (column, None));
+                Ok(self.select_input_columns(head, kept.collect()))
+            }
+
+            kast::TabularOperator::ProjectRename { columns } => {
+                let input = self.input_columns(&head, name, std::iter::empty())?;
+                let renamed = input
+                    .into_iter()
+                    .map(|column| {
+                        let new_name = columns
+                            .iter()
+                            .find(|(_, existing)| existing.value == column)
+                            .map(|(new_name, _)| new_name.value.clone());
+                        (column, new_name)
+                    })
+                    .collect();
+                Ok(self.select_input_columns(head, renamed))
+            }
+
+            kast::TabularOperator::ProjectReorder { columns } => {
+                let patterns = columns.iter().map(|(pattern, _)| pattern);
+                let input = self.input_columns(&head, name, patterns)?;
+                let mut reordered: Vec<String> = Vec::new();
+                for (pattern, order) in &columns {
+                    let mut matches: Vec<String> = Merger::matching_columns(&input, &pattern.value)
+                        .filter(|column| !reordered.contains(column))
+                        .collect();
+                    match order.as_ref().map(|order| &order.value) {
+                        Some(kast::SortOrder::Ascending) => matches.sort(),
+                        Some(kast::SortOrder::Descending) => matches.sort_by(|a, b| b.cmp(a)),
+                        None => {}
+                    }
+                    reordered.extend(matches);
+                }
+                let rest: Vec<String> = input
+                    .into_iter()
+                    .filter(|column| !reordered.contains(column))
+                    .collect();
+                reordered.extend(rest);
+                let reordered = reordered.into_iter().map(|column| (column, None));
+                Ok(self.select_input_columns(head, reordered.collect()))
+            }
+
             kast::TabularOperator::Sort { by_kwd, sortings } => {
-                self.not_yet_implemented(name.span, "sort operator")
+                let order_by = sast::OrderByClause {
+                    specs: sortings
+                        .into_iter()
+                        .map(|sorting| {
+                            let value = Box::new(sast::ValueExpression::Column {
+                                name: sorting.column.value,
+                            });
+                            Merger::to_sort_specification(value, sorting.order, sorting.nulls)
+                        })
+                        .collect(),
+                };
+
+                let needs_wrapping = head.limit.is_some()
+                    || head.order_by.is_some()
+                    || if let ColumnsState::Modified { modified, .. } = &self.columns {
+                        order_by.depends_on_any(modified)
+                    } else {
+                        false
+                    };
+
+                if needs_wrapping {
+                    self.columns = ColumnsState::Unmodified;
+                    head = sast::SelectStatement::simple_wrapping(head);
+                }
+
+                head.order_by = Some(order_by);
+                Ok((self, head))
+            }
+
+            kast::TabularOperator::Union { params, tables } => {
+                self.merge_union(Some(head), name.span, params, tables)
             }
 
             kast::TabularOperator::Summarize {
                 result_columns,
                 by_kwd,
                 grouping_columns,
-            } => self.not_yet_implemented(name.span, "summarize operator"),
+            } => {
+                if self.needs_wrapping_for_aggregation(&head) {
+                    head = sast::SelectStatement::simple_wrapping(head);
+                }
+
+                let arg_extremum = result_columns.iter().position(|col| {
+                    matches!(
+                        col.expr.as_ref().map(|expr| &*expr.value),
+                        Some(kast::Expression::FuncCall { name, .. })
+                            if aggregates::is_arg_extremum(&name.value)
+                    )
+                });
+                if let Some(index) = arg_extremum {
+                    return self.merge_arg_extremum(head, result_columns, index, grouping_columns);
+                }
+
+                let group_by = self.to_group_by(&grouping_columns)?;
+                let mut columns = grouping_columns
+                    .iter()
+                    .map(|col| self.to_select_column(col.clone()))
+                    .collect::<Result<Vec<sast::SelectColumn>, ConverterError>>()?;
+                for col in result_columns.iter() {
+                    columns.push(self.to_aggregate_column(col.clone())?);
+                }
+
+                self.columns = Merger::get_summarize_column_state(
+                    &grouping_columns,
+                    result_columns.iter().map(|col| col.column.value.clone()),
+                );
+
+                head.select = sast::SelectList {
+                    wildcard: false,
+                    columns,
+                };
+                head.group_by = group_by;
+                Ok((self, head))
+            }
 
             kast::TabularOperator::Top {
                 limit,
-                by_kwd,
                 expr,
                 order,
                 nulls,
-            } => self.not_yet_implemented(name.span, "top operator"),
+                ..
+            } => {
+                let order_by = sast::OrderByClause {
+                    specs: vec![Merger::to_sort_specification(
+                        self.to_value_expression(expr)?,
+                        order,
+                        nulls,
+                    )],
+                };
+
+                let needs_wrapping = head.limit.is_some()
+                    || head.order_by.is_some()
+                    || if let ColumnsState::Modified { modified, .. } = &self.columns {
+                        order_by.depends_on_any(modified)
+                    } else {
+                        false
+                    };
+
+                if needs_wrapping {
+                    self.columns = ColumnsState::Unmodified;
+                    head = sast::SelectStatement::simple_wrapping(head);
+                }
+
+                head.order_by = Some(order_by);
+                head.limit = Some(limit.value);
+                Ok((self, head))
+            }
 
             kast::TabularOperator::Where { expr } => {
                 let cond = self.to_search_condition(expr)?;
+                // Filtering must happen after any earlier limit has been applied
                 let needs_wrapping = head.where_.is_some()
+                    || head.limit.is_some()
                     || if let ColumnsState::Modified { modified, .. } = &self.columns {
                         cond.depends_on_any(&modified)
                     } else {
@@ -168,111 +632,1107 @@ impl Merger {
         }
     }
 
-    fn to_select_column(
-        &self,
-        column: kast::ColumnDefinition,
-    ) -> Result<sast::SelectColumn, ConverterError> {
-        let name = column.column.value;
+    /// Translates `summarize arg_max(ExprToMaximize, Col1, ...) by Group` by numbering the
+    /// rows of each group from the extremum and keeping the first row of every group.
+    fn merge_arg_extremum(
+        mut self,
+        mut head: sast::SelectStatement,
+        result_columns: Vec<kast::ColumnDefinition>,
+        index: usize,
+        grouping_columns: Vec<kast::ColumnDefinition>,
+    ) -> Result<(Self, sast::SelectStatement), ConverterError> {
+        if let Some(other) = result_columns
+            .iter()
+            .enumerate()
+            .find(|(i, _)| *i != index)
+            .map(|(_, col)| col)
+        {
+            return self.not_yet_implemented(
+                other.column.span.clone(),
+                "arg_max and arg_min combined with other aggregations",
+            );
+        }
 
-        let def = match column.expr {
-            Some(k_expr) => sast::SelectColumn {
-                value: self.to_value_expression(k_expr)?,
-                alias: Some(name),
-            },
-            None => sast::SelectColumn {
-                value: Box::new(sast::ValueExpression::Column { name }),
-                alias: None,
-            },
+        let column = result_columns.into_iter().nth(index).unwrap();
+        let expr = column.expr.unwrap();
+        let (name, args) = match *expr.value {
+            kast::Expression::FuncCall { name, args, .. } => (name, args),
+            _ => unreachable!(),
+        };
+        if args.len() < 2 {
+            return Err(self.wrong_argument_count(expr.span, &name.value, 2, usize::MAX));
+        }
+        let order = if name.value == "arg_max" {
+            sast::SortOrder::Descending
+        } else {
+            sast::SortOrder::Ascending
         };
 
-        Ok(def)
-    }
-
-    pub fn to_value_expression(
-        &self,
-        expr: MBox<kast::Expression>,
-    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
-        let value = match *expr.value {
-            kast::Expression::Identifier { name } => {
-                sast::ValueExpression::Column { name: name.value }
-            }
-            kast::Expression::FuncCall {
-                name,
-                open_paren_sym,
-                args,
-                close_paren_sym,
-            } => {
-                let name = name.value;
-                let result: Result<Vec<Box<sast::ValueExpression>>, ConverterError> = args
-                    .into_iter()
-                    .map(|arg| self.to_value_expression(arg))
-                    .collect();
-                sast::ValueExpression::FuncCall {
-                    name,
-                    args: result?,
-                }
-            }
-            kast::Expression::BinaryOp { left, op, right } => {
-                let left = self.to_value_expression(left)?;
-                let right = self.to_value_expression(right)?;
-                match op.value {
-                    kast::BinaryOp::Add
-                    | kast::BinaryOp::Sub
-                    | kast::BinaryOp::Mul
-                    | kast::BinaryOp::Div => sast::ValueExpression::ArithmeticExpr {
-                        left,
-                        op: match op.value {
-                            kast::BinaryOp::Add => sast::ArithmeticOperator::Add,
-                            kast::BinaryOp::Sub => sast::ArithmeticOperator::Sub,
-                            kast::BinaryOp::Mul => sast::ArithmeticOperator::Mul,
-                            kast::BinaryOp::Div => sast::ArithmeticOperator::Div,
-                            _ => unreachable!(),
-                        },
-                        right,
-                    },
-                    kast::BinaryOp::Mod => todo!(),
-                    kast::BinaryOp::LogicalAnd => todo!(),
-                    kast::BinaryOp::LogicalOr => todo!(),
-                    kast::BinaryOp::LT => todo!(),
-                    kast::BinaryOp::GT => todo!(),
-                    kast::BinaryOp::EQ => todo!(),
-                    kast::BinaryOp::NEQ => todo!(),
-                    kast::BinaryOp::LTE => todo!(),
-                    kast::BinaryOp::GTE => todo!(),
+        let mut args = args.into_iter();
+        let extremum = args.next().unwrap();
+        let mut returned = Vec::new();
+        for arg in args {
+            match *arg.value {
+                kast::Expression::Identifier { name } => returned.push(name.value),
+                // the remaining input columns, after those the aggregation already returns
+                kast::Expression::Wildcard => {
+                    let pattern = M::new("*".to_string(), arg.span);
+                    for input in self.input_columns(&head, name.clone(), [&pattern].into_iter())? {
+                        let taken = returned.contains(&input)
+                            || input == column.column.value
+                            || grouping_columns.iter().any(|col| col.column.value == input);
+                        if !taken {
+                            returned.push(input);
+                        }
+                    }
                 }
+                _ => return Err(self.invalid_argument(arg.span, "expected a column name")),
             }
-            kast::Expression::Literal { value } => {
-                let literal = match value {
-                    kast::Literal::Bool(Some(v)) => sast::Literal::Bool(v),
-                    kast::Literal::Int(Some(v)) => sast::Literal::Integer(v as i64),
-                    kast::Literal::Long(Some(v)) => sast::Literal::Integer(v),
-                    kast::Literal::Real(Some(v)) => sast::Literal::Real(v),
-                    kast::Literal::String(v) => sast::Literal::String(v),
-                    _ => todo!(),
-                };
-                sast::ValueExpression::Literal { value: literal }
+        }
+
+        // Number the rows of each group, starting from the extremum
+        let partition_by = grouping_columns
+            .iter()
+            .map(|col| self.to_group_value(col))
+            .collect::<Result<Vec<Box<sast::ValueExpression>>, ConverterError>>()?;
+        let row_number = sast::ValueExpression::RowNumber {
+            partition_by,
+            order_by: sast::OrderByClause {
+                specs: vec![sast::SortSpecification {
+                    value: self.to_value_expression(extremum.clone())?,
+                    order,
+                    nulls: Some(sast::NullsPosition::Last),
+                }],
+            },
+        };
+        head.select.columns.push(sast::SelectColumn {
+            value: Box::new(row_number),
+            alias: Some(ROW_NUMBER_COLUMN.to_string()),
+        });
+
+        // Keep the first row of every group
+        let mut columns = grouping_columns
+            .iter()
+            .map(|col| self.to_select_column(col.clone()))
+            .collect::<Result<Vec<sast::SelectColumn>, ConverterError>>()?;
+        let extremum_column = kast::ColumnDefinition {
+            expr: match &*extremum.value {
+                kast::Expression::Identifier { name } if name.value == column.column.value => None,
+                _ => Some(extremum),
+            },
+            column: column.column,
+        };
+        let mut retained = Vec::new();
+        let mut modified = Vec::new();
+        for col in grouping_columns
+            .iter()
+            .chain(std::iter::once(&extremum_column))
+        {
+            match col.expr {
+                Some(_) => modified.push(col.column.value.clone()),
+                None => retained.push(col.column.value.clone()),
             }
+        }
+        columns.push(self.to_select_column(extremum_column)?);
+        for name in returned {
+            columns.push(sast::SelectColumn {
+                value: Box::new(sast::ValueExpression::Column { name: name.clone() }),
+                alias: None,
+            });
+            retained.push(name);
+        }
+
+        let mut new_head = sast::SelectStatement::simple_wrapping(head);
+        new_head.select = sast::SelectList {
+            wildcard: false,
+            columns,
         };
+        new_head.where_ = Some(Merger::first_row_condition());
+        self.columns = ColumnsState::Modified { retained, modified };
+        Ok((self, new_head))
+    }
 
-        Ok(Box::new(value))
+    /// `value = TRUE`, for bool values used as conditions
+    fn is_true(value: Box<sast::ValueExpression>) -> sast::SearchCondition {
+        sast::SearchCondition::ComparisonExpr {
+            left: value,
+            op: sast::ComparisonOperator::EQ,
+            right: Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::Bool(true),
+            }),
+        }
     }
 
-    fn to_search_condition(
-        &self,
-        expr: MBox<kast::Expression>,
-    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
-        let cond: sast::SearchCondition = match *expr.value {
-            kast::Expression::Identifier { .. }
-            | kast::Expression::FuncCall { .. }
-            | kast::Expression::Literal { .. } => {
-                return Err(self.non_condition_expression(expr.span))
-            }
-            kast::Expression::BinaryOp { left, op, right } => match op.value {
-                kast::BinaryOp::Add
-                | kast::BinaryOp::Sub
-                | kast::BinaryOp::Mul
-                | kast::BinaryOp::Div
-                | kast::BinaryOp::Mod => return Err(self.non_condition_expression(expr.span)),
+    /// `__row_number == 1`
+    fn first_row_condition() -> Box<sast::SearchCondition> {
+        Box::new(sast::SearchCondition::ComparisonExpr {
+            left: Box::new(sast::ValueExpression::Column {
+                name: ROW_NUMBER_COLUMN.to_string(),
+            }),
+            op: sast::ComparisonOperator::EQ,
+            right: Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::Integer(1),
+            }),
+        })
+    }
+
+    /// Joins the head (the left table) with another query.
+    ///
+    /// Semi and anti joins return only one side and become `[NOT] EXISTS` filters.
+    /// The other kinds return both sides, so the columns of both must be known in
+    /// order to rename duplicate right side columns the way Kusto does.
+    fn merge_join(
+        mut self,
+        head: sast::SelectStatement,
+        name: M<String>,
+        params: kast::JoinParams,
+        right_table: Box<kast::Query>,
+        attributes: Vec<kast::JoinAttribute>,
+    ) -> Result<(Self, sast::SelectStatement), ConverterError> {
+        let kind = params.kind.unwrap_or(kast::JoinKind::InnerUnique);
+        let right_span = right_table.source_span();
+        let (right, right_types) =
+            crate::convert_query(self.src.clone(), self.catalog.clone(), *right_table)?;
+        if let Some(right_columns) = self.known_columns(&right) {
+            for attribute in &attributes {
+                let right_name = match attribute {
+                    kast::JoinAttribute::Matching { name } => name,
+                    kast::JoinAttribute::NonMatching { right_name, .. } => right_name,
+                };
+                validation::check_column(&self, &right_columns, right_name)?;
+            }
+        }
+
+        // Pairs of (left column, right column) which must be equal
+        let keys: Vec<(String, String)> = attributes
+            .into_iter()
+            .map(|attribute| match attribute {
+                kast::JoinAttribute::Matching { name } => (name.value.clone(), name.value),
+                kast::JoinAttribute::NonMatching {
+                    left_name,
+                    right_name,
+                    ..
+                } => (left_name.value, right_name.value),
+            })
+            .collect();
+        let on = Merger::join_condition(&keys);
+
+        let join_type = match kind {
+            kast::JoinKind::LeftSemi
+            | kast::JoinKind::LeftAnti
+            | kast::JoinKind::RightSemi
+            | kast::JoinKind::RightAnti => {
+                let negated = matches!(kind, kast::JoinKind::LeftAnti | kast::JoinKind::RightAnti);
+                let (outer, outer_alias, inner, inner_alias) =
+                    if kind.return_columns() == kast::JoinReturnColumns::Left {
+                        (head, LEFT_TABLE, right, RIGHT_TABLE)
+                    } else {
+                        self.types = right_types;
+                        (right, RIGHT_TABLE, head, LEFT_TABLE)
+                    };
+
+                let mut subquery = sast::SelectStatement::selecting_from(
+                    inner.into_table_reference(inner_alias.to_string()),
+                );
+                subquery.select = sast::SelectList {
+                    wildcard: false,
+                    columns: vec![sast::SelectColumn {
+                        value: Box::new(sast::ValueExpression::Literal {
+                            value: sast::Literal::Integer(1),
+                        }),
+                        alias: None,
+                    }],
+                };
+                subquery.where_ = Some(on);
+
+                let mut new_head = sast::SelectStatement::selecting_from(
+                    outer.into_table_reference(outer_alias.to_string()),
+                );
+                new_head.where_ = Some(Box::new(sast::SearchCondition::Exists {
+                    negated,
+                    query: Box::new(subquery),
+                }));
+                self.columns = ColumnsState::Unmodified;
+                return Ok((self, new_head));
+            }
+            kast::JoinKind::InnerUnique | kast::JoinKind::Inner => sast::JoinType::Inner,
+            kast::JoinKind::LeftOuter => sast::JoinType::LeftOuter,
+            kast::JoinKind::RightOuter => sast::JoinType::RightOuter,
+            kast::JoinKind::FullOuter => sast::JoinType::FullOuter,
+        };
+
+        let left_columns = match self.known_columns(&head) {
+            Some(columns) => columns,
+            None => return Err(self.unknown_join_columns(name.span, "left")),
+        };
+        let right_columns = match self.known_columns(&right) {
+            Some(columns) => columns,
+            None => return Err(self.unknown_join_columns(right_span, "right")),
+        };
+
+        let left = if kind == kast::JoinKind::InnerUnique {
+            let left_keys: Vec<String> = keys.into_iter().map(|(left, _)| left).collect();
+            Merger::deduplicate(head, &left_keys, &left_columns)
+        } else {
+            head
+        };
+
+        // Right side columns which share a name with another column get a "1" appended
+        let mut names = left_columns.clone();
+        let mut columns: Vec<sast::SelectColumn> = left_columns
+            .into_iter()
+            .map(|name| sast::SelectColumn {
+                value: Box::new(sast::ValueExpression::QualifiedColumn {
+                    table: LEFT_TABLE.to_string(),
+                    name,
+                }),
+                alias: None,
+            })
+            .collect();
+        for name in right_columns {
+            let mut output_name = name.clone();
+            while names.contains(&output_name) {
+                output_name.push('1');
+            }
+            match right_types.get(&name) {
+                Some(data_type) => self.types.insert(output_name.clone(), *data_type),
+                None => self.types.remove(&output_name),
+            };
+            columns.push(sast::SelectColumn {
+                value: Box::new(sast::ValueExpression::QualifiedColumn {
+                    table: RIGHT_TABLE.to_string(),
+                    name: name.clone(),
+                }),
+                alias: if output_name != name {
+                    Some(output_name.clone())
+                } else {
+                    None
+                },
+            });
+            names.push(output_name);
+        }
+
+        let mut new_head = sast::SelectStatement::selecting_from(sast::TableReference::Join {
+            left: Box::new(left.into_table_reference(LEFT_TABLE.to_string())),
+            join_type,
+            right: Box::new(right.into_table_reference(RIGHT_TABLE.to_string())),
+            on,
+        });
+        new_head.select = sast::SelectList {
+            wildcard: false,
+            columns,
+        };
+        // Columns of the join are only reachable through the table aliases,
+        // so they all count as modified and later operators wrap the join.
+        self.columns = ColumnsState::Modified {
+            retained: vec![],
+            modified: names,
+        };
+        Ok((self, new_head))
+    }
+
+    fn join_condition(keys: &[(String, String)]) -> Box<sast::SearchCondition> {
+        keys.iter()
+            .map(|(left, right)| {
+                Box::new(sast::SearchCondition::ComparisonExpr {
+                    left: Box::new(sast::ValueExpression::QualifiedColumn {
+                        table: LEFT_TABLE.to_string(),
+                        name: left.clone(),
+                    }),
+                    op: sast::ComparisonOperator::EQ,
+                    right: Box::new(sast::ValueExpression::QualifiedColumn {
+                        table: RIGHT_TABLE.to_string(),
+                        name: right.clone(),
+                    }),
+                })
+            })
+            .reduce(|left, right| {
+                Box::new(sast::SearchCondition::BoolExpr {
+                    left,
+                    op: sast::BoolOperator::AND,
+                    right,
+                })
+            })
+            .unwrap()
+    }
+
+    /// Keeps a single row for every combination of `keys`, as `innerunique` joins do
+    /// with their left side.
+    fn deduplicate(
+        head: sast::SelectStatement,
+        keys: &[String],
+        columns: &[String],
+    ) -> sast::SelectStatement {
+        let key_values = || {
+            keys.iter()
+                .map(|key| Box::new(sast::ValueExpression::Column { name: key.clone() }))
+        };
+
+        let mut numbered = sast::SelectStatement::simple_wrapping(head);
+        numbered.select.columns.push(sast::SelectColumn {
+            value: Box::new(sast::ValueExpression::RowNumber {
+                partition_by: key_values().collect(),
+                order_by: sast::OrderByClause {
+                    specs: key_values()
+                        .map(|value| sast::SortSpecification {
+                            value,
+                            order: sast::SortOrder::Ascending,
+                            nulls: None,
+                        })
+                        .collect(),
+                },
+            }),
+            alias: Some(ROW_NUMBER_COLUMN.to_string()),
+        });
+
+        let mut deduplicated = sast::SelectStatement::simple_wrapping(numbered);
+        deduplicated.select = sast::SelectList {
+            wildcard: false,
+            columns: columns
+                .iter()
+                .map(|name| sast::SelectColumn {
+                    value: Box::new(sast::ValueExpression::Column { name: name.clone() }),
+                    alias: None,
+                })
+                .collect(),
+        };
+        deduplicated.where_ = Some(Merger::first_row_condition());
+        deduplicated
+    }
+
+    /// Combines the rows of several tables (and the head, if there is one) with
+    /// `UNION ALL`.
+    ///
+    /// SQL requires every statement of a union to have the same columns, so each table
+    /// selects the output columns in the same order, using NULL for those it lacks.
+    /// Outer unions keep every column and inner unions only those found in every table.
+    pub fn merge_union(
+        mut self,
+        head: Option<sast::SelectStatement>,
+        span: Span,
+        params: kast::UnionParams,
+        tables: Vec<kast::Query>,
+    ) -> Result<(Self, sast::SelectStatement), ConverterError> {
+        let is_fuzzy = params.is_fuzzy.is_some_and(|is_fuzzy| is_fuzzy.value);
+
+        // (source name, statement, columns, span) for every table of the union
+        let mut legs = Vec::new();
+        let mut leg_types = Vec::new();
+        if let Some(head) = head {
+            let columns = self.known_columns(&head);
+            let name = Merger::source_table(&head).unwrap_or_default();
+            legs.push((name, head, columns, span.clone()));
+            leg_types.push(std::mem::take(&mut self.types));
+        }
+        for query in self.expand_union_tables(tables, is_fuzzy)? {
+            let name = match query.table() {
+                Some(table) => table.value.clone(),
+                None => format!("union_arg{}", legs.len()),
+            };
+            let leg_span = query.source_span();
+            let (statement, types) =
+                crate::convert_query(self.src.clone(), self.catalog.clone(), query)?;
+            let columns = self.known_columns(&statement);
+            legs.push((name, statement, columns, leg_span));
+            leg_types.push(types);
+        }
+        if legs.is_empty() {
+            return Err(self.invalid_argument(span, "none of the tables of the union exist"));
+        }
+
+        let mut leg_columns = Vec::new();
+        for (_, _, columns, leg_span) in &legs {
+            match columns {
+                Some(columns) => leg_columns.push(columns.clone()),
+                None => return Err(self.unknown_union_columns(leg_span.clone())),
+            }
+        }
+        let columns: Vec<String> = match params.kind.map(|kind| kind.value) {
+            Some(kast::UnionKind::Inner) => leg_columns[0]
+                .iter()
+                .filter(|name| leg_columns.iter().all(|columns| columns.contains(name)))
+                .cloned()
+                .collect(),
+            Some(kast::UnionKind::Outer) | None => {
+                let mut columns: Vec<String> = Vec::new();
+                for name in leg_columns.iter().flatten() {
+                    if !columns.contains(name) {
+                        columns.push(name.clone());
+                    }
+                }
+                columns
+            }
+        };
+
+        // A column has a type when every table which has it agrees on the type
+        let mut types = Types::new();
+        for column in &columns {
+            let mut found = leg_columns
+                .iter()
+                .zip(&leg_types)
+                .filter(|(leg_columns, _)| leg_columns.contains(column))
+                .map(|(_, types)| types.get(column).copied());
+            if let Some(Some(data_type)) = found.next() {
+                if found.all(|other| other == Some(data_type)) {
+                    types.insert(column.clone(), data_type);
+                }
+            }
+        }
+
+        let statements = legs
+            .into_iter()
+            .map(|(name, statement, leg_columns, _)| {
+                let leg_columns = leg_columns.unwrap();
+                // ORDER BY and LIMIT would apply to the whole union
+                if leg_columns == columns
+                    && params.with_source.is_none()
+                    && statement.order_by.is_none()
+                    && statement.limit.is_none()
+                {
+                    return statement;
+                }
+
+                let mut statement = if Merger::selects_only_columns(&statement) {
+                    statement
+                } else {
+                    sast::SelectStatement::simple_wrapping(statement)
+                };
+                let mut select = Vec::new();
+                if let Some(with_source) = &params.with_source {
+                    select.push(sast::SelectColumn {
+                        value: Box::new(sast::ValueExpression::Literal {
+                            value: sast::Literal::String(name),
+                        }),
+                        alias: Some(with_source.value.clone()),
+                    });
+                }
+                select.extend(columns.iter().map(|column| {
+                    if leg_columns.contains(column) {
+                        Merger::column(column)
+                    } else {
+                        // a typed NULL keeps the type of the column the other tables have
+                        let value = match types.get(column) {
+                            Some(data_type) => Merger::typed_null(typing::to_data_type(*data_type)),
+                            None => Box::new(sast::ValueExpression::Literal {
+                                value: sast::Literal::Null,
+                            }),
+                        };
+                        sast::SelectColumn {
+                            value,
+                            alias: Some(column.clone()),
+                        }
+                    }
+                }));
+                statement.select = sast::SelectList {
+                    wildcard: false,
+                    columns: select,
+                };
+                statement
+            })
+            .collect();
+
+        if let Some(with_source) = &params.with_source {
+            types.insert(with_source.value.clone(), ScalarType::String);
+        }
+        let mut output_columns: Vec<String> = params
+            .with_source
+            .into_iter()
+            .map(|with_source| with_source.value)
+            .collect();
+        output_columns.extend(columns);
+
+        let mut new_head =
+            sast::SelectStatement::selecting_from(sast::TableReference::SetOperation {
+                op: sast::SetOperator::UnionAll,
+                statements,
+                alias: Some(UNION_TABLE.to_string()),
+            });
+        new_head.select = sast::SelectList {
+            wildcard: false,
+            columns: output_columns
+                .iter()
+                .map(|name| Merger::column(name))
+                .collect(),
+        };
+        self.columns = ColumnsState::Limited {
+            columns: output_columns,
+        };
+        self.types = types;
+        Ok((self, new_head))
+    }
+
+    /// Replaces table patterns such as `Events*` with the catalog tables they match.
+    /// Fuzzy unions also drop the tables which the catalog does not know.
+    fn expand_union_tables(
+        &self,
+        tables: Vec<kast::Query>,
+        is_fuzzy: bool,
+    ) -> Result<Vec<kast::Query>, ConverterError> {
+        let mut expanded = Vec::new();
+        for query in tables {
+            let table = match (query.table(), query.operators.is_empty()) {
+                (Some(table), true) => table.clone(),
+                _ => {
+                    expanded.push(query);
+                    continue;
+                }
+            };
+
+            let table_names = self.catalog.as_ref().map(|catalog| catalog.table_names());
+            if !table.value.contains('*') {
+                match &table_names {
+                    Some(names) if is_fuzzy && !names.contains(&table.value) => {}
+                    _ => expanded.push(query),
+                }
+                continue;
+            }
+
+            let matches: Vec<String> = match table_names {
+                Some(names) => names
+                    .into_iter()
+                    .filter(|name| matches_wildcard(&table.value, name))
+                    .collect(),
+                None => {
+                    return Err(self.unresolved_wildcard(
+                        table,
+                        "A schema catalog is needed to expand wildcards",
+                    ))
+                }
+            };
+            if matches.is_empty() && !is_fuzzy {
+                return Err(
+                    self.unresolved_wildcard(table, "No table in the schema catalog matches it")
+                );
+            }
+            expanded.extend(matches.into_iter().map(|name| kast::Query {
+                source: kast::Source::Table(M::new(name, table.span.clone())),
+                operators: vec![],
+            }));
+        }
+        Ok(expanded)
+    }
+
+    /// The columns a statement returns, from its select list or, when it selects all
+    /// the columns of a table, from the catalog
+    fn known_columns(&self, statement: &sast::SelectStatement) -> Option<Vec<String>> {
+        if !statement.select.wildcard {
+            return statement.select.column_names();
+        }
+
+        let mut columns = match &statement.from {
+            sast::TableReference::TableName { name, .. } => {
+                let columns = self.catalog.as_ref()?.columns(name)?;
+                columns.into_iter().map(|column| column.name).collect()
+            }
+            sast::TableReference::InnerStatement { value, .. } => self.known_columns(value)?,
+            _ => return None,
+        };
+        // columns added next to the wildcard, which may not replace existing ones
+        for column in &statement.select.columns {
+            let name = match (&column.alias, &*column.value) {
+                (Some(alias), _) => alias.clone(),
+                (None, sast::ValueExpression::Column { name }) => name.clone(),
+                _ => return None,
+            };
+            if columns.contains(&name) {
+                return None;
+            }
+            columns.push(name);
+        }
+        Some(columns)
+    }
+
+    /// Whether the select list of a statement can be replaced without changing its rows
+    fn selects_only_columns(statement: &sast::SelectStatement) -> bool {
+        let columns_only = if statement.select.wildcard {
+            statement.select.columns.is_empty()
+        } else {
+            statement.select.columns.iter().all(|column| {
+                column.alias.is_none()
+                    && matches!(*column.value, sast::ValueExpression::Column { .. })
+            })
+        };
+        columns_only
+            && statement.with.is_empty()
+            && statement.modifier.is_none()
+            && statement.group_by.is_none()
+            && statement.order_by.is_none()
+            && statement.limit.is_none()
+    }
+
+    /// The columns of the head, which the project variants need in order to list the
+    /// columns they return. A wildcard pattern is reported when there is one, as it
+    /// is what cannot be resolved.
+    fn input_columns<'a>(
+        &self,
+        head: &sast::SelectStatement,
+        operator: M<String>,
+        mut patterns: impl Iterator<Item = &'a M<String>>,
+    ) -> Result<Vec<String>, ConverterError> {
+        match self.known_columns(head) {
+            Some(columns) => Ok(columns),
+            None => match patterns.find(|pattern| pattern.value.contains('*')) {
+                Some(pattern) => Err(self.unresolved_wildcard(
+                    pattern.clone(),
+                    "The input columns are unknown, so list them with project first \
+                    or provide a schema catalog",
+                )),
+                None => Err(self.unknown_input_columns(operator)),
+            },
+        }
+    }
+
+    fn matching_columns<'a>(
+        columns: &'a [String],
+        pattern: &'a str,
+    ) -> impl Iterator<Item = String> + 'a {
+        columns
+            .iter()
+            .filter(move |column| matches_wildcard(pattern, column))
+            .cloned()
+    }
+
+    /// Selects input columns of the head, given as (column, new name) pairs
+    fn select_input_columns(
+        mut self,
+        head: sast::SelectStatement,
+        columns: Vec<(String, Option<String>)>,
+    ) -> (Self, sast::SelectStatement) {
+        let mut head = if Merger::selects_only_columns(&head) {
+            head
+        } else {
+            sast::SelectStatement::simple_wrapping(head)
+        };
+
+        let (retained, renamed): (Vec<_>, Vec<_>) =
+            columns.iter().partition(|(_, new_name)| new_name.is_none());
+        self.columns = if renamed.is_empty() {
+            ColumnsState::Limited {
+                columns: retained.into_iter().map(|(name, _)| name.clone()).collect(),
+            }
+        } else {
+            // renamed columns cannot be referenced by the clauses of the same statement
+            ColumnsState::Modified {
+                retained: retained.into_iter().map(|(name, _)| name.clone()).collect(),
+                modified: renamed
+                    .into_iter()
+                    .filter_map(|(_, new_name)| new_name.clone())
+                    .collect(),
+            }
+        };
+
+        head.select = sast::SelectList {
+            wildcard: false,
+            columns: columns
+                .into_iter()
+                .map(|(name, alias)| sast::SelectColumn {
+                    value: Box::new(sast::ValueExpression::Column { name }),
+                    alias,
+                })
+                .collect(),
+        };
+        (self, head)
+    }
+
+    /// The innermost table a statement reads
+    fn source_table(statement: &sast::SelectStatement) -> Option<String> {
+        match &statement.from {
+            sast::TableReference::TableName { name, .. } => Some(name.clone()),
+            sast::TableReference::InnerStatement { value, .. } => Merger::source_table(value),
+            _ => None,
+        }
+    }
+
+    fn column(name: &str) -> sast::SelectColumn {
+        sast::SelectColumn {
+            value: Box::new(sast::ValueExpression::Column {
+                name: name.to_string(),
+            }),
+            alias: None,
+        }
+    }
+
+    fn to_group_by(
+        &self,
+        grouping_columns: &[kast::ColumnDefinition],
+    ) -> Result<Option<sast::GroupByClause>, ConverterError> {
+        if grouping_columns.is_empty() {
+            return Ok(None);
+        }
+
+        let columns = grouping_columns
+            .iter()
+            .map(|col| self.to_group_value(col))
+            .collect::<Result<Vec<Box<sast::ValueExpression>>, ConverterError>>()?;
+        Ok(Some(sast::GroupByClause { columns }))
+    }
+
+    /// The value a grouping column is computed from
+    fn to_group_value(
+        &self,
+        column: &kast::ColumnDefinition,
+    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
+        match &column.expr {
+            Some(expr) => self.to_value_expression(expr.clone()),
+            None => Ok(Box::new(sast::ValueExpression::Column {
+                name: column.column.value.clone(),
+            })),
+        }
+    }
+
+    fn to_aggregate_column(
+        &self,
+        column: kast::ColumnDefinition,
+    ) -> Result<sast::SelectColumn, ConverterError> {
+        let value = match column.expr {
+            Some(expr) => self.to_aggregate_expression(expr)?,
+            None => return Err(self.expected_aggregate(column.column.span)),
+        };
+
+        Ok(sast::SelectColumn {
+            value,
+            alias: Some(column.column.value),
+        })
+    }
+
+    /// Converts a summarize result expression, which is built from aggregation function
+    /// calls combined with arithmetic, literals, and scalar functions.
+    pub fn to_aggregate_expression(
+        &self,
+        expr: MBox<kast::Expression>,
+    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
+        let span = expr.span;
+        let value = match *expr.value {
+            kast::Expression::FuncCall { name, args, .. }
+                if aggregates::is_aggregate(&name.value) =>
+            {
+                return aggregates::to_aggregate(self, name, args, span)
+            }
+            kast::Expression::FuncCall { name, args, .. }
+                if args
+                    .iter()
+                    .any(|arg| aggregates::contains_aggregate(&arg.value)) =>
+            {
+                return functions::to_scalar_function(
+                    self,
+                    name,
+                    args,
+                    span,
+                    Merger::to_aggregate_expression,
+                )
+            }
+            kast::Expression::FuncCall { name, .. } => return Err(self.unknown_aggregate(name)),
+            kast::Expression::BinaryOp { left, op, right } => {
+                match self.to_typed_arithmetic_operator(op.value, &left, &right) {
+                    Some(op) => sast::ValueExpression::ArithmeticExpr {
+                        left: self.to_aggregate_expression(left)?,
+                        op,
+                        right: self.to_aggregate_expression(right)?,
+                    },
+                    None => return Err(self.expected_aggregate(span)),
+                }
+            }
+            kast::Expression::UnaryOp { op, expr } => match Merger::to_unary_operator(op.value) {
+                Some(op) => sast::ValueExpression::UnaryExpr {
+                    op,
+                    value: self.to_aggregate_expression(expr)?,
+                },
+                None => return Err(self.expected_aggregate(span)),
+            },
+            kast::Expression::Literal { value } => {
+                return self
+                    .to_value_expression(MBox::new(kast::Expression::Literal { value }, span))
+            }
+            kast::Expression::Identifier { .. } => return Err(self.expected_aggregate(span)),
+            kast::Expression::List { .. } | kast::Expression::Range { .. } => {
+                return Err(self.misplaced_list(span))
+            }
+            kast::Expression::Wildcard => return Err(self.misplaced_wildcard(span)),
+        };
+
+        Ok(Box::new(value))
+    }
+
+    fn to_select_column(
+        &self,
+        column: kast::ColumnDefinition,
+    ) -> Result<sast::SelectColumn, ConverterError> {
+        let name = column.column.value;
+
+        let def = match column.expr {
+            Some(k_expr) => sast::SelectColumn {
+                value: self.to_value_expression(k_expr)?,
+                alias: Some(name),
+            },
+            None => sast::SelectColumn {
+                value: Box::new(sast::ValueExpression::Column { name }),
+                alias: None,
+            },
+        };
+
+        Ok(def)
+    }
+
+    pub fn to_value_expression(
+        &self,
+        expr: MBox<kast::Expression>,
+    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
+        let value = match *expr.value {
+            kast::Expression::Identifier { name } => {
+                sast::ValueExpression::Column { name: name.value }
+            }
+            kast::Expression::FuncCall { name, args, .. } => {
+                return functions::to_scalar_function(
+                    self,
+                    name,
+                    args,
+                    expr.span,
+                    Merger::to_value_expression,
+                )
+            }
+            kast::Expression::BinaryOp { left, op, right } => {
+                match self.to_typed_arithmetic_operator(op.value, &left, &right) {
+                    Some(arithmetic_op) => sast::ValueExpression::ArithmeticExpr {
+                        left: self.to_value_expression(left)?,
+                        op: arithmetic_op,
+                        right: self.to_value_expression(right)?,
+                    },
+                    // Logical and comparison operators produce a boolean value
+                    None => sast::ValueExpression::Condition {
+                        condition: self.to_search_condition(MBox::new(
+                            kast::Expression::BinaryOp { left, op, right },
+                            expr.span,
+                        ))?,
+                    },
+                }
+            }
+            kast::Expression::UnaryOp { op, expr: operand } => {
+                // The sign of a timespan is part of the interval literal
+                if let (
+                    kast::UnaryOp::Neg,
+                    kast::Expression::Literal {
+                        value: kast::Literal::Timespan(Some(ticks)),
+                    },
+                ) = (op.value, &*operand.value)
+                {
+                    if let Some(ticks) = ticks.checked_neg() {
+                        let value = self.to_interval(ticks, expr.span)?;
+                        return Ok(Box::new(sast::ValueExpression::Literal { value }));
+                    }
+                }
+                match Merger::to_unary_operator(op.value) {
+                    Some(unary_op) => sast::ValueExpression::UnaryExpr {
+                        op: unary_op,
+                        value: self.to_value_expression(operand)?,
+                    },
+                    None => sast::ValueExpression::Condition {
+                        condition: self.to_search_condition(MBox::new(
+                            kast::Expression::UnaryOp { op, expr: operand },
+                            expr.span,
+                        ))?,
+                    },
+                }
+            }
+            kast::Expression::Literal { value } => {
+                let literal = match value {
+                    kast::Literal::Bool(Some(v)) => sast::Literal::Bool(v),
+                    kast::Literal::Int(Some(v)) => sast::Literal::Integer(v as i64),
+                    kast::Literal::Long(Some(v)) => sast::Literal::Integer(v),
+                    kast::Literal::Real(Some(v)) => sast::Literal::Real(v),
+                    kast::Literal::String(v) => sast::Literal::String(v),
+                    kast::Literal::DateTime(Some(v)) => {
+                        sast::Literal::Timestamp(v.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+                    }
+                    kast::Literal::Timespan(Some(v)) => self.to_interval(v, expr.span)?,
+                    kast::Literal::Decimal(Some(v)) => sast::Literal::Decimal(v),
+                    kast::Literal::Dynamic(kast::Dynamic::Null) => {
+                        return Ok(Merger::typed_null(sast::DataType::Json))
+                    }
+                    kast::Literal::Dynamic(v) => sast::Literal::Json(v.to_string()),
+                    kast::Literal::Guid(Some(v)) => sast::Literal::String(v),
+                    kast::Literal::Bool(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Boolean))
+                    }
+                    kast::Literal::Int(None) => return Ok(Merger::typed_null(sast::DataType::Int)),
+                    kast::Literal::Long(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::BigInt))
+                    }
+                    kast::Literal::Real(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Double))
+                    }
+                    kast::Literal::DateTime(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Timestamp))
+                    }
+                    kast::Literal::Timespan(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Interval))
+                    }
+                    kast::Literal::Decimal(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Decimal))
+                    }
+                    kast::Literal::Guid(None) => {
+                        return Ok(Merger::typed_null(sast::DataType::Varchar))
+                    }
+                };
+                sast::ValueExpression::Literal { value: literal }
+            }
+            kast::Expression::List { .. } | kast::Expression::Range { .. } => {
+                return Err(self.misplaced_list(expr.span))
+            }
+            kast::Expression::Wildcard => return Err(self.misplaced_wildcard(expr.span)),
+        };
+
+        Ok(Box::new(value))
+    }
+
+    /// KQL nulls are typed, so they are cast to keep the type of the column they produce
+    fn typed_null(data_type: sast::DataType) -> Box<sast::ValueExpression> {
+        Box::new(sast::ValueExpression::Cast {
+            value: Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::Null,
+            }),
+            data_type,
+        })
+    }
+
+    /// `iff(condition, then, else)` and `case(condition, value, ..., else)` become a
+    /// searched CASE expression
+    pub fn to_case(
+        &self,
+        name: M<String>,
+        args: Vec<MBox<kast::Expression>>,
+        span: Span,
+    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
+        if name.value == "case" {
+            if args.len() < 3 || args.len() % 2 == 0 {
+                return Err(self.invalid_argument(
+                    span,
+                    "case expects pairs of conditions and values followed by an else value",
+                ));
+            }
+        } else if args.len() != 3 {
+            return Err(self.wrong_argument_count(span, &name.value, 3, 3));
+        }
+
+        let mut args = args.into_iter();
+        let mut branches = Vec::new();
+        while args.len() > 1 {
+            branches.push(sast::WhenClause {
+                condition: self.to_search_condition(args.next().unwrap())?,
+                result: self.to_value_expression(args.next().unwrap())?,
+            });
+        }
+        Ok(Box::new(sast::ValueExpression::Case {
+            branches,
+            else_: Some(self.to_value_expression(args.next().unwrap())?),
+        }))
+    }
+
+    /// Expresses a number of ticks in the largest unit which represents it exactly
+    fn to_interval(&self, ticks: i64, span: Span) -> Result<sast::Literal, ConverterError> {
+        let units = [
+            (864_000_000_000, sast::IntervalUnit::Day),
+            (36_000_000_000, sast::IntervalUnit::Hour),
+            (600_000_000, sast::IntervalUnit::Minute),
+            (10_000_000, sast::IntervalUnit::Second),
+            (10_000, sast::IntervalUnit::Millisecond),
+            (10, sast::IntervalUnit::Microsecond),
+        ];
+
+        for (ticks_per_unit, unit) in units {
+            if ticks % ticks_per_unit == 0 {
+                return Ok(sast::Literal::Interval {
+                    value: ticks / ticks_per_unit,
+                    unit,
+                });
+            }
+        }
+        self.not_yet_implemented(span, "timespans with sub-microsecond precision")
+    }
+
+    fn to_unary_operator(op: kast::UnaryOp) -> Option<sast::UnaryOperator> {
+        match op {
+            kast::UnaryOp::Neg => Some(sast::UnaryOperator::Neg),
+            kast::UnaryOp::Plus => Some(sast::UnaryOperator::Plus),
+            kast::UnaryOp::Not => None,
+        }
+    }
+
+    fn to_arithmetic_operator(op: kast::BinaryOp) -> Option<sast::ArithmeticOperator> {
+        match op {
+            kast::BinaryOp::Add => Some(sast::ArithmeticOperator::Add),
+            kast::BinaryOp::Sub => Some(sast::ArithmeticOperator::Sub),
+            kast::BinaryOp::Mul => Some(sast::ArithmeticOperator::Mul),
+            kast::BinaryOp::Div => Some(sast::ArithmeticOperator::Div),
+            kast::BinaryOp::Mod => Some(sast::ArithmeticOperator::Mod),
+            _ => None,
+        }
+    }
+
+    /// Dividing integers discards the fraction in KQL, while SQL returns a decimal
+    fn to_typed_arithmetic_operator(
+        &self,
+        op: kast::BinaryOp,
+        left: &MBox<kast::Expression>,
+        right: &MBox<kast::Expression>,
+    ) -> Option<sast::ArithmeticOperator> {
+        let is_integer = |side| self.type_of(side).is_some_and(typing::is_integer);
+        match Merger::to_arithmetic_operator(op) {
+            Some(sast::ArithmeticOperator::Div) if is_integer(left) && is_integer(right) => {
+                Some(sast::ArithmeticOperator::IntDiv)
+            }
+            arithmetic_op => arithmetic_op,
+        }
+    }
+
+    pub fn to_search_condition(
+        &self,
+        expr: MBox<kast::Expression>,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        let cond: sast::SearchCondition = match *expr.value {
+            kast::Expression::FuncCall { name, args, .. } => match name.value.as_str() {
+                "isnull" | "isnotnull" | "isempty" | "isnotempty" => {
+                    return self.to_null_check(name, args, expr.span)
+                }
+                // a conditional choosing between conditions is compared against TRUE
+                "iff" | "iif" | "case" => Merger::is_true(self.to_case(name, args, expr.span)?),
+                _ => return Err(self.non_condition_expression(expr.span)),
+            },
+            // a column or literal which is or may be a bool is compared against TRUE as well
+            value @ (kast::Expression::Identifier { .. } | kast::Expression::Literal { .. }) => {
+                let value = MBox::new(value, expr.span);
+                match self.type_of(&value) {
+                    None | Some(ScalarType::Bool) => {
+                        Merger::is_true(self.to_value_expression(value)?)
+                    }
+                    Some(_) => return Err(self.non_condition_expression(value.span)),
+                }
+            }
+            kast::Expression::List { .. } | kast::Expression::Range { .. } => {
+                return Err(self.misplaced_list(expr.span))
+            }
+            kast::Expression::Wildcard => return Err(self.misplaced_wildcard(expr.span)),
+            kast::Expression::UnaryOp { op, expr: operand } => match op.value {
+                kast::UnaryOp::Not => match *self.to_search_condition(operand)? {
+                    sast::SearchCondition::Exists { negated, query } => {
+                        sast::SearchCondition::Exists {
+                            negated: !negated,
+                            query,
+                        }
+                    }
+                    condition => sast::SearchCondition::Not {
+                        condition: Box::new(condition),
+                    },
+                },
+                kast::UnaryOp::Neg | kast::UnaryOp::Plus => {
+                    return Err(self.non_condition_expression(expr.span))
+                }
+            },
+            kast::Expression::BinaryOp { left, op, right } => match op.value {
+                kast::BinaryOp::Add
+                | kast::BinaryOp::Sub
+                | kast::BinaryOp::Mul
+                | kast::BinaryOp::Div
+                | kast::BinaryOp::Mod => return Err(self.non_condition_expression(expr.span)),
 
                 kast::BinaryOp::LogicalAnd | kast::BinaryOp::LogicalOr => {
                     sast::SearchCondition::BoolExpr {
@@ -286,18 +1746,63 @@ impl Merger {
                     }
                 }
 
+                kast::BinaryOp::StringPredicate {
+                    predicate,
+                    case_sensitive,
+                    negated,
+                } => {
+                    return self.to_string_predicate(
+                        left,
+                        predicate,
+                        case_sensitive,
+                        negated,
+                        right,
+                    )
+                }
+
+                kast::BinaryOp::In {
+                    case_sensitive,
+                    negated,
+                } => return self.to_in(left, case_sensitive, negated, right),
+                kast::BinaryOp::HasAny | kast::BinaryOp::HasAll => {
+                    return self.to_has_any(left, op, right)
+                }
+                kast::BinaryOp::Between { negated } => {
+                    let (low, high) = match *right.value {
+                        kast::Expression::Range { start, end, .. } => (start, end),
+                        _ => return Err(self.invalid_argument(right.span, "expected a range")),
+                    };
+                    sast::SearchCondition::Between {
+                        value: self.to_value_expression(left)?,
+                        low: self.to_value_expression(low)?,
+                        high: self.to_value_expression(high)?,
+                        negated,
+                    }
+                }
+
+                kast::BinaryOp::EQ | kast::BinaryOp::NEQ => {
+                    let strings = self.compares_strings([&left, &right].into_iter());
+                    let (left, right) = (
+                        self.to_compared_value(left, strings)?,
+                        self.to_compared_value(right, strings)?,
+                    );
+                    sast::SearchCondition::ComparisonExpr {
+                        left,
+                        op: match op.value {
+                            kast::BinaryOp::EQ => sast::ComparisonOperator::EQ,
+                            _ => sast::ComparisonOperator::NEQ,
+                        },
+                        right,
+                    }
+                }
                 kast::BinaryOp::LT
                 | kast::BinaryOp::GT
-                | kast::BinaryOp::EQ
-                | kast::BinaryOp::NEQ
                 | kast::BinaryOp::LTE
                 | kast::BinaryOp::GTE => sast::SearchCondition::ComparisonExpr {
                     left: self.to_value_expression(left)?,
                     op: match op.value {
                         kast::BinaryOp::LT => sast::ComparisonOperator::LT,
                         kast::BinaryOp::GT => sast::ComparisonOperator::GT,
-                        kast::BinaryOp::EQ => sast::ComparisonOperator::EQ,
-                        kast::BinaryOp::NEQ => sast::ComparisonOperator::NEQ,
                         kast::BinaryOp::LTE => sast::ComparisonOperator::LTE,
                         kast::BinaryOp::GTE => sast::ComparisonOperator::GTE,
                         _ => unreachable!(),
@@ -309,6 +1814,422 @@ impl Merger {
         Ok(Box::new(cond))
     }
 
+    /// Whether any of the operands of `==`, `!=` or `in` is known to be a string
+    fn compares_strings<'a>(
+        &self,
+        mut operands: impl Iterator<Item = &'a MBox<kast::Expression>>,
+    ) -> bool {
+        operands.any(|operand| self.type_of(operand) == Some(ScalarType::String))
+    }
+
+    /// An operand of `==`, `!=` or `in`, which compare strings case-sensitively. When
+    /// strings are compared, the operands which are or may be strings are marked as such.
+    fn to_compared_value(
+        &self,
+        expr: MBox<kast::Expression>,
+        compares_strings: bool,
+    ) -> Result<Box<sast::ValueExpression>, ConverterError> {
+        let may_be_string = matches!(self.type_of(&expr), None | Some(ScalarType::String));
+        let value = self.to_value_expression(expr)?;
+        if compares_strings && may_be_string {
+            Ok(Box::new(sast::ValueExpression::CaseSensitive { value }))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// `in~` compares lowercased values, lowercasing string literals directly
+    fn to_in(
+        &self,
+        left: MBox<kast::Expression>,
+        case_sensitive: bool,
+        negated: bool,
+        right: MBox<kast::Expression>,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        let lower = |value: Box<sast::ValueExpression>| {
+            Box::new(sast::ValueExpression::FuncCall {
+                name: String::from("LOWER"),
+                args: vec![value],
+            })
+        };
+
+        let items = self.to_list_items(right)?;
+        let strings = self.compares_strings(std::iter::once(&left).chain(&items));
+
+        let mut list = Vec::new();
+        for item in items {
+            let value = match *item.value {
+                kast::Expression::Literal {
+                    value: kast::Literal::String(text),
+                } if !case_sensitive => Box::new(sast::ValueExpression::Literal {
+                    value: sast::Literal::String(text.to_lowercase()),
+                }),
+                _ if !case_sensitive => lower(self.to_value_expression(item)?),
+                _ => self.to_compared_value(item, strings)?,
+            };
+            list.push(value);
+        }
+
+        let value = if case_sensitive {
+            self.to_compared_value(left, strings)?
+        } else {
+            lower(self.to_value_expression(left)?)
+        };
+        Ok(Box::new(sast::SearchCondition::In {
+            value,
+            list,
+            negated,
+        }))
+    }
+
+    /// `has_any` and `has_all` combine one `has` per term with OR and AND
+    fn to_has_any(
+        &self,
+        left: MBox<kast::Expression>,
+        op: M<kast::BinaryOp>,
+        right: MBox<kast::Expression>,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        let bool_op = || match op.value {
+            kast::BinaryOp::HasAll => sast::BoolOperator::AND,
+            _ => sast::BoolOperator::OR,
+        };
+        let mut condition: Option<Box<sast::SearchCondition>> = None;
+        for item in self.to_list_items(right)? {
+            let has = self.to_string_predicate(
+                left.clone(),
+                kast::StringPredicate::Has,
+                false,
+                false,
+                item,
+            )?;
+            condition = Some(match condition {
+                Some(previous) => Box::new(sast::SearchCondition::BoolExpr {
+                    left: previous,
+                    op: bool_op(),
+                    right: has,
+                }),
+                None => has,
+            });
+        }
+        Ok(condition.unwrap())
+    }
+
+    /// The items of a non-empty list, with the elements of `dynamic` arrays expanded in place
+    fn to_list_items(
+        &self,
+        list: MBox<kast::Expression>,
+    ) -> Result<Vec<MBox<kast::Expression>>, ConverterError> {
+        let items = match *list.value {
+            kast::Expression::List { items, .. } => items,
+            _ => return Err(self.invalid_argument(list.span, "expected a list")),
+        };
+
+        let mut expanded = Vec::new();
+        for item in items {
+            match *item.value {
+                kast::Expression::Literal {
+                    value: kast::Literal::Dynamic(kast::Dynamic::Array(values)),
+                } => {
+                    for value in values {
+                        let value = Merger::to_dynamic_element(value);
+                        expanded.push(MBox::new(
+                            kast::Expression::Literal { value },
+                            item.span.clone(),
+                        ));
+                    }
+                }
+                value => expanded.push(MBox::new(value, item.span)),
+            }
+        }
+
+        if expanded.is_empty() {
+            return Err(self.invalid_argument(list.span, "the list cannot be empty"));
+        }
+        Ok(expanded)
+    }
+
+    /// Scalar elements of a `dynamic` array become plain literals, others stay JSON
+    fn to_dynamic_element(value: kast::Dynamic) -> kast::Literal {
+        match value {
+            kast::Dynamic::Bool(v) => kast::Literal::Bool(Some(v)),
+            kast::Dynamic::Long(v) => kast::Literal::Long(Some(v)),
+            kast::Dynamic::Real(v) => kast::Literal::Real(Some(v)),
+            kast::Dynamic::String(v) => kast::Literal::String(v),
+            value => kast::Literal::Dynamic(value),
+        }
+    }
+
+    /// String literals are matched with LIKE patterns, other expressions by searching
+    /// for the string.
+    /// The `_cs` variants compare under a case-sensitive collation, the others lowercase.
+    fn to_string_predicate(
+        &self,
+        left: MBox<kast::Expression>,
+        predicate: kast::StringPredicate,
+        case_sensitive: bool,
+        negated: bool,
+        right: MBox<kast::Expression>,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        let to_cased = |expr: MBox<kast::Expression>| -> Result<_, ConverterError> {
+            let value = self.to_value_expression(expr)?;
+            if case_sensitive {
+                Ok(Box::new(sast::ValueExpression::CaseSensitive { value }))
+            } else {
+                Ok(Box::new(sast::ValueExpression::FuncCall {
+                    name: String::from("LOWER"),
+                    args: vec![value],
+                }))
+            }
+        };
+
+        let (prefix, suffix) = match predicate {
+            kast::StringPredicate::Equals => {
+                return Ok(Box::new(sast::SearchCondition::ComparisonExpr {
+                    left: to_cased(left)?,
+                    op: if negated {
+                        sast::ComparisonOperator::NEQ
+                    } else {
+                        sast::ComparisonOperator::EQ
+                    },
+                    right: to_cased(right)?,
+                }))
+            }
+            kast::StringPredicate::MatchesRegex => {
+                return Ok(Box::new(sast::SearchCondition::Regex {
+                    value: self.to_value_expression(left)?,
+                    pattern: self.to_value_expression(right)?,
+                    negated,
+                }))
+            }
+            kast::StringPredicate::Has => {
+                return self.to_has(left, case_sensitive, negated, right);
+            }
+            kast::StringPredicate::Contains => ("%", "%"),
+            kast::StringPredicate::StartsWith => ("", "%"),
+            kast::StringPredicate::EndsWith => ("%", ""),
+        };
+
+        if let kast::Expression::Literal {
+            value: kast::Literal::String(text),
+        } = &*right.value
+        {
+            let pattern = format!("{}{}{}", prefix, Merger::escape_like_pattern(text), suffix);
+            let value = self.to_value_expression(left)?;
+            return Ok(Box::new(sast::SearchCondition::Like {
+                value: if case_sensitive {
+                    Box::new(sast::ValueExpression::CaseSensitive { value })
+                } else {
+                    value
+                },
+                pattern: Box::new(sast::ValueExpression::Literal {
+                    value: sast::Literal::String(pattern),
+                }),
+                case_insensitive: !case_sensitive,
+                negated,
+            }));
+        }
+
+        let func = |name: &str, args: Vec<Box<sast::ValueExpression>>| {
+            Box::new(sast::ValueExpression::FuncCall {
+                name: String::from(name),
+                args,
+            })
+        };
+        let integer = |value: i64| {
+            Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::Integer(value),
+            })
+        };
+        let condition = match predicate {
+            // RIGHT(haystack, CHAR_LENGTH(needle)) == needle
+            kast::StringPredicate::EndsWith => sast::SearchCondition::ComparisonExpr {
+                left: func(
+                    "RIGHT",
+                    vec![
+                        to_cased(left)?,
+                        func("CHAR_LENGTH", vec![to_cased(right.clone())?]),
+                    ],
+                ),
+                op: sast::ComparisonOperator::EQ,
+                right: to_cased(right)?,
+            },
+            // LOCATE(needle, haystack) == 1
+            kast::StringPredicate::StartsWith => sast::SearchCondition::ComparisonExpr {
+                left: func("LOCATE", vec![to_cased(right)?, to_cased(left)?]),
+                op: sast::ComparisonOperator::EQ,
+                right: integer(1),
+            },
+            // LOCATE(needle, haystack) > 0
+            _ => sast::SearchCondition::ComparisonExpr {
+                left: func("LOCATE", vec![to_cased(right)?, to_cased(left)?]),
+                op: sast::ComparisonOperator::GT,
+                right: integer(0),
+            },
+        };
+
+        if negated {
+            Ok(Box::new(sast::SearchCondition::Not {
+                condition: Box::new(condition),
+            }))
+        } else {
+            Ok(Box::new(condition))
+        }
+    }
+
+    /// `has` looks for whole terms, so the term must not be next to other alphanumeric
+    /// characters. Only string literals are supported, as they become regular expressions.
+    fn to_has(
+        &self,
+        left: MBox<kast::Expression>,
+        case_sensitive: bool,
+        negated: bool,
+        right: MBox<kast::Expression>,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        let term = match &*right.value {
+            kast::Expression::Literal {
+                value: kast::Literal::String(text),
+            } => text,
+            _ => {
+                return self
+                    .not_yet_implemented(right.span, "has with a term which is not a string")
+            }
+        };
+        let value = self.to_value_expression(left)?;
+        let (value, term) = if case_sensitive {
+            (sast::ValueExpression::CaseSensitive { value }, term.clone())
+        } else {
+            (
+                sast::ValueExpression::FuncCall {
+                    name: String::from("LOWER"),
+                    args: vec![value],
+                },
+                term.to_lowercase(),
+            )
+        };
+        let pattern = format!(
+            "(^|[^[:alnum:]]){}($|[^[:alnum:]])",
+            Merger::escape_regex(&term)
+        );
+        Ok(Box::new(sast::SearchCondition::Regex {
+            value: Box::new(value),
+            pattern: Box::new(sast::ValueExpression::Literal {
+                value: sast::Literal::String(pattern),
+            }),
+            negated,
+        }))
+    }
+
+    /// Escapes the characters which have a meaning in regular expressions
+    fn escape_regex(text: &str) -> String {
+        let mut pattern = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(
+                c,
+                '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}'
+            ) {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        pattern
+    }
+
+    /// Escapes the LIKE wildcards, using the default escape character `\`
+    fn escape_like_pattern(text: &str) -> String {
+        let mut pattern = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(c, '%' | '_' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        pattern
+    }
+
+    /// `isnull(x)` and `isnotnull(x)` test for NULL, while `isempty(x)` and `isnotempty(x)`
+    /// also treat the empty string as missing.
+    pub fn to_null_check(
+        &self,
+        name: M<String>,
+        args: Vec<MBox<kast::Expression>>,
+        span: Span,
+    ) -> Result<Box<sast::SearchCondition>, ConverterError> {
+        if args.len() != 1 {
+            return Err(self.wrong_argument_count(span, &name.value, 1, 1));
+        }
+        let arg = args.into_iter().next().unwrap();
+        let negated = name.value.starts_with("isnot");
+        // only strings can be empty
+        let may_be_string = !matches!(
+            self.type_of(&arg),
+            Some(data_type) if !matches!(data_type, ScalarType::String | ScalarType::Guid)
+        );
+        let is_null = Box::new(sast::SearchCondition::IsNull {
+            value: self.to_value_expression(arg.clone())?,
+            negated,
+        });
+
+        if name.value.ends_with("null") || !may_be_string {
+            return Ok(is_null);
+        }
+
+        let empty = Box::new(sast::ValueExpression::Literal {
+            value: sast::Literal::String(String::new()),
+        });
+        let (op, comparison) = if negated {
+            (sast::BoolOperator::AND, sast::ComparisonOperator::NEQ)
+        } else {
+            (sast::BoolOperator::OR, sast::ComparisonOperator::EQ)
+        };
+        Ok(Box::new(sast::SearchCondition::BoolExpr {
+            left: is_null,
+            op,
+            right: Box::new(sast::SearchCondition::ComparisonExpr {
+                left: self.to_value_expression(arg)?,
+                op: comparison,
+                right: empty,
+            }),
+        }))
+    }
+
+    /// KQL sorts in descending order unless told otherwise.
+    /// The nulls position is only carried over when given explicitly.
+    fn to_sort_specification(
+        value: Box<sast::ValueExpression>,
+        order: Option<M<kast::SortOrder>>,
+        nulls: Option<(Span, M<kast::NullsPosition>)>,
+    ) -> sast::SortSpecification {
+        let order = match order.map(|order| order.value) {
+            Some(kast::SortOrder::Ascending) => sast::SortOrder::Ascending,
+            Some(kast::SortOrder::Descending) | None => sast::SortOrder::Descending,
+        };
+        // Kusto puts NULLs first in ascending order and last in descending order
+        let nulls = match nulls.map(|(_, nulls)| nulls.value) {
+            Some(kast::NullsPosition::First) => sast::NullsPosition::First,
+            Some(kast::NullsPosition::Last) => sast::NullsPosition::Last,
+            None => match order {
+                sast::SortOrder::Ascending => sast::NullsPosition::First,
+                sast::SortOrder::Descending => sast::NullsPosition::Last,
+            },
+        };
+
+        sast::SortSpecification {
+            value,
+            order,
+            nulls: Some(nulls),
+        }
+    }
+
+    /// Aggregating replaces the select list, so any clause which must be applied
+    /// before the aggregation requires a new SELECT statement
+    fn needs_wrapping_for_aggregation(&self, head: &sast::SelectStatement) -> bool {
+        head.modifier.is_some()
+            || head.group_by.is_some()
+            || head.order_by.is_some()
+            || head.limit.is_some()
+            || matches!(self.columns, ColumnsState::Modified { .. })
+    }
+
     fn get_column_state(columns: &Vec<ColumnDefinition>) -> ColumnsState {
         let (retained, modified): (Vec<_>, Vec<_>) =
             columns.iter().partition(|col| col.expr.is_none());
@@ -328,4 +2249,25 @@ impl Merger {
             ColumnsState::Modified { retained, modified }
         }
     }
+
+    /// Grouping columns come first, followed by the aggregations which are always new values
+    fn get_summarize_column_state(
+        grouping_columns: &Vec<ColumnDefinition>,
+        aggregate_columns: impl Iterator<Item = String>,
+    ) -> ColumnsState {
+        match Merger::get_column_state(grouping_columns) {
+            ColumnsState::Limited { columns } => ColumnsState::Modified {
+                retained: columns,
+                modified: aggregate_columns.collect(),
+            },
+            ColumnsState::Modified {
+                retained,
+                mut modified,
+            } => {
+                modified.extend(aggregate_columns);
+                ColumnsState::Modified { retained, modified }
+            }
+            ColumnsState::Unmodified => unreachable!(),
+        }
+    }
 }
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+
+use kql_parser::{ast as kast, spans::MBox};
+use sql_emitter::ast as sast;
+
+use crate::{
+    aggregates,
+    catalog::{matches_wildcard, ScalarType},
+    merger::Merger,
+    ConverterError,
+};
+
+/// The types of the columns of a table, for the columns whose type is known
+pub type Types = HashMap<String, ScalarType>;
+
+/// Infers the type of an expression from the types of the columns it reads.
+/// The type is unknown when it depends on a column of unknown type.
+pub fn infer(
+    merger: &Merger,
+    types: &Types,
+    expr: &MBox<kast::Expression>,
+) -> Result<Option<ScalarType>, ConverterError> {
+    let data_type = match &*expr.value {
+        kast::Expression::Identifier { name } => types.get(&name.value).copied(),
+        kast::Expression::Literal { value } => Some(literal_type(value)),
+        kast::Expression::FuncCall { name, args, .. } => {
+            let arg_types = args
+                .iter()
+                .map(|arg| infer(merger, types, arg))
+                .collect::<Result<Vec<Option<ScalarType>>, ConverterError>>()?;
+            function_type(merger, &name.value, args, &arg_types)?
+        }
+        kast::Expression::BinaryOp { left, op, right } => {
+            let left_type = infer(merger, types, left)?;
+            let right_type = infer(merger, types, right)?;
+            binary_type(
+                merger,
+                expr,
+                op.value,
+                (left, left_type),
+                (right, right_type),
+            )?
+        }
+        kast::Expression::UnaryOp { op, expr: operand } => {
+            let operand_type = infer(merger, types, operand)?;
+            match (op.value, operand_type) {
+                (kast::UnaryOp::Not, Some(found)) if !is_boolean(found) => {
+                    return Err(merger.unexpected_type(operand.span.clone(), "bool", found))
+                }
+                (kast::UnaryOp::Not, _) => Some(ScalarType::Bool),
+                (_, Some(found)) if !is_numeric(found) && found != ScalarType::Timespan => {
+                    return Err(merger.unexpected_type(operand.span.clone(), "numeric", found))
+                }
+                (_, operand_type) => operand_type,
+            }
+        }
+        kast::Expression::List { items, .. } => {
+            for item in items {
+                infer(merger, types, item)?;
+            }
+            None
+        }
+        kast::Expression::Range { start, end, .. } => {
+            infer(merger, types, start)?;
+            infer(merger, types, end)?;
+            None
+        }
+        kast::Expression::Wildcard => None,
+    };
+    Ok(data_type)
+}
+
+/// The types of the columns an operator returns, or nothing for joins and unions,
+/// whose types also depend on their other tables
+pub fn output_types(
+    merger: &Merger,
+    types: &Types,
+    operator: &kast::TabularOperator,
+) -> Result<Option<Types>, ConverterError> {
+    let mut output = types.clone();
+    match operator {
+        kast::TabularOperator::Count => {
+            output = Types::from([(String::from("Count"), ScalarType::Long)]);
+        }
+        kast::TabularOperator::Distinct {
+            columns: kast::Columns::Explicit(names),
+        } => output.retain(|column, _| names.iter().any(|name| &name.value == column)),
+        kast::TabularOperator::Extend { columns } => {
+            // later columns may use earlier ones
+            for column in columns {
+                set_type(merger, &mut output, column)?;
+            }
+        }
+        kast::TabularOperator::Project { columns } => {
+            output = Types::new();
+            for column in columns {
+                let data_type = match &column.expr {
+                    Some(expr) => infer(merger, types, expr)?,
+                    None => types.get(&column.column.value).copied(),
+                };
+                if let Some(data_type) = data_type {
+                    output.insert(column.column.value.clone(), data_type);
+                }
+            }
+        }
+        kast::TabularOperator::ProjectAway { columns } => output.retain(|column, _| {
+            !columns
+                .iter()
+                .any(|pattern| matches_wildcard(&pattern.value, column))
+        }),
+        kast::TabularOperator::ProjectKeep { columns } => output.retain(|column, _| {
+            columns
+                .iter()
+                .any(|pattern| matches_wildcard(&pattern.value, column))
+        }),
+        kast::TabularOperator::ProjectRename { columns } => {
+            for (new_name, existing) in columns {
+                output.remove(&new_name.value);
+                if let Some(data_type) = types.get(&existing.value) {
+                    output.insert(new_name.value.clone(), *data_type);
+                }
+            }
+            for (_, existing) in columns {
+                if !columns
+                    .iter()
+                    .any(|(new_name, _)| new_name.value == existing.value)
+                {
+                    output.remove(&existing.value);
+                }
+            }
+        }
+        kast::TabularOperator::Summarize {
+            result_columns,
+            grouping_columns,
+            ..
+        } => {
+            let extremum = result_columns.iter().any(|column| {
+                match column.expr.as_ref().map(|expr| &*expr.value) {
+                    Some(kast::Expression::FuncCall { name, .. }) => {
+                        aggregates::is_arg_extremum(&name.value)
+                    }
+                    _ => false,
+                }
+            });
+            // arg_max and arg_min return whole rows
+            if !extremum {
+                output = Types::new();
+            }
+            for column in grouping_columns.iter().chain(result_columns) {
+                let data_type = match &column.expr {
+                    Some(expr) => infer(merger, types, expr)?,
+                    None => types.get(&column.column.value).copied(),
+                };
+                match data_type {
+                    Some(data_type) => output.insert(column.column.value.clone(), data_type),
+                    None => output.remove(&column.column.value),
+                };
+            }
+        }
+        kast::TabularOperator::Top { expr, .. } => {
+            infer(merger, types, expr)?;
+        }
+        kast::TabularOperator::Where { expr } => match infer(merger, types, expr)? {
+            Some(found) if !is_boolean(found) => {
+                return Err(merger.unexpected_type(expr.span.clone(), "bool", found))
+            }
+            _ => {}
+        },
+        kast::TabularOperator::Join { .. } | kast::TabularOperator::Union { .. } => {
+            return Ok(None)
+        }
+        kast::TabularOperator::Distinct { .. }
+        | kast::TabularOperator::Invoke { .. }
+        | kast::TabularOperator::Limit { .. }
+        | kast::TabularOperator::ProjectReorder { .. }
+        | kast::TabularOperator::Sort { .. } => {}
+    }
+    Ok(Some(output))
+}
+
+fn set_type(
+    merger: &Merger,
+    types: &mut Types,
+    column: &kast::ColumnDefinition,
+) -> Result<(), ConverterError> {
+    let data_type = match &column.expr {
+        Some(expr) => infer(merger, types, expr)?,
+        None => types.get(&column.column.value).copied(),
+    };
+    match data_type {
+        Some(data_type) => types.insert(column.column.value.clone(), data_type),
+        None => types.remove(&column.column.value),
+    };
+    Ok(())
+}
+
+/// The SQL type holding values of a KQL type
+pub fn to_data_type(data_type: ScalarType) -> sast::DataType {
+    match data_type {
+        ScalarType::Bool => sast::DataType::Boolean,
+        ScalarType::Int => sast::DataType::Int,
+        ScalarType::Long => sast::DataType::BigInt,
+        ScalarType::Real => sast::DataType::Double,
+        ScalarType::Decimal => sast::DataType::Decimal,
+        ScalarType::String | ScalarType::Guid => sast::DataType::Varchar,
+        ScalarType::DateTime => sast::DataType::Timestamp,
+        ScalarType::Timespan => sast::DataType::Interval,
+        ScalarType::Dynamic => sast::DataType::Json,
+    }
+}
+
+pub fn is_numeric(data_type: ScalarType) -> bool {
+    matches!(
+        data_type,
+        ScalarType::Int | ScalarType::Long | ScalarType::Real | ScalarType::Decimal
+    )
+}
+
+pub fn is_integer(data_type: ScalarType) -> bool {
+    matches!(data_type, ScalarType::Int | ScalarType::Long)
+}
+
+/// Dynamic values may hold a value of any type
+fn is_boolean(data_type: ScalarType) -> bool {
+    matches!(data_type, ScalarType::Bool | ScalarType::Dynamic)
+}
+
+fn literal_type(value: &kast::Literal) -> ScalarType {
+    match value {
+        kast::Literal::Bool(_) => ScalarType::Bool,
+        kast::Literal::Int(_) => ScalarType::Int,
+        kast::Literal::Long(_) => ScalarType::Long,
+        kast::Literal::Real(_) => ScalarType::Real,
+        kast::Literal::String(_) => ScalarType::String,
+        kast::Literal::DateTime(_) => ScalarType::DateTime,
+        kast::Literal::Timespan(_) => ScalarType::Timespan,
+        kast::Literal::Decimal(_) => ScalarType::Decimal,
+        kast::Literal::Dynamic(_) => ScalarType::Dynamic,
+        kast::Literal::Guid(_) => ScalarType::Guid,
+    }
+}
+
+/// Numbers of different types are promoted to the wider type
+fn promote(left: ScalarType, right: ScalarType) -> ScalarType {
+    [ScalarType::Real, ScalarType::Decimal, ScalarType::Long]
+        .into_iter()
+        .find(|wider| left == *wider || right == *wider)
+        .unwrap_or(ScalarType::Int)
+}
+
+fn arithmetic_type(op: kast::BinaryOp, left: ScalarType, right: ScalarType) -> Option<ScalarType> {
+    use kast::BinaryOp::{Add, Div, Mul, Sub};
+    use ScalarType::{DateTime, Dynamic, Real, Timespan};
+
+    match (op, left, right) {
+        (_, Dynamic, _) | (_, _, Dynamic) => Some(Dynamic),
+        (_, left, right) if is_numeric(left) && is_numeric(right) => Some(promote(left, right)),
+        (Add, DateTime, Timespan) | (Add, Timespan, DateTime) | (Sub, DateTime, Timespan) => {
+            Some(DateTime)
+        }
+        (Sub, DateTime, DateTime) | (Add | Sub, Timespan, Timespan) => Some(Timespan),
+        (Mul, Timespan, number) | (Mul, number, Timespan) | (Div, Timespan, number)
+            if is_numeric(number) =>
+        {
+            Some(Timespan)
+        }
+        (Div, Timespan, Timespan) => Some(Real),
+        _ => None,
+    }
+}
+
+fn comparable(left: ScalarType, right: ScalarType) -> bool {
+    left == right
+        || (is_numeric(left) && is_numeric(right))
+        || matches!(
+            (left, right),
+            (ScalarType::Dynamic, _)
+                | (_, ScalarType::Dynamic)
+                | (ScalarType::String, ScalarType::Guid)
+                | (ScalarType::Guid, ScalarType::String)
+        )
+}
+
+fn binary_type(
+    merger: &Merger,
+    expr: &MBox<kast::Expression>,
+    op: kast::BinaryOp,
+    (left, left_type): (&MBox<kast::Expression>, Option<ScalarType>),
+    (right, right_type): (&MBox<kast::Expression>, Option<ScalarType>),
+) -> Result<Option<ScalarType>, ConverterError> {
+    let invalid = |symbol: &str, left: ScalarType, right: ScalarType| {
+        merger.invalid_operand_types(expr.span.clone(), symbol, left, right)
+    };
+
+    match op {
+        kast::BinaryOp::Add
+        | kast::BinaryOp::Sub
+        | kast::BinaryOp::Mul
+        | kast::BinaryOp::Div
+        | kast::BinaryOp::Mod => match (left_type, right_type) {
+            (Some(left_type), Some(right_type)) => {
+                match arithmetic_type(op, left_type, right_type) {
+                    Some(data_type) => Ok(Some(data_type)),
+                    None => Err(invalid(operator_symbol(op), left_type, right_type)),
+                }
+            }
+            _ => Ok(None),
+        },
+        kast::BinaryOp::LogicalAnd | kast::BinaryOp::LogicalOr => {
+            for (side, side_type) in [(left, left_type), (right, right_type)] {
+                if let Some(found) = side_type.filter(|found| !is_boolean(*found)) {
+                    return Err(merger.unexpected_type(side.span.clone(), "bool", found));
+                }
+            }
+            Ok(Some(ScalarType::Bool))
+        }
+        kast::BinaryOp::LT
+        | kast::BinaryOp::GT
+        | kast::BinaryOp::EQ
+        | kast::BinaryOp::NEQ
+        | kast::BinaryOp::LTE
+        | kast::BinaryOp::GTE => match (left_type, right_type) {
+            (Some(left_type), Some(right_type)) if !comparable(left_type, right_type) => {
+                Err(invalid(operator_symbol(op), left_type, right_type))
+            }
+            _ => Ok(Some(ScalarType::Bool)),
+        },
+        kast::BinaryOp::In { .. }
+        | kast::BinaryOp::HasAny
+        | kast::BinaryOp::HasAll
+        | kast::BinaryOp::Between { .. }
+        | kast::BinaryOp::StringPredicate { .. } => Ok(Some(ScalarType::Bool)),
+    }
+}
+
+fn operator_symbol(op: kast::BinaryOp) -> &'static str {
+    match op {
+        kast::BinaryOp::Add => "+",
+        kast::BinaryOp::Sub => "-",
+        kast::BinaryOp::Mul => "*",
+        kast::BinaryOp::Div => "/",
+        kast::BinaryOp::Mod => "%",
+        kast::BinaryOp::LT => "<",
+        kast::BinaryOp::GT => ">",
+        kast::BinaryOp::EQ => "==",
+        kast::BinaryOp::NEQ => "!=",
+        kast::BinaryOp::LTE => "<=",
+        kast::BinaryOp::GTE => ">=",
+        _ => "operator",
+    }
+}
+
+/// The type returned by a scalar or aggregation function
+fn function_type(
+    merger: &Merger,
+    name: &str,
+    args: &[MBox<kast::Expression>],
+    arg_types: &[Option<ScalarType>],
+) -> Result<Option<ScalarType>, ConverterError> {
+    let first = arg_types.first().copied().flatten();
+    let data_type = match name {
+        "strlen" | "indexof" | "count" | "countif" | "dcount" => Some(ScalarType::Long),
+        "tolower" | "toupper" | "reverse" | "strcat" | "replace_string" | "substring"
+        | "extract" | "tostring" => Some(ScalarType::String),
+        "exp" | "log" | "log10" | "pow" | "sqrt" | "avg" | "stdev" | "variance" | "toreal"
+        | "todouble" => Some(ScalarType::Real),
+        "abs" | "ceiling" | "floor" | "round" | "sign" | "bin" | "min" | "max" | "percentile"
+        | "arg_max" | "arg_min" => first,
+        "max_of" | "min_of" | "coalesce" => arg_types.iter().copied().flatten().next(),
+        "sum" | "sumif" => first.map(|data_type| match data_type {
+            ScalarType::Int => ScalarType::Long,
+            data_type => data_type,
+        }),
+        "toint" => Some(ScalarType::Int),
+        "tolong" => Some(ScalarType::Long),
+        "todecimal" => Some(ScalarType::Decimal),
+        "tobool" | "toboolean" | "isnull" | "isnotnull" | "isempty" | "isnotempty" => {
+            Some(ScalarType::Bool)
+        }
+        "todatetime" | "ago" | "now" => Some(ScalarType::DateTime),
+        "parse_json" | "todynamic" | "split" | "make_list" | "make_set" => {
+            Some(ScalarType::Dynamic)
+        }
+        "iff" | "iif" | "case" => {
+            // conditions come before each value, and case ends with the else value
+            let conditions = args.iter().zip(arg_types).step_by(2).take(args.len() / 2);
+            for (condition, condition_type) in conditions {
+                if let Some(found) = condition_type.filter(|found| !is_boolean(*found)) {
+                    return Err(merger.unexpected_type(condition.span.clone(), "bool", found));
+                }
+            }
+            arg_types.get(1).copied().flatten()
+        }
+        _ => None,
+    };
+    Ok(data_type)
+}
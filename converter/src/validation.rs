@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use kql_parser::{
+    ast as kast,
+    spans::{MBox, M},
+    suggestions::closest,
+};
+use miette::NamedSource;
+
+use crate::{merger::Merger, ConverterError};
+
+/// Checks that every column an operator reads is one of its input columns
+pub fn check_operator(
+    merger: &Merger,
+    input: &[String],
+    operator: &kast::TabularOperator,
+) -> Result<(), ConverterError> {
+    let check_name = |name: &M<String>| check_column(merger, input, name);
+    let check_pattern = |pattern: &M<String>| match pattern.value.contains('*') {
+        true => Ok(()),
+        false => check_name(pattern),
+    };
+
+    match operator {
+        kast::TabularOperator::Distinct {
+            columns: kast::Columns::Explicit(names),
+        } => names.iter().try_for_each(check_name),
+        kast::TabularOperator::Extend { columns } | kast::TabularOperator::Project { columns } => {
+            check_definitions(merger, input, columns)
+        }
+        kast::TabularOperator::Join { attributes, .. } => {
+            attributes.iter().try_for_each(|attribute| match attribute {
+                kast::JoinAttribute::Matching { name } => check_name(name),
+                kast::JoinAttribute::NonMatching { left_name, .. } => check_name(left_name),
+            })
+        }
+        kast::TabularOperator::ProjectAway { columns }
+        | kast::TabularOperator::ProjectKeep { columns } => {
+            columns.iter().try_for_each(check_pattern)
+        }
+        kast::TabularOperator::ProjectRename { columns } => columns
+            .iter()
+            .try_for_each(|(_, existing)| check_name(existing)),
+        kast::TabularOperator::ProjectReorder { columns } => columns
+            .iter()
+            .try_for_each(|(pattern, _)| check_pattern(pattern)),
+        kast::TabularOperator::Sort { sortings, .. } => sortings
+            .iter()
+            .try_for_each(|sorting| check_name(&sorting.column)),
+        kast::TabularOperator::Summarize {
+            result_columns,
+            grouping_columns,
+            ..
+        } => {
+            check_definitions(merger, input, grouping_columns)?;
+            result_columns
+                .iter()
+                .filter_map(|column| column.expr.as_ref())
+                .try_for_each(|expr| check_expression(merger, input, expr))
+        }
+        kast::TabularOperator::Top { expr, .. } | kast::TabularOperator::Where { expr } => {
+            check_expression(merger, input, expr)
+        }
+        kast::TabularOperator::Count
+        | kast::TabularOperator::Distinct { .. }
+        | kast::TabularOperator::Invoke { .. }
+        | kast::TabularOperator::Limit { .. }
+        | kast::TabularOperator::Union { .. } => Ok(()),
+    }
+}
+
+/// Columns defined earlier in the same list may be used by later definitions
+fn check_definitions(
+    merger: &Merger,
+    input: &[String],
+    columns: &[kast::ColumnDefinition],
+) -> Result<(), ConverterError> {
+    let mut available = input.to_vec();
+    for column in columns {
+        match &column.expr {
+            Some(expr) => check_expression(merger, &available, expr)?,
+            None => check_column(merger, &available, &column.column)?,
+        }
+        available.push(column.column.value.clone());
+    }
+    Ok(())
+}
+
+pub fn check_expression(
+    merger: &Merger,
+    input: &[String],
+    expr: &MBox<kast::Expression>,
+) -> Result<(), ConverterError> {
+    match &*expr.value {
+        kast::Expression::Identifier { name } => check_column(merger, input, name),
+        kast::Expression::FuncCall { args, .. } => args
+            .iter()
+            .try_for_each(|arg| check_expression(merger, input, arg)),
+        kast::Expression::BinaryOp { left, right, .. } => {
+            check_expression(merger, input, left)?;
+            check_expression(merger, input, right)
+        }
+        kast::Expression::UnaryOp { expr, .. } => check_expression(merger, input, expr),
+        kast::Expression::List { items, .. } => items
+            .iter()
+            .try_for_each(|item| check_expression(merger, input, item)),
+        kast::Expression::Range { start, end, .. } => {
+            check_expression(merger, input, start)?;
+            check_expression(merger, input, end)
+        }
+        kast::Expression::Literal { .. } | kast::Expression::Wildcard => Ok(()),
+    }
+}
+
+pub fn check_column(
+    merger: &Merger,
+    input: &[String],
+    name: &M<String>,
+) -> Result<(), ConverterError> {
+    match input.contains(&name.value) {
+        true => Ok(()),
+        false => Err(merger.unknown_column(name.clone(), input)),
+    }
+}
+
+/// Checks that every table a query reads is either in the catalog or defined by a let
+/// statement. Tables of fuzzy unions may be missing, and patterns are expanded later.
+pub fn check_tables(
+    src: &Arc<NamedSource>,
+    known: &[String],
+    query: &kast::Query,
+) -> Result<(), ConverterError> {
+    match &query.source {
+        kast::Source::Table(table) => check_table(src, known, table)?,
+        kast::Source::Union { params, tables, .. } => check_union(src, known, params, tables)?,
+    }
+
+    query
+        .operators
+        .iter()
+        .try_for_each(|(_, operator)| match operator {
+            kast::TabularOperator::Join { right_table, .. } => {
+                check_tables(src, known, right_table)
+            }
+            kast::TabularOperator::Union { params, tables } => {
+                check_union(src, known, params, tables)
+            }
+            _ => Ok(()),
+        })
+}
+
+fn check_union(
+    src: &Arc<NamedSource>,
+    known: &[String],
+    params: &kast::UnionParams,
+    tables: &[kast::Query],
+) -> Result<(), ConverterError> {
+    if params
+        .is_fuzzy
+        .as_ref()
+        .is_some_and(|is_fuzzy| is_fuzzy.value)
+    {
+        return Ok(());
+    }
+    tables
+        .iter()
+        .try_for_each(|query| check_tables(src, known, query))
+}
+
+fn check_table(
+    src: &Arc<NamedSource>,
+    known: &[String],
+    table: &M<String>,
+) -> Result<(), ConverterError> {
+    if table.value.contains('*') || known.contains(&table.value) {
+        return Ok(());
+    }
+
+    Err(ConverterError::UnknownTable {
+        src: src.clone(),
+        span: table.span.clone(),
+        name: table.value.clone(),
+        help: match closest(&table.value, known.iter().map(String::as_str)) {
+            Some(closest) => format!("did you mean '{}'?", closest),
+            None => String::from("the schema catalog has no table with a similar name"),
+        },
+    })
+}
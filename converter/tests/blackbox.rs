@@ -1,24 +1,61 @@
 use std::fs;
 
-use converter::kql_to_sql;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
 
+use converter::{
+    dialect_by_name, kql_to_sql, kql_to_sql_with_catalog, Ansi, Catalog, Column, Dialect,
+    MemoryCatalog, PostgreSql, ScalarType, SingleStore, Sqlite,
+};
+
+use kql_parser::lexer::tokenize;
+use miette::NamedSource;
 use pretty_assertions::assert_eq;
 
 #[test]
 fn test_all() {
-    let kql_files = fs::read_dir("./tests/conversions")
+    check_conversions("./tests/conversions", None, &SingleStore);
+}
+
+/// Conversions with the schema catalog of `catalog.yaml`
+#[test]
+fn test_all_with_catalog() {
+    check_conversions("./tests/catalog_conversions", Some(catalog()), &SingleStore);
+}
+
+/// Conversions to the PostgreSQL dialect
+#[test]
+fn test_all_postgres() {
+    check_conversions("./tests/postgres_conversions", None, &PostgreSql);
+}
+
+/// Conversions to the SQLite dialect
+#[test]
+fn test_all_sqlite() {
+    check_conversions("./tests/sqlite_conversions", None, &Sqlite);
+}
+
+fn check_conversions(directory: &str, catalog: Option<Arc<MemoryCatalog>>, dialect: &dyn Dialect) {
+    let kql_files = fs::read_dir(directory)
         .unwrap()
         .map(|path| path.unwrap().file_name().into_string().unwrap())
         .filter(|name| name.ends_with(".kql"));
 
     for file_name in kql_files {
         println!("Testing '{}'", file_name);
-        let kql_contents =
-            fs::read_to_string(format!("./tests/conversions/{}", file_name)).unwrap();
+        let kql_contents = fs::read_to_string(format!("{}/{}", directory, file_name)).unwrap();
         let stem = file_name.trim_end_matches(".kql");
-        let sql_contents = fs::read_to_string(format!("./tests/conversions/{}.sql", stem)).unwrap();
+        let sql_contents = fs::read_to_string(format!("{}/{}.sql", directory, stem)).unwrap();
 
-        let result_sql = match kql_to_sql(file_name.clone(), kql_contents) {
+        let result = match &catalog {
+            Some(catalog) => {
+                kql_to_sql_with_catalog(file_name.clone(), kql_contents, catalog.clone(), dialect)
+            }
+            None => kql_to_sql(file_name.clone(), kql_contents, dialect),
+        };
+        let result_sql = match result {
             Ok(output) => output,
             Err(error) => {
                 println!("{}", error);
@@ -29,3 +66,425 @@ fn test_all() {
         assert_eq!(sql_contents, result_sql);
     }
 }
+
+fn catalog() -> Arc<MemoryCatalog> {
+    let yaml = fs::read_to_string("./tests/catalog.yaml").unwrap();
+    Arc::new(MemoryCatalog::from_yaml(&yaml).unwrap())
+}
+
+#[test]
+fn test_unknown_aggregate() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "foobar | summarize hll(a) by b".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Unknown aggregation function 'hll'")),
+    }
+}
+
+#[test]
+fn test_unknown_function() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "foobar | extend b = frobnicate(a)".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Unknown function 'frobnicate'")),
+    }
+}
+
+#[test]
+fn test_index_overflow() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | extend b = substring(a, long(9223372036854775807))".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Invalid argument: the index is too large")),
+    }
+}
+
+#[test]
+fn test_negative_index() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | extend b = substring(a, -1)".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Invalid argument: the index cannot be negative")),
+    }
+}
+
+#[test]
+fn test_invoke_of_scalar_function() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "let f = (T:(*)) { 1 }; foobar | invoke f()".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Function 'f' does not return a tabular value")),
+    }
+}
+
+#[test]
+fn test_function_missing_from_dialect() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | extend Hour = bin(todatetime(Time), 1h)".into(),
+        &Ansi,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert_eq!(
+            error,
+            "The function FROM_UNIXTIME is not supported by the SQL dialect"
+        ),
+    }
+}
+
+#[test]
+fn test_interval_missing_from_dialect() {
+    let result = kql_to_sql("test.kql".into(), "T | extend W = 1h".into(), &Sqlite);
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert_eq!(error, "An interval is not supported by the SQL dialect"),
+    }
+}
+
+#[test]
+fn test_special_real_missing_from_dialect() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | where Ratio < real(+inf)".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert_eq!(
+            error,
+            "The real value Infinity is not supported by the SQL dialect"
+        ),
+    }
+}
+
+#[test]
+fn test_join_with_unknown_columns() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "foobar | join (other) on a".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Cannot determine the columns of the left side")),
+    }
+}
+
+#[test]
+fn test_union_of_unmatched_pattern() {
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "union kind=inner Logs*, Missing* | take 10".into(),
+        catalog(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Cannot resolve the wildcard 'Missing*'")),
+    }
+}
+
+#[test]
+fn test_fuzzy_union_of_missing_tables() {
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "union isfuzzy=true Missing*, Absent".into(),
+        catalog(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("none of the tables of the union exist")),
+    }
+}
+
+#[test]
+fn test_union_without_catalog() {
+    let result = kql_to_sql("test.kql".into(), "union Logs*".into(), &SingleStore);
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("A schema catalog is needed to expand wildcards")),
+    }
+}
+
+#[test]
+fn test_arg_max_wildcard_without_catalog() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | summarize arg_max(Timestamp, *) by Key".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Cannot resolve the wildcard '*'")),
+    }
+}
+
+#[test]
+fn test_project_away_without_catalog() {
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "Weather | project-away Temp*".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Cannot resolve the wildcard 'Temp*'")),
+    }
+}
+
+#[test]
+fn test_unknown_table() {
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "Event | take 5".into(),
+        catalog(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => {
+            assert!(error.contains("Unknown table 'Event'"));
+            assert!(error.contains("did you mean 'Events'?"));
+        }
+    }
+}
+
+#[test]
+fn test_unknown_column() {
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "Events | where Damge > 1".into(),
+        catalog(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => {
+            assert!(error.contains("Unknown column 'Damge'"));
+            assert!(error.contains("did you mean 'Damage'?"));
+        }
+    }
+
+    // the columns of a projection are known without a catalog
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | project a, b | where c > 1".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Unknown column 'c'")),
+    }
+}
+
+#[test]
+fn test_type_errors() {
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "Events | extend Title = Name + Name".into(),
+        catalog(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => {
+            assert!(error.contains("Cannot apply '+' to string and string"));
+            assert!(error.contains("Use strcat to concatenate strings"));
+        }
+    }
+
+    // the types of literals are known without a catalog
+    let result = kql_to_sql(
+        "test.kql".into(),
+        "T | where 1 and a > 2".into(),
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Expected a bool value, found long")),
+    }
+}
+
+#[test]
+fn test_catalog_from_json() {
+    let json = r#"{"tables": [{"name": "T", "columns": [{"name": "a", "type": "long"}]}]}"#;
+    let catalog = MemoryCatalog::from_json(json).unwrap();
+    assert_eq!(
+        catalog.columns("T"),
+        Some(vec![Column::new("a", ScalarType::Long)])
+    );
+
+    let json = r#"{"tables": [{"name": "T", "columns": [{"name": "a", "type": "integer"}]}]}"#;
+    match MemoryCatalog::from_json(json) {
+        Ok(catalog) => panic!("Should not have loaded, got {:?}", catalog),
+        Err(error) => assert!(error.to_string().contains("unknown data type 'integer'")),
+    }
+}
+
+#[test]
+fn test_catalog_from_ddl() {
+    let ddl = fs::read_to_string("./tests/schema.sql").unwrap();
+    let imported = MemoryCatalog::from_ddl(&ddl).unwrap();
+    let expected = catalog();
+    assert_eq!(imported.table_names(), expected.table_names());
+    for table in expected.table_names() {
+        assert_eq!(imported.columns(&table), expected.columns(&table));
+    }
+
+    let ddl = "CREATE TABLE T (\n  a BIGINT,\n  b GEOMETRY\n)";
+    match MemoryCatalog::from_ddl(ddl) {
+        Ok(catalog) => panic!("Should not have loaded, got {:?}", catalog),
+        Err(error) => assert_eq!(
+            error.to_string(),
+            "Invalid SQL schema at line 3: unsupported type 'geometry' of column 'b'"
+        ),
+    }
+}
+
+#[test]
+fn test_bool_columns_from_ddl() {
+    let ddl = "CREATE TABLE T (\n  Flag tinyint(1),\n  Name text\n)";
+    let catalog = Arc::new(MemoryCatalog::from_ddl(ddl).unwrap());
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "T | where not(Flag) | extend Size = iff(Flag, 1, 2)".into(),
+        catalog.clone(),
+        &SingleStore,
+    );
+    assert_eq!(
+        result.unwrap(),
+        "SELECT *, CASE WHEN (Flag = TRUE) THEN 1 ELSE 2 END as Size\nFROM T\nWHERE NOT (Flag = TRUE)\n"
+    );
+
+    let result = kql_to_sql_with_catalog(
+        "test.kql".into(),
+        "T | where Name".into(),
+        catalog,
+        &SingleStore,
+    );
+    match result {
+        Ok(output) => panic!("Should not have converted, got '{}'", output),
+        Err(error) => assert!(error.contains("Expected a bool value, found string")),
+    }
+}
+
+/// Every prefix of every conversion fixture, and every fixture with one word removed
+/// or one token removed, repeated or swapped with the next, must produce either SQL or
+/// an error message in every dialect, with and without a catalog, rather than panicking.
+#[test]
+fn test_no_panics() {
+    let catalog = catalog();
+    let dialects: Vec<Box<dyn Dialect>> = ["singlestore", "postgres", "sqlite", "ansi"]
+        .iter()
+        .map(|name| dialect_by_name(name).unwrap())
+        .collect();
+
+    let kql_files = [
+        "conversions",
+        "catalog_conversions",
+        "postgres_conversions",
+        "sqlite_conversions",
+    ]
+    .iter()
+    .flat_map(|directory| fs::read_dir(format!("./tests/{}", directory)).unwrap())
+    .map(|path| path.unwrap().path())
+    .filter(|path| path.extension() == Some("kql".as_ref()));
+
+    for path in kql_files {
+        let kql = fs::read_to_string(path).unwrap();
+
+        let prefixes = kql
+            .char_indices()
+            .map(|(index, _)| kql[..index].to_string());
+        let words: Vec<&str> = kql.split(' ').collect();
+        let removals = (0..words.len()).map(|skipped| {
+            words
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != skipped)
+                .map(|(_, word)| *word)
+                .collect::<Vec<&str>>()
+                .join(" ")
+        });
+
+        let inputs: Vec<String> = prefixes
+            .chain(removals)
+            .chain(token_mutations(&kql))
+            .collect();
+        for input in inputs {
+            for dialect in &dialects {
+                // a panic leaves nothing behind which the next conversion reads
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    kql_to_sql("test.kql".into(), input.clone(), &**dialect)
+                }));
+                assert!(result.is_ok(), "Converting '{}' panicked", input);
+
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    kql_to_sql_with_catalog(
+                        "test.kql".into(),
+                        input.clone(),
+                        catalog.clone(),
+                        &**dialect,
+                    )
+                }));
+                assert!(
+                    result.is_ok(),
+                    "Converting '{}' with the catalog panicked",
+                    input
+                );
+            }
+        }
+    }
+}
+
+/// The script with each of its tokens removed, repeated, or swapped with the next token
+fn token_mutations(kql: &str) -> Vec<String> {
+    let src = Arc::new(NamedSource::new("test.kql", kql.to_string()));
+    let spans: Vec<(usize, usize)> = match tokenize(src, kql.to_string()) {
+        Ok(tokens) => tokens
+            .iter()
+            .map(|token| (token.span.offset(), token.span.offset() + token.span.len()))
+            .collect(),
+        Err(_) => return vec![],
+    };
+
+    let mut mutations = Vec::new();
+    for (index, &(start, end)) in spans.iter().enumerate() {
+        let token = &kql[start..end];
+        mutations.push(format!("{}{}", &kql[..start], &kql[end..]));
+        mutations.push(format!("{}{} {}", &kql[..end], token, &kql[end..]));
+        if let Some(&(next_start, next_end)) = spans.get(index + 1) {
+            mutations.push(format!(
+                "{}{}{}{}{}",
+                &kql[..start],
+                &kql[next_start..next_end],
+                &kql[end..next_start],
+                token,
+                &kql[next_end..]
+            ));
+        }
+    }
+    mutations
+}
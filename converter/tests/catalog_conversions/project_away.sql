@@ -0,0 +1,2 @@
+SELECT Timestamp, City
+FROM Weather
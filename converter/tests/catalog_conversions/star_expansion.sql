@@ -0,0 +1,10 @@
+SELECT __left.Timestamp, __left.Name, __left.Damage, __left.Details, __right.Timestamp as Timestamp1, __right.City
+FROM (
+    SELECT Timestamp, Name, Damage * 2 as Damage, Details
+    FROM Events
+) as __left
+LEFT OUTER JOIN (
+    SELECT Timestamp, City
+    FROM Weather
+) as __right
+ON (__left.Timestamp = __right.Timestamp)
@@ -0,0 +1,6 @@
+SELECT Name, Timestamp, Damage, Details
+FROM (
+    SELECT *, ROW_NUMBER() OVER (PARTITION BY Name ORDER BY Timestamp DESC) as __row_number
+    FROM Events
+)
+WHERE (__row_number = 1)
@@ -0,0 +1,15 @@
+SELECT Timestamp, Name, Damage, Details, Hour, Level, Ratio, Label, Message
+FROM (
+    SELECT Timestamp, Name, Damage, Details, Hour, Level, Ratio, Label, CAST(NULL AS CHAR) as Message
+    FROM (
+        SELECT *, FROM_UNIXTIME(FLOOR(UNIX_TIMESTAMP(Timestamp) / 3600) * 3600) as Hour, Damage DIV 3 as Level, Damage / 3.0 as Ratio, Name as Label
+        FROM Events
+        WHERE (((Name IS NOT NULL) AND (Name != '')) AND (Damage IS NOT NULL))
+    )
+    UNION ALL
+    SELECT Timestamp, Name, CAST(NULL AS SIGNED) as Damage, CAST(NULL AS JSON) as Details, CAST(NULL AS DATETIME) as Hour, Level, CAST(NULL AS DOUBLE) as Ratio, CAST(NULL AS CHAR) as Label, Message
+    FROM (
+        SELECT *, CAST(Level AS CHAR) as Name
+        FROM LogsWest
+    )
+) as __union
@@ -0,0 +1,9 @@
+SELECT Timestamp, Message
+FROM (
+    SELECT *
+    FROM LogsEast
+    UNION ALL
+    SELECT Timestamp, Message
+    FROM LogsWest
+) as __union
+LIMIT 10
@@ -0,0 +1,3 @@
+SELECT *, CASE WHEN (Large = TRUE) THEN 1 ELSE 2 END as Size
+FROM T
+WHERE (((Flag = TRUE) AND NOT (Archived = TRUE)) AND (TRUE = TRUE))
@@ -0,0 +1,3 @@
+SELECT *, CASE WHEN (DamageProperty > 1000000) THEN 'high' ELSE 'low' END as Severity, CASE WHEN (EventType IS NULL) THEN 'unknown' ELSE EventType END as Kind, CASE WHEN (Deaths = 0) THEN 0 WHEN (Deaths < 10) THEN 1 ELSE 2 END as Band, COALESCE(NULLIF(Source, ''), NULLIF(Reporter, ''), 'none') as Origin, COALESCE(DamageProperty, 0) as Loss
+FROM StormEvents
+WHERE (CASE WHEN (State COLLATE utf8mb4_bin = 'TEXAS' COLLATE utf8mb4_bin) THEN (Injuries > 0) ELSE (Deaths > 0) END = TRUE)
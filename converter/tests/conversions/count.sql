@@ -0,0 +1,3 @@
+SELECT COUNT(*) as Count
+FROM StormEvents
+WHERE (State COLLATE utf8mb4_bin = 'TEXAS' COLLATE utf8mb4_bin)
@@ -0,0 +1,3 @@
+SELECT *, CAST('{"team":"core","tags":["a","it''s"],"limit":2.5}' AS JSON) as Labels
+FROM Deployments
+WHERE ((Version = 12.5) AND (Id != '74be27de-1e4e-49d9-b579-fe0b331d3642'))
@@ -0,0 +1,2 @@
+SELECT DISTINCT State, EventType
+FROM StormEvents
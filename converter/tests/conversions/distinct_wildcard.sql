@@ -0,0 +1,2 @@
+SELECT DISTINCT *, DamageProperty + DamageCrops as Damage
+FROM StormEvents
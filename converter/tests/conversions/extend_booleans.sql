@@ -0,0 +1,2 @@
+SELECT *, ((DamageProperty > 10000) AND (EndTime IS NOT NULL)) as IsBig, EventId % 2 as Parity
+FROM StormEvents
@@ -0,0 +1,6 @@
+SELECT *
+FROM (
+    SELECT *, a * 2 as c
+    FROM foobar
+)
+ORDER BY c DESC
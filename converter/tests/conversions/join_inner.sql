@@ -0,0 +1,10 @@
+SELECT __left.a, __left.b, __right.a as a1, __right.c
+FROM (
+    SELECT a, b
+    FROM foobar
+) as __left
+INNER JOIN (
+    SELECT a, c
+    FROM other
+) as __right
+ON (__left.a = __right.a)
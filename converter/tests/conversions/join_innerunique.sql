@@ -0,0 +1,17 @@
+SELECT __left.a, __left.b, __right.a as a1, __right.c
+FROM (
+    SELECT a, b
+    FROM (
+        SELECT *, ROW_NUMBER() OVER (PARTITION BY a ORDER BY a ASC) as __row_number
+        FROM (
+            SELECT a, b
+            FROM foobar
+        )
+    )
+    WHERE (__row_number = 1)
+) as __left
+INNER JOIN (
+    SELECT a, c
+    FROM other
+) as __right
+ON (__left.a = __right.a)
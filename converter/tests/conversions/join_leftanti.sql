@@ -0,0 +1,7 @@
+SELECT *
+FROM foobar as __left
+WHERE NOT EXISTS (
+    SELECT 1
+    FROM other as __right
+    WHERE (__left.a = __right.a)
+)
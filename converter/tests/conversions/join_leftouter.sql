@@ -0,0 +1,14 @@
+SELECT *
+FROM (
+    SELECT __left.a, __left.b, __right.a as a1, __right.b as b1, __right.b1 as b11
+    FROM (
+        SELECT a, b
+        FROM foobar
+    ) as __left
+    LEFT OUTER JOIN (
+        SELECT a, b, b1
+        FROM other
+    ) as __right
+    ON (__left.b = __right.a)
+)
+WHERE (b1 > 0)
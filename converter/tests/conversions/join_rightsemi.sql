@@ -0,0 +1,15 @@
+SELECT *
+FROM (
+    SELECT *
+    FROM other
+    LIMIT 10
+) as __right
+WHERE EXISTS (
+    SELECT 1
+    FROM (
+        SELECT *
+        FROM foobar
+        WHERE (a > 1)
+    ) as __left
+    WHERE ((__left.a = __right.a) AND (__left.b = __right.b))
+)
@@ -0,0 +1,8 @@
+WITH Recent AS (
+    SELECT *
+    FROM Events
+    WHERE (Damage > 100)
+)
+SELECT Name, Damage, 100 as threshold
+FROM Recent
+LIMIT 5
@@ -0,0 +1,7 @@
+SELECT *
+FROM (
+    SELECT *
+    FROM foobar
+    LIMIT 10
+)
+ORDER BY a ASC
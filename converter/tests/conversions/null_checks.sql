@@ -0,0 +1,3 @@
+SELECT *, CAST(NULL AS SIGNED) as Missing, CAST(NULL AS JSON) as Payload
+FROM StormEvents
+WHERE ((EndTime IS NULL) OR ((State IS NOT NULL) AND (State != '')))
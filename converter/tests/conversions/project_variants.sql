@@ -0,0 +1,13 @@
+SELECT EndTime, Length
+FROM (
+    SELECT *, CHAR_LENGTH(Event) as Length
+    FROM (
+        SELECT EndTime, Event, Timestamp
+        FROM (
+            SELECT Timestamp, Name as Event, EndTime
+            FROM Events
+            WHERE (Name COLLATE utf8mb4_bin != 'test' COLLATE utf8mb4_bin)
+        )
+        WHERE (Event COLLATE utf8mb4_bin != 'noise' COLLATE utf8mb4_bin)
+    )
+)
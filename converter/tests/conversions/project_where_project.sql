@@ -1,4 +1,4 @@
-SELECT max(a, b) + 2.5 + 1 * 2 as c, b
+SELECT GREATEST(a, b) + 2.5 + 1 * 2 as c, b
 FROM (
     SELECT a, 2 as b
     FROM foobar
@@ -0,0 +1,3 @@
+SELECT `Event Type`, EventId as `Key`, `order`
+FROM `Storm Events`
+WHERE (`Event Type` COLLATE utf8mb4_bin = 'It''s a "storm" \\ here' COLLATE utf8mb4_bin)
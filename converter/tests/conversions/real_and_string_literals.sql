@@ -0,0 +1,3 @@
+SELECT *, 'secreté	value' as Label, 1e300 as Huge, 0.12345678901234568 as Precise
+FROM Metrics
+WHERE ((Ratio > 0.0015) AND (Scale != 0.5))
@@ -0,0 +1,6 @@
+SELECT State, GREATEST(MAX(Injuries), 1) as Longest
+FROM (
+    SELECT *, CHAR_LENGTH(Source) as Length, LOWER(State) as Lower, SUBSTRING(EventType, 1, 3) as Prefix, SUBSTRING(EventType, GREATEST(Start, 0) + 1) as Rest, CONCAT(State, '-', CAST(Deaths AS CHAR)) as Label, CAST(TRUNCATE(DamageProperty, 0) AS SIGNED) + CAST(TRUNCATE(DamageCrops, 0) AS SIGNED) as Total, LOCATE('A', State) - 1 as Position, SPLIT(Source, ',') as Parts, REGEXP_SUBSTR(EpisodeNarrative, '[0-9]+') as Code, CAST(Payload AS JSON) as Details, ROUND(CAST(DamageProperty AS DOUBLE) / 1000, 2) as Cost
+    FROM StormEvents
+)
+GROUP BY State
@@ -0,0 +1,3 @@
+SELECT *
+FROM StormEvents
+WHERE (((((((State COLLATE utf8mb4_bin IN ('TEXAS' COLLATE utf8mb4_bin, 'KANSAS' COLLATE utf8mb4_bin)) AND (LOWER(EventType) NOT IN ('hail', LOWER(Kind)))) AND (Source COLLATE utf8mb4_bin IN ('Radar' COLLATE utf8mb4_bin, 1, CAST(NULL AS JSON)))) AND (Damage BETWEEN 1000 AND 5000)) AND (Deaths NOT BETWEEN 1 AND 10)) AND ((LOWER(EpisodeNarrative) RLIKE '(^|[^[:alnum:]])tornado($|[^[:alnum:]])') OR (LOWER(EpisodeNarrative) RLIKE '(^|[^[:alnum:]])funnel($|[^[:alnum:]])'))) AND ((LOWER(EventNarrative) RLIKE '(^|[^[:alnum:]])flood($|[^[:alnum:]])') AND (LOWER(EventNarrative) RLIKE '(^|[^[:alnum:]])50%($|[^[:alnum:]])')))
@@ -0,0 +1,3 @@
+SELECT *
+FROM foobar
+ORDER BY a DESC, b ASC
@@ -0,0 +1,4 @@
+SELECT *
+FROM foobar
+ORDER BY a DESC
+LIMIT 10
@@ -0,0 +1,3 @@
+SELECT *
+FROM Logs
+WHERE (((((((LOWER(Message) RLIKE '(^|[^[:alnum:]])error($|[^[:alnum:]])') AND (Message COLLATE utf8mb4_bin NOT LIKE '%50\\%\\_done%')) AND (LOCATE(LOWER(Prefix), LOWER(Source)) = 1)) AND (Path COLLATE utf8mb4_bin LIKE '%.rs')) AND (LOWER(Level) = LOWER('WARN'))) AND (Host RLIKE '^web-[0-9]+$')) AND (Region NOT ILIKE 'us%'))
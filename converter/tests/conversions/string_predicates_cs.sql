@@ -0,0 +1,3 @@
+SELECT *
+FROM Files
+WHERE ((((((LOCATE(Prefix COLLATE utf8mb4_bin, Name COLLATE utf8mb4_bin) = 1) AND (RIGHT(Name COLLATE utf8mb4_bin, CHAR_LENGTH(Suffix COLLATE utf8mb4_bin)) = Suffix COLLATE utf8mb4_bin)) AND (Name COLLATE utf8mb4_bin NOT RLIKE '(^|[^[:alnum:]])Draft($|[^[:alnum:]])')) AND (Kind COLLATE utf8mb4_bin = 'Report' COLLATE utf8mb4_bin)) AND (Owner COLLATE utf8mb4_bin IN ('Admin' COLLATE utf8mb4_bin, Creator COLLATE utf8mb4_bin))) AND (Owner COLLATE utf8mb4_bin LIKE '%Admin%'))
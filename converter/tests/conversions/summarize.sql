@@ -0,0 +1,3 @@
+SELECT fruit, COUNT(*) as count_, SUM(price) as Total
+FROM foobar
+GROUP BY fruit
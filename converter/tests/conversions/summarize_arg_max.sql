@@ -0,0 +1,6 @@
+SELECT store, price, fruit, qty
+FROM (
+    SELECT *, ROW_NUMBER() OVER (PARTITION BY store ORDER BY price DESC) as __row_number
+    FROM foobar
+)
+WHERE (__row_number = 1)
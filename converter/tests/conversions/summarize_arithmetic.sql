@@ -0,0 +1,6 @@
+SELECT FLOOR((d - 1) / 5) * 5 as Column1, SUM(c) / COUNT(*) as total
+FROM (
+    SELECT *, (a + b) * 2 as c
+    FROM foobar
+)
+GROUP BY FLOOR((d - 1) / 5) * 5
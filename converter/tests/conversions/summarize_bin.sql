@@ -0,0 +1,8 @@
+SELECT *
+FROM (
+    SELECT FLOOR(size / 10) * 10 as size, AVG(price) as avg_price, JSON_AGG(DISTINCT fruit) as set_fruit
+    FROM foobar
+    WHERE (qty > 0)
+    GROUP BY FLOOR(size / 10) * 10
+)
+WHERE (avg_price > 2)
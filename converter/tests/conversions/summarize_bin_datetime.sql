@@ -0,0 +1,3 @@
+SELECT FROM_UNIXTIME(FLOOR(UNIX_TIMESTAMP(Timestamp) / 3600) * 3600) as Timestamp, COUNT(*) as count_
+FROM T
+GROUP BY FROM_UNIXTIME(FLOOR(UNIX_TIMESTAMP(Timestamp) / 3600) * 3600)
@@ -0,0 +1,2 @@
+SELECT COUNT(CASE WHEN (price > 10) THEN 1 END) as countif_, SUM(CASE WHEN (qty >= 2) THEN price END) as sumif_price, COUNT(DISTINCT fruit) as dcount_fruit, PERCENTILE_DISC(0.95) WITHIN GROUP (ORDER BY price ASC) as percentile_price_95
+FROM foobar
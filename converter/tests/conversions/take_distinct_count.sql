@@ -0,0 +1,10 @@
+SELECT COUNT(*) as Count
+FROM (
+    SELECT DISTINCT State
+    FROM (
+        SELECT *
+        FROM StormEvents
+        LIMIT 10
+    )
+    WHERE (State COLLATE utf8mb4_bin = 'TEXAS' COLLATE utf8mb4_bin)
+)
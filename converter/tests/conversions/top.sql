@@ -0,0 +1,4 @@
+SELECT *
+FROM StormEvents
+ORDER BY (DamageProperty + DamageCrops IS NULL) ASC, DamageProperty + DamageCrops ASC
+LIMIT 3
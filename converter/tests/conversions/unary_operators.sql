@@ -0,0 +1,3 @@
+SELECT *, -(DamageProperty + DamageCrops) as Loss, NOT (BeginLat > 0) as Valid
+FROM StormEvents
+WHERE (NOT ((State COLLATE utf8mb4_bin = 'TEXAS' COLLATE utf8mb4_bin) OR (EndTime IS NULL)) AND (DamageProperty - 1 > -DamageCrops))
@@ -0,0 +1,20 @@
+SELECT x, y
+FROM (
+    SELECT x, y
+    FROM (
+        SELECT 'A' as Source, x, y, NULL as z
+        FROM A
+        UNION ALL
+        SELECT 'B' as Source, x, NULL as y, z
+        FROM B
+        WHERE (x > 0)
+    ) as __union
+    WHERE (x > 1)
+    UNION ALL
+    SELECT x, y
+    FROM (
+        SELECT x, y
+        FROM C
+        LIMIT 5
+    )
+) as __union
@@ -0,0 +1,6 @@
+SELECT *, Damage * x as Score, factor * 2 as Doubled
+FROM (
+    SELECT Name, Damage, x, factor
+    FROM Events
+    WHERE (Damage > 5 * 10)
+)
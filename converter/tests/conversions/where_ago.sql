@@ -0,0 +1,3 @@
+SELECT *
+FROM StormEvents
+WHERE (StartTime > CURRENT_TIMESTAMP - INTERVAL '1' HOUR)
@@ -0,0 +1,3 @@
+SELECT *, "EndTime" + INTERVAL '36' HOUR as "Deadline", INTERVAL '0.250' SECOND as "Window", CURRENT_TIMESTAMP + INTERVAL '-1' DAY as "Yesterday"
+FROM "StormEvents"
+WHERE (("StartTime" >= TIMESTAMP '2007-01-01 00:00:00') AND ("EndTime" < TIMESTAMP '2007-06-30 12:30:00.500'))
@@ -0,0 +1,2 @@
+SELECT *, CAST(TRUNC(1.5) AS BIGINT) as "Whole", CAST(TRUNC("Ratio" * 10) AS INT) as "Tenths", SUBSTRING("Name", GREATEST("Offset", 0) + 1) as "Tail"
+FROM "Metrics"
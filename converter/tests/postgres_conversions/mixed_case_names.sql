@@ -0,0 +1,3 @@
+SELECT "State", "EventId", "EndTime" - "StartTime" as duration
+FROM "StormEvents"
+WHERE ("EndTime" > "StartTime")
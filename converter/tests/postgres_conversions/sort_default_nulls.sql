@@ -0,0 +1,4 @@
+SELECT *
+FROM "StormEvents"
+ORDER BY "State" ASC NULLS FIRST, "DamageProperty" DESC NULLS LAST, "EventId" DESC NULLS LAST
+LIMIT 10
@@ -0,0 +1,3 @@
+SELECT *, CAST('NaN' AS DOUBLE PRECISION) as "Missing"
+FROM "Metrics"
+WHERE (("Ratio" < CAST('Infinity' AS DOUBLE PRECISION)) AND ("Ratio" > CAST('-Infinity' AS DOUBLE PRECISION)))
@@ -0,0 +1,33 @@
+-- Output of SHOW CREATE TABLE for the tables of catalog.yaml
+CREATE DATABASE IF NOT EXISTS game;
+USE game;
+
+CREATE TABLE `Events` (
+  `Timestamp` datetime(6) DEFAULT NULL,
+  `Name` varchar(255) CHARACTER SET utf8 COLLATE utf8_general_ci NOT NULL DEFAULT 'unknown, yet',
+  `Damage` bigint(20) NOT NULL,
+  `Details` JSON COLLATE utf8_bin,
+  SHARD KEY `__SHARDKEY` (`Name`),
+  SORT KEY `Timestamp` (`Timestamp`)
+) AUTOSTATS_CARDINALITY_MODE=INCREMENTAL AUTOSTATS_HISTOGRAM_MODE=CREATE SQL_MODE='STRICT_ALL_TABLES';
+
+CREATE ROWSTORE TABLE IF NOT EXISTS game.`LogsEast` (
+  `Timestamp` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  `Message` text,
+  PRIMARY KEY (`Timestamp`)
+);
+
+/* computed columns take the type after PERSISTED */
+CREATE TABLE `LogsWest` (
+  `Timestamp` datetime(6),
+  `Message` longtext,
+  `Level` int(11) AS (LENGTH(`Message`)) PERSISTED int(11),
+  KEY (`Level`) USING HASH
+);
+
+CREATE TABLE Weather (
+  Timestamp DATETIME(6),
+  TempMin DOUBLE,
+  TempMax DOUBLE,
+  City VARCHAR(100)
+);
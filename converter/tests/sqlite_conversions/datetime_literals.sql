@@ -0,0 +1,3 @@
+SELECT *, DATETIME(EndTime, '+36 hours') as Deadline, DATETIME(StartTime, '-0.250 seconds') as Early
+FROM StormEvents
+WHERE (((StartTime >= '2007-01-01 00:00:00') AND (EndTime < '2007-06-30 12:30:00.500')) AND (StartTime > DATETIME(CURRENT_TIMESTAMP, '-1 hours')))
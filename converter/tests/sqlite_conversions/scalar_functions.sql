@@ -0,0 +1,3 @@
+SELECT *, DATETIME(FLOOR(CAST(STRFTIME('%s', CAST(Time AS TEXT)) AS INTEGER) / 3600) * 3600, 'unixepoch') as Hour, INSTR(Name, '.') - 1 as Position
+FROM Logs
+WHERE (((INSTR(Name, Prefix) = 1) AND (SUBSTR(Name, LENGTH(Name) - (LENGTH(Suffix)) + 1) = Suffix)) AND NOT (INSTR(Name, Part) > 0))
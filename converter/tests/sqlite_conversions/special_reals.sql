@@ -0,0 +1,3 @@
+SELECT *
+FROM Metrics
+WHERE ((Ratio < 9e999) AND (Ratio > -9e999))
@@ -0,0 +1,3 @@
+SELECT *
+FROM Logs
+WHERE (((Path GLOB '*.rs') AND (Message NOT GLOB '*50%_done [[][*]]*')) AND (Host LIKE 'web%' ESCAPE '\'))
@@ -1,3 +1,7 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+
 use crate::spans::{MBox, Span, M};
 
 /// Represents scalar, aggregate, and group expressions
@@ -17,9 +21,27 @@ pub enum Expression {
         op: M<BinaryOp>,
         right: MBox<Expression>,
     },
+    UnaryOp {
+        op: M<UnaryOp>,
+        expr: MBox<Expression>,
+    },
     Literal {
         value: Literal,
     },
+    /// A parenthesized list "(a, b, c)", the right side of "in", "has_any" and "has_all"
+    List {
+        open_paren_sym: Span,
+        items: Vec<MBox<Expression>>,
+        close_paren_sym: Span,
+    },
+    /// A range "(start .. end)", the right side of "between"
+    Range {
+        start: MBox<Expression>,
+        dot_dot_sym: Span,
+        end: MBox<Expression>,
+    },
+    /// "*" as a function argument, standing for all the columns, as in "arg_max(Price, *)"
+    Wildcard,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -52,6 +74,48 @@ pub enum BinaryOp {
     LTE,
     /// Greater Than or Equal ">="
     GTE,
+
+    /// Set membership "in", "!in", "in~" and "!in~"
+    In { case_sensitive: bool, negated: bool },
+    /// "has_any", which matches any term of a list
+    HasAny,
+    /// "has_all", which matches every term of a list
+    HasAll,
+    /// Range check "between" and "!between", inclusive at both ends
+    Between { negated: bool },
+
+    /// String predicate such as "has", "!contains_cs", "matches regex" or "=~"
+    StringPredicate {
+        predicate: StringPredicate,
+        case_sensitive: bool,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StringPredicate {
+    /// "=~" and "!~", the case-sensitive forms are the "==" and "!=" operators
+    Equals,
+    /// "has", which matches a whole term
+    Has,
+    /// "contains"
+    Contains,
+    /// "startswith"
+    StartsWith,
+    /// "endswith"
+    EndsWith,
+    /// "matches regex"
+    MatchesRegex,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOp {
+    /// Negate "-"
+    Neg,
+    /// Plus "+"
+    Plus,
+    /// Logical Not "not(...)"
+    Not,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -71,4 +135,82 @@ pub enum Literal {
     /// A literal string value
     /// There is no "null" string value
     String(String),
+    /// A literal datetime value, in UTC
+    /// "null" value represented by `None`
+    DateTime(Option<NaiveDateTime>),
+    /// A literal timespan value, in ticks of 100 nanoseconds
+    /// "null" value represented by `None`
+    Timespan(Option<i64>),
+    /// A literal decimal value, as its exact digits
+    /// "null" value represented by `None`
+    Decimal(Option<String>),
+    /// A literal dynamic value
+    /// "null" value represented by `Dynamic::Null`
+    Dynamic(Dynamic),
+    /// A literal guid value, in lowercase
+    /// "null" value represented by `None`
+    Guid(Option<String>),
+}
+
+/// The value tree of a `dynamic` literal
+#[derive(Debug, PartialEq, Clone)]
+pub enum Dynamic {
+    Null,
+    Bool(bool),
+    Long(i64),
+    Real(f64),
+    String(String),
+    Array(Vec<Dynamic>),
+    /// A property bag, with properties in the order they were written
+    Bag(Vec<(String, Dynamic)>),
+}
+
+/// Displays the value as JSON
+impl fmt::Display for Dynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dynamic::Null => write!(f, "null"),
+            Dynamic::Bool(v) => write!(f, "{}", v),
+            Dynamic::Long(v) => write!(f, "{}", v),
+            Dynamic::Real(v) => write!(f, "{:?}", v),
+            Dynamic::String(v) => write_json_string(f, v),
+            Dynamic::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Dynamic::Bag(properties) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in properties.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
 }
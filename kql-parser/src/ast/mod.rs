@@ -1,5 +1,7 @@
 pub mod expression;
 pub mod query;
+pub mod script;
 
 pub use expression::*;
 pub use query::*;
+pub use script::*;
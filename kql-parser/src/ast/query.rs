@@ -3,14 +3,47 @@ use crate::spans::{MBox, Span, M};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Query {
-    /// The base table value to start with.
-    pub table: M<String>,
+    /// The tabular data to start with.
+    pub source: Source,
     /// The tabular operators to apply to it.
     /// The first field is the name used, which may differ from the operator name.
     /// (e.g. name may be "take" for operator "limit")
     pub operators: Vec<(M<String>, TabularOperator)>,
 }
 
+impl Query {
+    /// The table the query starts with, unless it starts with a union
+    pub fn table(&self) -> Option<&M<String>> {
+        match &self.source {
+            Source::Table(table) => Some(table),
+            Source::Union { .. } => None,
+        }
+    }
+
+    /// Span of the table or union the query starts with
+    pub fn source_span(&self) -> Span {
+        match &self.source {
+            Source::Table(table) => table.span.clone(),
+            Source::Union { union_kwd, .. } => union_kwd.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Source {
+    /// A table name, which may contain wildcards inside a union (e.g. `Events*`)
+    Table(M<String>),
+    /// A leading union of tables (e.g. `union T1, T2`)
+    Union {
+        /// Span for union keyword
+        union_kwd: Span,
+        /// The union parameters
+        params: UnionParams,
+        /// The tables to combine
+        tables: Vec<Query>,
+    },
+}
+
 /// The logically distinct operators (aliases are not included).
 ///
 /// Aliases:
@@ -27,6 +60,12 @@ pub enum TabularOperator {
         /// New columns to define
         columns: Vec<ColumnDefinition>,
     },
+    Invoke {
+        /// The tabular function, which receives the input as its first argument
+        name: M<String>,
+        /// The remaining arguments
+        args: Vec<MBox<Expression>>,
+    },
     Join {
         /// The join parameters
         params: JoinParams,
@@ -43,15 +82,41 @@ pub enum TabularOperator {
         /// Columns to retain and new columns to define
         columns: Vec<ColumnDefinition>,
     },
+    ProjectAway {
+        /// Columns to remove, which may contain wildcards (e.g. `Temp*`)
+        columns: Vec<M<String>>,
+    },
+    ProjectKeep {
+        /// Columns to retain, which may contain wildcards
+        columns: Vec<M<String>>,
+    },
+    ProjectRename {
+        /// Pairs of (new name, existing name)
+        columns: Vec<(M<String>, M<String>)>,
+    },
+    ProjectReorder {
+        /// Columns to put first, which may contain wildcards.
+        /// Columns matching a wildcard are sorted by name when an order is given.
+        columns: Vec<(M<String>, Option<M<SortOrder>>)>,
+    },
     Sort {
         /// Span for by keyword
         by_kwd: Span,
         /// Sortings to be performed
         sortings: Vec<Sorting>,
     },
+    Union {
+        /// The union parameters
+        params: UnionParams,
+        /// The other tables to combine with the input
+        tables: Vec<Query>,
+    },
     Summarize {
+        /// Aggregations to compute
         result_columns: Vec<ColumnDefinition>,
-        by_kwd: Span,
+        /// Span for by keyword, if there is a grouping
+        by_kwd: Option<Span>,
+        /// Columns to group by
         grouping_columns: Vec<ColumnDefinition>,
     },
     Top {
@@ -78,6 +143,26 @@ pub enum Columns {
     Wildcard(Span),
 }
 
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct UnionParams {
+    /// Which columns to keep, `kind=outer` by default
+    pub kind: Option<M<UnionKind>>,
+    /// Name of a column holding the table each row comes from, `withsource=Column`
+    pub with_source: Option<M<String>>,
+    /// Whether tables which do not exist are ignored, `isfuzzy=true`
+    pub is_fuzzy: Option<M<bool>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnionKind {
+    /// Keep only the columns found in every table
+    /// Name "inner"
+    Inner,
+    /// Keep every column, filling in nulls for tables without it
+    /// Name "outer"
+    Outer,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct JoinParams {
     pub kind: Option<JoinKind>,
@@ -0,0 +1,71 @@
+use crate::ast::expression::Expression;
+use crate::ast::query::Query;
+use crate::spans::{MBox, Span, M};
+
+/// A sequence of statements separated by semicolons
+#[derive(Debug, PartialEq, Clone)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    /// Binds a name for use by the statements which follow
+    Let {
+        /// Span for let keyword
+        let_kwd: Span,
+        /// The name being bound
+        name: M<String>,
+        /// The value bound to the name
+        value: LetValue,
+    },
+    /// A tabular expression whose results are returned
+    Query(Query),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LetValue {
+    /// A scalar expression (e.g. `let threshold = 100`)
+    Scalar(MBox<Expression>),
+    /// A tabular expression (e.g. `let Recent = Events | where Timestamp > ago(1d)`)
+    Tabular(Query),
+    /// A user-defined function (e.g. `let add = (a:long, b:long = 1) { a + b }`)
+    Function(Function),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub open_paren_sym: Span,
+    pub params: Vec<Parameter>,
+    pub close_paren_sym: Span,
+    pub body: FunctionBody,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Parameter {
+    pub name: M<String>,
+    pub kind: ParameterKind,
+    /// The value used when no argument is passed
+    pub default: Option<MBox<Expression>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParameterKind {
+    /// A scalar of the named type (e.g. `a:long`)
+    Scalar { data_type: M<String> },
+    /// A table with at least the listed columns (e.g. `T:(Name:string)`), or with any
+    /// columns for `T:(*)`
+    Tabular {
+        columns: Option<Vec<(M<String>, M<String>)>>,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionBody {
+    pub open_brace_sym: Span,
+    /// Let statements local to the body
+    pub statements: Vec<Statement>,
+    /// The value the function returns
+    pub value: Box<LetValue>,
+    pub close_brace_sym: Span,
+}
@@ -1,8 +1,12 @@
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
 use logos::Logos;
 
-use crate::spans::{Span, M};
+use crate::{
+    ast::expression::Dynamic,
+    spans::{Span, M},
+};
 use miette::{Diagnostic, NamedSource};
 use thiserror::Error;
 
@@ -67,13 +71,33 @@ pub enum Token {
     #[token("bool(null)")]
     BoolNullLiteral,
 
-    // TODO datetime: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/datetime
+    /// `datetime` literal: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/datetime
+    #[regex(r"datetime\([^)]*\)", |lex| parse_datetime_literal(lex.slice(), 9, 1))]
+    DateTimeLiteral(NaiveDateTime),
 
-    // TODO decimal: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/decimal
+    #[token("datetime(null)")]
+    DateTimeNullLiteral,
 
-    // TODO dynamic: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/dynamic
+    /// `decimal` literal, holding the exact digits of the number:
+    /// https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/decimal
+    #[regex(r"decimal\(-?[0-9]+(\.[0-9]+)?([eE][+-]?[0-9]+)?\)", |lex| parse_decimal_literal(lex.slice(), 8, 1))]
+    DecimalLiteral(String),
+
+    #[token("decimal(null)")]
+    DecimalNullLiteral,
+
+    /// `dynamic` literal, including `dynamic(null)`:
+    /// https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/dynamic
+    #[token("dynamic(", parse_dynamic_literal)]
+    DynamicLiteral(Dynamic),
+
+    /// `guid` literal: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/guid
+    #[regex(r"guid\([0-9a-fA-F-]+\)", |lex| parse_guid_literal(lex.slice(), 5, 1))]
+    GuidLiteral(String),
+
+    #[token("guid(null)")]
+    GuidNullLiteral,
 
-    // Intentionally Omitting guid type
     /// `int` literal
     #[regex(r"int\(-?[0-9]+\)", |lex| parse_int_dec_literal(lex.slice(), 4, 1))]
     #[regex(r"int\(0x[0-9a-fA-F][0-9a-fA-F]*\)", |lex| parse_int_hex_literal(lex.slice(), 4, 1))]
@@ -83,34 +107,52 @@ pub enum Token {
     IntNullLiteral,
 
     /// `long` literal
-    #[regex(r"-?[0-9]+", |lex| parse_long_dec_literal(lex.slice(), 0, 0))]
+    #[regex(r"[0-9]+", |lex| parse_long_dec_literal(lex.slice(), 0, 0))]
     #[regex(r"0x[0-9a-fA-F][0-9a-fA-F]*", |lex| parse_long_hex_literal(lex.slice(), 0, 0))]
     #[regex(r"long\(-?[0-9]+\)", |lex| parse_long_dec_literal(lex.slice(), 5, 1))]
     #[regex(r"long\(0x[0-9a-fA-F][0-9a-fA-F]*\)", |lex| parse_long_hex_literal(lex.slice(), 5, 1))]
     LongLiteral(i64),
 
-    #[token(r"long\(null\)")]
+    #[token("long(null)")]
     LongNullLiteral,
 
     /// `real` literal
-    #[regex(r"[0-9]+\.[0-9]+", |lex| lex.slice().parse())] // TODO: exp notation
-    #[token(r"real\(nan\)", |_| f64::NAN)]
-    #[token(r"real\(+inf\)", |_| f64::INFINITY)]
-    #[token(r"real\(-inf\)", |_| f64::NEG_INFINITY)]
+    #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?", |lex| lex.slice().parse())]
+    #[regex(r"[0-9]+[eE][+-]?[0-9]+", |lex| lex.slice().parse())]
+    #[regex(r"\.[0-9]+([eE][+-]?[0-9]+)?", |lex| lex.slice().parse())]
+    #[regex(r"real\([^)]*\)", |lex| parse_real_literal(lex.slice(), 5, 1))]
     RealLiteral(f64),
 
-    #[token(r"real\(null\)")]
+    #[token("real(null)")]
     RealNullLiteral,
 
     /// `string` literal
+    /// The `h` or `H` prefix marks an obfuscated string, which only affects logging
     #[token("\"", |lex| parse_string_literal('"', lex))]
     #[token("\'", |lex| parse_string_literal('\'', lex))]
+    #[token("h\"", |lex| parse_string_literal('"', lex))]
+    #[token("h\'", |lex| parse_string_literal('\'', lex))]
+    #[token("H\"", |lex| parse_string_literal('"', lex))]
+    #[token("H\'", |lex| parse_string_literal('\'', lex))]
     #[token("@\"", |lex| parse_verbatim_string_literal('"', lex))]
     #[token("@\'", |lex| parse_verbatim_string_literal('\'', lex))]
+    #[token("h@\"", |lex| parse_verbatim_string_literal('"', lex))]
+    #[token("h@\'", |lex| parse_verbatim_string_literal('\'', lex))]
+    #[token("H@\"", |lex| parse_verbatim_string_literal('"', lex))]
+    #[token("H@\'", |lex| parse_verbatim_string_literal('\'', lex))]
     #[token("```", |lex| parse_multiline_string_literal(lex))]
     StringLiteral(String),
 
-    // TODO timespan: https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/timespan
+    /// `timespan` literal, measured in ticks of 100 nanoseconds:
+    /// https://docs.microsoft.com/en-us/azure/data-explorer/kusto/query/scalar-data-types/timespan
+    #[regex(r"[0-9]+(\.[0-9]+)?(d|days?|h|hrs?|hours?|m|min|minutes?|s|sec|seconds?|ms|millis?|milliseconds?|microseconds?|ticks?)", |lex| parse_timespan(lex.slice()))]
+    #[regex(r"time\([^)]*\)", |lex| parse_timespan_literal(lex.slice(), 5, 1))]
+    #[regex(r"timespan\([^)]*\)", |lex| parse_timespan_literal(lex.slice(), 9, 1))]
+    TimespanLiteral(i64),
+
+    #[token("time(null)")]
+    #[token("timespan(null)")]
+    TimespanNullLiteral,
 
     // Symbols -----------------------------------------
     /// Pipe Symbol "|"
@@ -145,10 +187,22 @@ pub enum Token {
     #[token(",")]
     Comma,
 
+    /// The Type Annotation Delimiter ":"
+    #[token(":")]
+    Colon,
+
+    /// The Statement Delimiter ";"
+    #[token(";")]
+    Semicolon,
+
     /// The Period or Dot Operator "."
     #[token(".")]
     Dot,
 
+    /// The Range Operator ".."
+    #[token("..")]
+    DotDot,
+
     /// Assignment Operator "="
     #[token("=")]
     Assign,
@@ -204,6 +258,82 @@ pub enum Token {
     // Not Equals Operator "!="
     #[token("!=")]
     NEQ,
+
+    /// Case-insensitive Equals Operator "=~"
+    #[token("=~")]
+    EQTilde,
+
+    /// Case-insensitive Not Equals Operator "!~"
+    #[token("!~")]
+    NEQTilde,
+
+    /// Case-insensitive Set Membership Operator "in~"
+    #[token("in~")]
+    InTilde,
+
+    /// Case-insensitive Set Non-membership Operator "!in~"
+    #[token("!in~")]
+    NotInTilde,
+}
+
+/// Describes a token the way it would be referred to in an error message
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Error => write!(f, "unrecognized text"),
+            Token::Term(name) => write!(f, "term '{}'", name),
+            Token::BangTerm(name) => write!(f, "'!{}'", name),
+            Token::DollarTerm(name) => write!(f, "'${}'", name),
+            Token::BoolLiteral(value) => write!(f, "bool literal {}", value),
+            Token::IntLiteral(value) => write!(f, "int literal {}", value),
+            Token::LongLiteral(value) => write!(f, "long literal {}", value),
+            Token::RealLiteral(value) => write!(f, "real literal {}", value),
+            Token::StringLiteral(value) => write!(f, "string literal {:?}", value),
+            Token::DateTimeLiteral(_) => write!(f, "datetime literal"),
+            Token::TimespanLiteral(_) => write!(f, "timespan literal"),
+            Token::DecimalLiteral(value) => write!(f, "decimal literal {}", value),
+            Token::DynamicLiteral(_) => write!(f, "dynamic literal"),
+            Token::GuidLiteral(value) => write!(f, "guid literal {}", value),
+            Token::BoolNullLiteral => write!(f, "'bool(null)'"),
+            Token::IntNullLiteral => write!(f, "'int(null)'"),
+            Token::LongNullLiteral => write!(f, "'long(null)'"),
+            Token::RealNullLiteral => write!(f, "'real(null)'"),
+            Token::DateTimeNullLiteral => write!(f, "'datetime(null)'"),
+            Token::TimespanNullLiteral => write!(f, "'timespan(null)'"),
+            Token::DecimalNullLiteral => write!(f, "'decimal(null)'"),
+            Token::GuidNullLiteral => write!(f, "'guid(null)'"),
+            Token::Pipe => write!(f, "'|'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::LBrace => write!(f, "'{{'"),
+            Token::RBrace => write!(f, "'}}'"),
+            Token::LBracket => write!(f, "'['"),
+            Token::RBracket => write!(f, "']'"),
+            Token::Comma => write!(f, "','"),
+            Token::Colon => write!(f, "':'"),
+            Token::Semicolon => write!(f, "';'"),
+            Token::Dot => write!(f, "'.'"),
+            Token::DotDot => write!(f, "'..'"),
+            Token::Assign => write!(f, "'='"),
+            Token::Add => write!(f, "'+'"),
+            Token::Sub => write!(f, "'-'"),
+            Token::Star => write!(f, "'*'"),
+            Token::Div => write!(f, "'/'"),
+            Token::Mod => write!(f, "'%'"),
+            Token::LogicalAnd => write!(f, "'and'"),
+            Token::LogicalOr => write!(f, "'or'"),
+            Token::LT => write!(f, "'<'"),
+            Token::LTE => write!(f, "'<='"),
+            Token::GT => write!(f, "'>'"),
+            Token::GTE => write!(f, "'>='"),
+            Token::EQ => write!(f, "'=='"),
+            Token::NEQ => write!(f, "'!='"),
+            Token::EQTilde => write!(f, "'=~'"),
+            Token::NEQTilde => write!(f, "'!~'"),
+            Token::InTilde => write!(f, "'in~'"),
+            Token::NotInTilde => write!(f, "'!in~'"),
+        }
+    }
 }
 
 /// Parses a string according to the JSON string format in ECMA-404.
@@ -225,10 +355,9 @@ fn parse_string_literal<'src>(init: char, lex: &mut logos::Lexer<'src, Token>) -
         // If slash, then parse an escaped character
         if c == '\\' {
             lex.bump(1);
-            if let Some((c_esc, c_len)) = parse_escaped_char(&mut c_iter) {
-                lex.bump(c_len);
-                buf.push(c_esc);
-            }
+            let (c_esc, c_len) = parse_escaped_char(&mut c_iter)?;
+            lex.bump(c_len);
+            buf.push(c_esc);
         } else {
             lex.bump(c.len_utf8());
             buf.push(c);
@@ -246,15 +375,43 @@ fn parse_escaped_char<'src>(lex: &mut std::str::Chars<'src>) -> Option<(char, us
         '\"' => ('\"', 1),
         '\'' => ('\'', 1),
         '\\' => ('\\', 1),
+        '/' => ('/', 1),
+        'b' => ('\u{8}', 1),
+        'f' => ('\u{c}', 1),
         'n' => ('\n', 1),
         'r' => ('\r', 1),
         't' => ('\t', 1),
+        'u' => {
+            let high = parse_hex_code_unit(lex)?;
+            if !(0xD800..0xDC00).contains(&high) {
+                return Some((char::from_u32(high)?, 5));
+            }
+            // Characters outside the basic multilingual plane are written as a surrogate pair
+            if lex.next()? != '\\' || lex.next()? != 'u' {
+                return None;
+            }
+            let low = parse_hex_code_unit(lex)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+            let c = char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))?;
+            (c, 11)
+        }
         _ => return None,
     };
 
     Some(res)
 }
 
+/// Parses the four hex digits of a `\\u` escape
+fn parse_hex_code_unit<'src>(lex: &mut std::str::Chars<'src>) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..4 {
+        value = value * 16 + lex.next()?.to_digit(16)?;
+    }
+    Some(value)
+}
+
 fn parse_verbatim_string_literal<'src>(
     init: char,
     lex: &mut logos::Lexer<'src, Token>,
@@ -316,6 +473,23 @@ fn parse_multiline_string_literal<'src>(lex: &mut logos::Lexer<'src, Token>) ->
     None
 }
 
+/// Parses the contents of `real(...)`, which is a number, `nan`, or a signed `inf`
+fn parse_real_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<f64> {
+    let s = s[trim_front..s.len() - trim_back].trim();
+    match s {
+        "nan" => Some(f64::NAN),
+        "inf" | "+inf" => Some(f64::INFINITY),
+        "-inf" => Some(f64::NEG_INFINITY),
+        _ => {
+            // Rust also accepts forms such as "infinity" which Kusto does not
+            let is_numeric = s
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+            s.parse().ok().filter(|_| is_numeric)
+        }
+    }
+}
+
 fn parse_int_dec_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<i32> {
     let s = &s[trim_front..s.len() - trim_back];
 
@@ -348,6 +522,305 @@ fn parse_long_hex_literal(s: &str, trim_front: usize, trim_back: usize) -> Optio
     i64::from_str_radix(&s[2..], 16).ok()
 }
 
+/// Parses the contents of `datetime(...)`.
+/// Accepts ISO 8601, RFC 822 and RFC 850 formats, with the time of day being optional.
+/// Values with an offset are converted to UTC, all others are taken to be UTC already.
+fn parse_datetime_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<NaiveDateTime> {
+    let s = s[trim_front..s.len() - trim_back].trim();
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime.naive_utc());
+    }
+
+    let s = s
+        .strip_suffix('Z')
+        .or_else(|| s.strip_suffix(" GMT"))
+        .or_else(|| s.strip_suffix(" UTC"))
+        .unwrap_or(s)
+        .trim_end();
+
+    const DATETIME_FORMATS: &[&str] = &[
+        // ISO 8601
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        // RFC 822
+        "%a, %d %b %y %H:%M:%S",
+        "%a, %d %b %y %H:%M",
+        "%d %b %y %H:%M:%S",
+        "%d %b %y %H:%M",
+        // RFC 850
+        "%A, %d-%b-%y %H:%M:%S",
+        "%A, %d-%b-%y %H:%M",
+        "%d-%b-%y %H:%M:%S",
+        "%d-%b-%y %H:%M",
+    ];
+
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Parses the contents of `decimal(...)`, moving the decimal point
+/// instead of keeping an exponent so that the value stays exact.
+fn parse_decimal_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<String> {
+    let s = &s[trim_front..s.len() - trim_back];
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(s) => ("-", s),
+        None => ("", s),
+    };
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(index) => (&s[..index], s[index + 1..].parse::<i32>().ok()?),
+        None => (s, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{}{}", whole, fraction);
+    let point = whole.len() as i64 + exponent as i64;
+    let (whole, fraction) = if point <= 0 {
+        let zeros = "0".repeat(point.unsigned_abs() as usize);
+        (String::from("0"), format!("{}{}", zeros, digits))
+    } else if point as usize >= digits.len() {
+        let zeros = "0".repeat(point as usize - digits.len());
+        (format!("{}{}", digits, zeros), String::new())
+    } else {
+        let (whole, fraction) = digits.split_at(point as usize);
+        (whole.to_string(), fraction.to_string())
+    };
+
+    let whole = whole.trim_start_matches('0');
+    let whole = if whole.is_empty() { "0" } else { whole };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        Some(format!("{}{}", sign, whole))
+    } else {
+        Some(format!("{}{}.{}", sign, whole, fraction))
+    }
+}
+
+/// Parses the contents of `guid(...)`, which must be in the `8-4-4-4-12` hex digit format
+fn parse_guid_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<String> {
+    let s = &s[trim_front..s.len() - trim_back];
+    let group_lengths: Vec<usize> = s.split('-').map(|group| group.len()).collect();
+    if group_lengths == [8, 4, 4, 4, 12] {
+        Some(s.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Parses the value of a `dynamic(...)` literal and its closing parenthesis
+fn parse_dynamic_literal<'src>(lex: &mut logos::Lexer<'src, Token>) -> Option<Dynamic> {
+    let remainder = lex.remainder();
+    let mut rest = remainder;
+
+    let value = parse_dynamic_value(&mut rest)?;
+    rest = rest.trim_start().strip_prefix(')')?;
+
+    lex.bump(remainder.len() - rest.len());
+    Some(value)
+}
+
+/// Parses a JSON-like value from the front of `rest`, advancing it past the value.
+fn parse_dynamic_value(rest: &mut &str) -> Option<Dynamic> {
+    *rest = rest.trim_start();
+
+    if consume_char(rest, '[') {
+        let mut items = Vec::new();
+        if !consume_char(rest, ']') {
+            loop {
+                items.push(parse_dynamic_value(rest)?);
+                if consume_char(rest, ']') {
+                    break;
+                }
+                if !consume_char(rest, ',') {
+                    return None;
+                }
+            }
+        }
+        return Some(Dynamic::Array(items));
+    }
+
+    if consume_char(rest, '{') {
+        let mut properties = Vec::new();
+        if !consume_char(rest, '}') {
+            loop {
+                *rest = rest.trim_start();
+                let key = parse_dynamic_string(rest)?;
+                if !consume_char(rest, ':') {
+                    return None;
+                }
+                properties.push((key, parse_dynamic_value(rest)?));
+                if consume_char(rest, '}') {
+                    break;
+                }
+                if !consume_char(rest, ',') {
+                    return None;
+                }
+            }
+        }
+        return Some(Dynamic::Bag(properties));
+    }
+
+    if rest.starts_with('"') || rest.starts_with('\'') {
+        return parse_dynamic_string(rest).map(Dynamic::String);
+    }
+
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(rest.len());
+    let (word, remaining) = rest.split_at(end);
+    let value = match word {
+        "null" => Dynamic::Null,
+        "true" => Dynamic::Bool(true),
+        "false" => Dynamic::Bool(false),
+        _ if word.contains(['.', 'e', 'E']) => {
+            Dynamic::Real(word.parse().ok().filter(|v: &f64| v.is_finite())?)
+        }
+        _ => Dynamic::Long(word.parse().ok()?),
+    };
+    *rest = remaining;
+    Some(value)
+}
+
+/// Parses a quoted string from the front of `rest`, advancing it past the closing quote.
+fn parse_dynamic_string(rest: &mut &str) -> Option<String> {
+    let mut c_iter = rest.chars();
+    let init = c_iter.next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut consumed = 1;
+    let mut buf = String::new();
+
+    while let Some(c) = c_iter.next() {
+        consumed += c.len_utf8();
+        if c == init {
+            *rest = &rest[consumed..];
+            return Some(buf);
+        }
+
+        if c == '\n' || c == '\r' {
+            return None;
+        }
+
+        if c == '\\' {
+            let (c_esc, c_len) = parse_escaped_char(&mut c_iter)?;
+            consumed += c_len;
+            buf.push(c_esc);
+        } else {
+            buf.push(c);
+        }
+    }
+
+    None
+}
+
+/// Advances `rest` past `c` if it is the next non-whitespace character
+fn consume_char(rest: &mut &str, c: char) -> bool {
+    match rest.trim_start().strip_prefix(c) {
+        Some(remaining) => {
+            *rest = remaining;
+            true
+        }
+        None => false,
+    }
+}
+
+const TICKS_PER_MICROSECOND: i64 = 10;
+const TICKS_PER_MILLISECOND: i64 = 1000 * TICKS_PER_MICROSECOND;
+const TICKS_PER_SECOND: i64 = 1000 * TICKS_PER_MILLISECOND;
+const TICKS_PER_MINUTE: i64 = 60 * TICKS_PER_SECOND;
+const TICKS_PER_HOUR: i64 = 60 * TICKS_PER_MINUTE;
+const TICKS_PER_DAY: i64 = 24 * TICKS_PER_HOUR;
+
+/// Parses the contents of `timespan(...)`, which is either a number with a unit,
+/// a number of days, or the `[days.]hours:minutes[:seconds[.fraction]]` format.
+fn parse_timespan_literal(s: &str, trim_front: usize, trim_back: usize) -> Option<i64> {
+    let s = s[trim_front..s.len() - trim_back].trim();
+
+    if let Some(s) = s.strip_prefix('-') {
+        return parse_timespan_literal(s.trim_start(), 0, 0).map(|ticks| -ticks);
+    }
+
+    if s.contains(':') {
+        let (days, time) = match s.split_once('.') {
+            Some((days, time)) if !days.contains(':') => (days.parse::<i64>().ok()?, time),
+            _ => (0, s),
+        };
+        let mut parts = time.split(':');
+        let hours: i64 = parts.next()?.parse().ok()?;
+        let minutes: i64 = parts.next()?.parse().ok()?;
+        let seconds = match parts.next() {
+            Some(seconds) => parse_scaled(seconds, TICKS_PER_SECOND)?,
+            None => 0,
+        };
+        if parts.next().is_some() || hours >= 24 || minutes >= 60 || seconds >= TICKS_PER_MINUTE {
+            return None;
+        }
+        return Some(
+            days * TICKS_PER_DAY + hours * TICKS_PER_HOUR + minutes * TICKS_PER_MINUTE + seconds,
+        );
+    }
+
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    if unit_start == s.len() {
+        // A plain number is a number of days
+        parse_scaled(s, TICKS_PER_DAY)
+    } else {
+        let (amount, unit) = s.split_at(unit_start);
+        parse_scaled(amount, timespan_unit(unit.trim_start())?)
+    }
+}
+
+/// Parses a number with a unit suffix, such as `1d`, `1.5h` or `100ms`
+fn parse_timespan(s: &str) -> Option<i64> {
+    let unit_start = s.find(|c: char| c.is_ascii_alphabetic())?;
+    let (amount, unit) = s.split_at(unit_start);
+    parse_scaled(amount, timespan_unit(unit)?)
+}
+
+/// The number of ticks in a timespan unit
+fn timespan_unit(unit: &str) -> Option<i64> {
+    let ticks = match unit {
+        "d" | "day" | "days" => TICKS_PER_DAY,
+        "h" | "hr" | "hrs" | "hour" | "hours" => TICKS_PER_HOUR,
+        "m" | "min" | "minute" | "minutes" => TICKS_PER_MINUTE,
+        "s" | "sec" | "second" | "seconds" => TICKS_PER_SECOND,
+        "ms" | "milli" | "millis" | "millisecond" | "milliseconds" => TICKS_PER_MILLISECOND,
+        "microsecond" | "microseconds" => TICKS_PER_MICROSECOND,
+        "tick" | "ticks" => 1,
+        _ => return None,
+    };
+    Some(ticks)
+}
+
+/// Multiplies a decimal number by a scale without going through floating point,
+/// so that e.g. `0.1s` is exactly one million ticks
+fn parse_scaled(s: &str, scale: i64) -> Option<i64> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if whole.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut value = whole.parse::<i64>().ok()?.checked_mul(scale)?;
+    let mut place = scale;
+    for digit in fraction.chars() {
+        if place % 10 != 0 {
+            // Finer than a single tick
+            return None;
+        }
+        place /= 10;
+        value = value.checked_add(digit.to_digit(10)? as i64 * place)?;
+    }
+    Some(value)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -401,6 +874,187 @@ mod test {
         }
     }
 
+    #[test]
+    fn tokenize_temporal_literals() {
+        let contents: String = "datetime(2023-01-01) datetime(2014-05-25T08:20:03.123Z) datetime(Sat, 8 Nov 14 15:05:02 GMT) datetime(null) 1d 1.5h 30m 100ms 1tick timespan(0.12:34:56.7) time(2) timespan(null)".into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        let datetime = |date: (i32, u32, u32), time: (u32, u32, u32), milli: u32| {
+            NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+                .and_then(|date| date.and_hms_milli_opt(time.0, time.1, time.2, milli))
+                .unwrap()
+        };
+        let output = vec![
+            Token::DateTimeLiteral(datetime((2023, 1, 1), (0, 0, 0), 0)),
+            Token::DateTimeLiteral(datetime((2014, 5, 25), (8, 20, 3), 123)),
+            Token::DateTimeLiteral(datetime((2014, 11, 8), (15, 5, 2), 0)),
+            Token::DateTimeNullLiteral,
+            Token::TimespanLiteral(TICKS_PER_DAY),
+            Token::TimespanLiteral(90 * TICKS_PER_MINUTE),
+            Token::TimespanLiteral(30 * TICKS_PER_MINUTE),
+            Token::TimespanLiteral(100 * TICKS_PER_MILLISECOND),
+            Token::TimespanLiteral(1),
+            Token::TimespanLiteral(
+                12 * TICKS_PER_HOUR + 34 * TICKS_PER_MINUTE + 567 * TICKS_PER_SECOND / 10,
+            ),
+            Token::TimespanLiteral(2 * TICKS_PER_DAY),
+            Token::TimespanNullLiteral,
+        ];
+
+        match tokenize(src, contents) {
+            Ok(tokens) => assert_eq!(
+                output,
+                tokens
+                    .into_iter()
+                    .map(|token| token.value)
+                    .collect::<Vec<Token>>()
+            ),
+            Err(_) => panic!("Should not have failed"),
+        }
+    }
+
+    #[test]
+    fn tokenize_invalid_temporal_literals() {
+        for contents in [
+            "datetime(yesterday)",
+            "timespan(2 weeks)",
+            "timespan(25:00)",
+        ] {
+            let src = Arc::new(NamedSource::new(String::from("test"), contents.to_string()));
+            assert!(tokenize(src, contents.to_string()).is_err());
+        }
+    }
+
+    #[test]
+    fn tokenize_decimal_dynamic_and_guid_literals() {
+        let contents: String = r#"decimal(1.50) decimal(-12e-3) decimal(null) dynamic(["a", 'b', {"c": [1, 2.5, true, null]}]) dynamic(null) guid(74BE27DE-1E4E-49D9-B579-FE0B331D3642)"#.into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        let output = vec![
+            Token::DecimalLiteral(String::from("1.5")),
+            Token::DecimalLiteral(String::from("-0.012")),
+            Token::DecimalNullLiteral,
+            Token::DynamicLiteral(Dynamic::Array(vec![
+                Dynamic::String(String::from("a")),
+                Dynamic::String(String::from("b")),
+                Dynamic::Bag(vec![(
+                    String::from("c"),
+                    Dynamic::Array(vec![
+                        Dynamic::Long(1),
+                        Dynamic::Real(2.5),
+                        Dynamic::Bool(true),
+                        Dynamic::Null,
+                    ]),
+                )]),
+            ])),
+            Token::DynamicLiteral(Dynamic::Null),
+            Token::GuidLiteral(String::from("74be27de-1e4e-49d9-b579-fe0b331d3642")),
+        ];
+
+        match tokenize(src, contents) {
+            Ok(tokens) => assert_eq!(
+                output,
+                tokens
+                    .into_iter()
+                    .map(|token| token.value)
+                    .collect::<Vec<Token>>()
+            ),
+            Err(_) => panic!("Should not have failed"),
+        }
+    }
+
+    #[test]
+    fn tokenize_null_literals() {
+        let contents: String = "bool(null) int(null) long(null) real(null) decimal(null) datetime(null) timespan(null) guid(null)".into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        let output = vec![
+            Token::BoolNullLiteral,
+            Token::IntNullLiteral,
+            Token::LongNullLiteral,
+            Token::RealNullLiteral,
+            Token::DecimalNullLiteral,
+            Token::DateTimeNullLiteral,
+            Token::TimespanNullLiteral,
+            Token::GuidNullLiteral,
+        ];
+
+        match tokenize(src, contents) {
+            Ok(tokens) => assert_eq!(
+                output,
+                tokens
+                    .into_iter()
+                    .map(|token| token.value)
+                    .collect::<Vec<Token>>()
+            ),
+            Err(_) => panic!("Should not have failed"),
+        }
+    }
+
+    #[test]
+    fn tokenize_reals() {
+        let contents: String =
+            "1e10 1.5e-3 .5 2.5E+2 real(1e3) real(-2) real(+inf) real(-inf)".into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        let output = vec![
+            Token::RealLiteral(1e10),
+            Token::RealLiteral(1.5e-3),
+            Token::RealLiteral(0.5),
+            Token::RealLiteral(250.0),
+            Token::RealLiteral(1000.0),
+            Token::RealLiteral(-2.0),
+            Token::RealLiteral(f64::INFINITY),
+            Token::RealLiteral(f64::NEG_INFINITY),
+        ];
+
+        match tokenize(src, contents) {
+            Ok(tokens) => assert_eq!(
+                output,
+                tokens
+                    .into_iter()
+                    .map(|token| token.value)
+                    .collect::<Vec<Token>>()
+            ),
+            Err(_) => panic!("Should not have failed"),
+        }
+
+        let contents: String = "real(nan)".into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        match tokenize(src, contents).as_deref() {
+            Ok(
+                [M {
+                    value: Token::RealLiteral(value),
+                    ..
+                }],
+            ) => assert!(value.is_nan()),
+            _ => panic!("Should have produced a single NaN"),
+        }
+    }
+
+    #[test]
+    fn tokenize_string_escapes() {
+        let contents: String = r#""a\"b\\c\nd\te\u1234f\ud83d\ude00" h'secret' H@"C:\path""#.into();
+        let src = Arc::new(NamedSource::new(String::from("test"), contents.clone()));
+        let output = vec![
+            Token::StringLiteral(String::from("a\"b\\c\nd\te\u{1234}f\u{1F600}")),
+            Token::StringLiteral(String::from("secret")),
+            Token::StringLiteral(String::from("C:\\path")),
+        ];
+
+        match tokenize(src, contents) {
+            Ok(tokens) => assert_eq!(
+                output,
+                tokens
+                    .into_iter()
+                    .map(|token| token.value)
+                    .collect::<Vec<Token>>()
+            ),
+            Err(_) => panic!("Should not have failed"),
+        }
+
+        for contents in [r#""\q""#, r#""\u12""#, r#""\ud83d""#] {
+            let src = Arc::new(NamedSource::new(String::from("test"), contents.to_string()));
+            assert!(tokenize(src, contents.to_string()).is_err());
+        }
+    }
+
     fn to_token_data(d: (Span, Token)) -> M<Token> {
         M::new(d.1, d.0)
     }
@@ -12,13 +12,21 @@ pub mod lexer;
 pub mod parser;
 /// Miette Span Utilities
 pub mod spans;
+/// "Did you mean" Suggestions for Misspelled Names
+pub mod suggestions;
 
 pub enum Error {
-    Lexer { errors: Vec<LexerError> },
-    Parser { error: ParserError },
+    Lexer {
+        errors: Vec<LexerError>,
+    },
+    /// The script is what could be parsed around the errors
+    Parser {
+        errors: Vec<ParserError>,
+        partial: ast::script::Script,
+    },
 }
 
-pub fn parse(src: Arc<NamedSource>, kql_code: String) -> Result<ast::query::Query, Error> {
+pub fn parse(src: Arc<NamedSource>, kql_code: String) -> Result<ast::script::Script, Error> {
     let tokens = match lexer::tokenize(src.clone(), kql_code) {
         Ok(token_data) => token_data,
         Err(errors) => {
@@ -26,12 +34,8 @@ pub fn parse(src: Arc<NamedSource>, kql_code: String) -> Result<ast::query::Quer
         }
     };
 
-    let ast = match parser::parse(src.clone(), tokens) {
-        Ok(query) => query,
-        Err(error) => {
-            return Err(Error::Parser { error });
-        }
-    };
-
-    Ok(ast)
+    match parser::parse(src.clone(), tokens) {
+        (script, errors) if errors.is_empty() => Ok(script),
+        (partial, errors) => Err(Error::Parser { errors, partial }),
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
-    ast::expression::{BinaryOp, Expression, Literal},
+    ast::expression::{BinaryOp, Expression, Literal, StringPredicate, UnaryOp},
     lexer::Token,
-    spans::{MBox, M},
+    spans::{join_spans, MBox, M},
 };
 
 use super::{parse_term, ParseInput, ParserError};
@@ -14,7 +14,16 @@ pub fn parse_expression(input: &mut ParseInput) -> Result<MBox<Expression>, Pars
 /// Pratt parsing of expressions based on
 /// https://matklad.github.io/2020/04/13/simple-but-powerful-pratt-parsing.html
 fn pratt_parse(input: &mut ParseInput, min_bp: u8) -> Result<MBox<Expression>, ParserError> {
-    let mut lhs = parse_leaf(input)?;
+    let mut lhs = match try_parse_prefix_op(input) {
+        Some(op) if op.value == UnaryOp::Not => parse_not(input, op)?,
+        Some(op) => {
+            let rhs = pratt_parse(input, prefix_binding_power(op.value))?;
+            let left = op.span.clone();
+            let right = rhs.span.clone();
+            MBox::new_range(Expression::UnaryOp { op, expr: rhs }, left, right)
+        }
+        None => parse_leaf(input)?,
+    };
     loop {
         let checkpoint = input.checkpoint();
         if let Some(bin_op) = try_parse_bin_op(input) {
@@ -25,7 +34,11 @@ fn pratt_parse(input: &mut ParseInput, min_bp: u8) -> Result<MBox<Expression>, P
                 break;
             }
 
-            let rhs = pratt_parse(input, r_bp)?;
+            let rhs = match bin_op.value {
+                BinaryOp::In { .. } | BinaryOp::HasAny | BinaryOp::HasAll => parse_list(input)?,
+                BinaryOp::Between { .. } => parse_range(input)?,
+                _ => pratt_parse(input, r_bp)?,
+            };
             let left = lhs.span.clone();
             let right = rhs.span.clone();
             let new_root = Expression::BinaryOp {
@@ -41,12 +54,24 @@ fn pratt_parse(input: &mut ParseInput, min_bp: u8) -> Result<MBox<Expression>, P
     Ok(lhs)
 }
 
+/// `not` is written like a function call, so its operand is always parenthesized
+fn parse_not(input: &mut ParseInput, op: M<UnaryOp>) -> Result<MBox<Expression>, ParserError> {
+    let open_paren_sym = input.assert_next(Token::LParen)?;
+    let expr = parse_expression(input)?;
+    let close_paren_sym = input.assert_closing(open_paren_sym)?;
+    let left = op.span.clone();
+    Ok(MBox::new_range(
+        Expression::UnaryOp { op, expr },
+        left,
+        close_paren_sym,
+    ))
+}
+
 fn parse_leaf(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
-    let checkpoint = input.checkpoint();
-    if let Ok(value) = parse_parenthetical(input) {
-        return Ok(value);
+    if matches!(input.peek(), Ok(token) if token.value == Token::LParen) {
+        return parse_parenthetical(input);
     }
-    input.restore(checkpoint);
+    let checkpoint = input.checkpoint();
     if let Ok(value) = parse_literal(input) {
         let span = value.span.clone();
         return Ok(MBox::new(Expression::Literal { value: value.value }, span));
@@ -56,12 +81,13 @@ fn parse_leaf(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
         let span = term.span.clone();
         if let Some(open_paren_sym) = input.next_if(Token::LParen) {
             let mut args = Vec::new();
-            args.push(parse_expression(input)?);
-            while input.next_if(Token::Comma).is_some() {
-                args.push(parse_expression(input)?);
+            if input.peek()?.value != Token::RParen {
+                args.push(parse_argument(input)?);
+                while input.next_if(Token::Comma).is_some() {
+                    args.push(parse_argument(input)?);
+                }
             }
-            let close_paren_sym =
-                input.assert_next(Token::RParen, "No closing parenthesis for function call")?;
+            let close_paren_sym = input.assert_closing(open_paren_sym.clone())?;
             let end_span = close_paren_sym.clone();
             return Ok(MBox::new_range(
                 Expression::FuncCall {
@@ -78,17 +104,73 @@ fn parse_leaf(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
         }
     }
     // advance so that error is generated on the correct token
+    input.restore(checkpoint);
     let _ = input.next();
-    Err(input.unexpected_token("Parse Leaf"))
+    Err(input.unexpected_token(&["an expression"]))
+}
+
+/// Parses a function argument, which may be a "*" wildcard
+fn parse_argument(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
+    let checkpoint = input.checkpoint();
+    if let Some(span) = input.next_if(Token::Star) {
+        if matches!(input.peek(), Ok(token) if matches!(token.value, Token::Comma | Token::RParen))
+        {
+            return Ok(MBox::new(Expression::Wildcard, span));
+        }
+        input.restore(checkpoint);
+    }
+    parse_expression(input)
 }
 
 fn parse_parenthetical(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
-    let _left = input.assert_next(Token::LParen, "Left parenthesis '('")?;
+    let left = input.assert_next(Token::LParen)?;
     let inner = parse_expression(input)?;
-    let _right = input.assert_next(Token::RParen, "Right parenthesis ')'")?;
+    let _right = input.assert_closing(left)?;
     Ok(inner)
 }
 
+/// Parses the right side of the set operators, "(a, b, c)"
+fn parse_list(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
+    let open_paren_sym = input.assert_next(Token::LParen)?;
+    let mut items = Vec::new();
+    if input.peek()?.value != Token::RParen {
+        items.push(parse_expression(input)?);
+        while input.next_if(Token::Comma).is_some() {
+            items.push(parse_expression(input)?);
+        }
+    }
+    let close_paren_sym = input.assert_closing(open_paren_sym.clone())?;
+    let left = open_paren_sym.clone();
+    let right = close_paren_sym.clone();
+    Ok(MBox::new_range(
+        Expression::List {
+            open_paren_sym,
+            items,
+            close_paren_sym,
+        },
+        left,
+        right,
+    ))
+}
+
+/// Parses the right side of "between", "(start .. end)"
+fn parse_range(input: &mut ParseInput) -> Result<MBox<Expression>, ParserError> {
+    let open_paren_sym = input.assert_next(Token::LParen)?;
+    let start = parse_expression(input)?;
+    let dot_dot_sym = input.assert_next(Token::DotDot)?;
+    let end = parse_expression(input)?;
+    let close_paren_sym = input.assert_closing(open_paren_sym.clone())?;
+    Ok(MBox::new_range(
+        Expression::Range {
+            start,
+            dot_dot_sym,
+            end,
+        },
+        open_paren_sym,
+        close_paren_sym,
+    ))
+}
+
 fn parse_literal(input: &mut ParseInput) -> Result<M<Literal>, ParserError> {
     let next = input.next()?;
     let span = next.span.clone();
@@ -107,15 +189,29 @@ fn parse_literal(input: &mut ParseInput) -> Result<M<Literal>, ParserError> {
         Token::RealNullLiteral => Literal::Real(None),
         // strings
         Token::StringLiteral(value) => Literal::String(value),
+        // datetimes
+        Token::DateTimeLiteral(value) => Literal::DateTime(Some(value)),
+        Token::DateTimeNullLiteral => Literal::DateTime(None),
+        // timespans
+        Token::TimespanLiteral(value) => Literal::Timespan(Some(value)),
+        Token::TimespanNullLiteral => Literal::Timespan(None),
+        // decimals
+        Token::DecimalLiteral(value) => Literal::Decimal(Some(value)),
+        Token::DecimalNullLiteral => Literal::Decimal(None),
+        // dynamics
+        Token::DynamicLiteral(value) => Literal::Dynamic(value),
+        // guids
+        Token::GuidLiteral(value) => Literal::Guid(Some(value)),
+        Token::GuidNullLiteral => Literal::Guid(None),
         // errors
-        _ => return Err(input.unexpected_token("Parse Literal")),
+        _ => return Err(input.unexpected_token(&["a literal"])),
     };
     Ok(M::new(value, span))
 }
 
 fn try_parse_bin_op(input: &mut ParseInput) -> Option<M<BinaryOp>> {
-    let next = input.peek().ok()?;
-    let span = next.span.clone();
+    let next = input.peek().ok()?.clone();
+    let mut span = next.span.clone();
     let op = match &next.value {
         Token::LogicalOr => BinaryOp::LogicalOr,
         Token::LogicalAnd => BinaryOp::LogicalAnd,
@@ -135,18 +231,120 @@ fn try_parse_bin_op(input: &mut ParseInput) -> Option<M<BinaryOp>> {
         Token::Div => BinaryOp::Div,
         Token::Mod => BinaryOp::Mod,
 
+        Token::EQTilde => string_predicate(StringPredicate::Equals, false, false),
+        Token::NEQTilde => string_predicate(StringPredicate::Equals, false, true),
+        Token::InTilde => BinaryOp::In {
+            case_sensitive: false,
+            negated: false,
+        },
+        Token::NotInTilde => BinaryOp::In {
+            case_sensitive: false,
+            negated: true,
+        },
+
+        // `matches regex` is the only operator spanning two terms
+        Token::Term(name) if name == "matches" => {
+            let checkpoint = input.checkpoint();
+            let _ = input.next();
+            match input.peek() {
+                Ok(M {
+                    value: Token::Term(name),
+                    span: regex_span,
+                }) if name == "regex" => {
+                    span = join_spans(span, regex_span.clone());
+                    string_predicate(StringPredicate::MatchesRegex, true, false)
+                }
+                _ => {
+                    input.restore(checkpoint);
+                    return None;
+                }
+            }
+        }
+        Token::Term(name) => parse_term_operator(name, false)?,
+        Token::BangTerm(name) => parse_term_operator(name, true)?,
+
         _ => return None,
     };
     let _ = input.next();
     Some(M::new(op, span))
 }
 
+/// Recognizes the operators written as terms, with "_cs" marking the case-sensitive
+/// forms of the string operators
+fn parse_term_operator(name: &str, negated: bool) -> Option<BinaryOp> {
+    match (name, negated) {
+        ("in", _) => {
+            return Some(BinaryOp::In {
+                case_sensitive: true,
+                negated,
+            })
+        }
+        ("between", _) => return Some(BinaryOp::Between { negated }),
+        ("has_any", false) => return Some(BinaryOp::HasAny),
+        ("has_all", false) => return Some(BinaryOp::HasAll),
+        _ => {}
+    }
+    let (name, case_sensitive) = match name.strip_suffix("_cs") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let predicate = match name {
+        "has" => StringPredicate::Has,
+        "contains" => StringPredicate::Contains,
+        "startswith" => StringPredicate::StartsWith,
+        "endswith" => StringPredicate::EndsWith,
+        _ => return None,
+    };
+    Some(string_predicate(predicate, case_sensitive, negated))
+}
+
+fn string_predicate(predicate: StringPredicate, case_sensitive: bool, negated: bool) -> BinaryOp {
+    BinaryOp::StringPredicate {
+        predicate,
+        case_sensitive,
+        negated,
+    }
+}
+
+fn try_parse_prefix_op(input: &mut ParseInput) -> Option<M<UnaryOp>> {
+    let checkpoint = input.checkpoint();
+    let next = input.next().ok()?.clone();
+    let op = match &next.value {
+        Token::Sub => UnaryOp::Neg,
+        Token::Add => UnaryOp::Plus,
+        Token::Term(name)
+            if name == "not"
+                && matches!(input.peek(), Ok(token) if token.value == Token::LParen) =>
+        {
+            UnaryOp::Not
+        }
+        _ => {
+            input.restore(checkpoint);
+            return None;
+        }
+    };
+    Some(M::new(op, next.span))
+}
+
+fn prefix_binding_power(op: UnaryOp) -> u8 {
+    match op {
+        UnaryOp::Neg | UnaryOp::Plus => 70,
+        UnaryOp::Not => 0,
+    }
+}
+
 fn infix_binding_power(op: BinaryOp) -> (u8, u8) {
     match op {
         BinaryOp::LogicalOr => (10, 1),
         BinaryOp::LogicalAnd => (20, 21),
 
-        BinaryOp::EQ | BinaryOp::NEQ => (30, 31),
+        BinaryOp::EQ
+        | BinaryOp::NEQ
+        | BinaryOp::StringPredicate { .. }
+        | BinaryOp::In { .. }
+        | BinaryOp::HasAny
+        | BinaryOp::HasAll
+        | BinaryOp::Between { .. } => (30, 31),
 
         BinaryOp::LT | BinaryOp::LTE | BinaryOp::GT | BinaryOp::GTE => (40, 41),
 
@@ -249,6 +447,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parsing_supports_timespans() {
+        let cases = [
+            ("1h", Some(36_000_000_000), Span::from((0, 2))),
+            ("timespan(null)", None, Span::from((0, 14))),
+        ];
+        for (source, value, span) in cases {
+            let parsed_expression = MBox::new(
+                Expression::Literal {
+                    value: Literal::Timespan(value),
+                },
+                span.clone(),
+            );
+            assert_eq!(
+                parse_expression(&mut make_input(source)).unwrap(),
+                parsed_expression
+            );
+        }
+    }
+
     macro_rules! lit {
         ($val:expr => ($span_l:expr, $span_r:expr)) => {
             MBox::new(
@@ -314,6 +532,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_expression_supports_prefix_operators() {
+        let source = "a -1 * -2";
+        let expected = bin!(
+            (
+                MBox::new(
+                    Expression::Identifier {
+                        name: M::new(String::from("a"), Span::from((0, 1))),
+                    },
+                    Span::from((0, 1))
+                ),
+                op!(BinaryOp::Sub => (2, 1)),
+                bin!((
+                    lit!(Some(1) => (3, 1)),
+                    op!(BinaryOp::Mul => (5, 1)),
+                    MBox::new(
+                        Expression::UnaryOp {
+                            op: op!(UnaryOp::Neg => (7, 1)),
+                            expr: lit!(Some(2) => (8, 1)),
+                        },
+                        Span::from((7, 2))
+                    )
+                ) => (3, 6))
+            ) => (0, 9)
+        );
+        assert_eq!(parse_expression(&mut make_input(source)).unwrap(), expected);
+
+        let source = "not(a) and b";
+        match *parse_expression(&mut make_input(source)).unwrap().value {
+            Expression::BinaryOp { left, op, .. } => {
+                assert_eq!(op.value, BinaryOp::LogicalAnd);
+                assert_eq!(left.span, Span::from((0, 6)));
+                assert!(matches!(
+                    *left.value,
+                    Expression::UnaryOp {
+                        op: M {
+                            value: UnaryOp::Not,
+                            ..
+                        },
+                        ..
+                    }
+                ));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn parse_expression_supports_string_predicates() {
+        let cases = [
+            ("a has 'x'", StringPredicate::Has, false, false, (2, 3)),
+            (
+                "a !contains_cs 'x'",
+                StringPredicate::Contains,
+                true,
+                true,
+                (2, 12),
+            ),
+            (
+                "a matches regex 'x'",
+                StringPredicate::MatchesRegex,
+                true,
+                false,
+                (2, 13),
+            ),
+            ("a !~ 'x'", StringPredicate::Equals, false, true, (2, 2)),
+        ];
+        for (source, predicate, case_sensitive, negated, span) in cases {
+            match *parse_expression(&mut make_input(source)).unwrap().value {
+                Expression::BinaryOp { op, .. } => assert_eq!(
+                    op,
+                    M::new(
+                        BinaryOp::StringPredicate {
+                            predicate,
+                            case_sensitive,
+                            negated
+                        },
+                        Span::from(span)
+                    )
+                ),
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_expression_supports_lists_and_ranges() {
+        let source0 = "0 !in (1, 2)";
+        let expected0 = bin! (
+            (
+                lit!(Some(0) => (0, 1)),
+                op!(BinaryOp::In { case_sensitive: true, negated: true } => (2, 3)),
+                MBox::new(
+                    Expression::List {
+                        open_paren_sym: Span::from((6, 1)),
+                        items: vec![lit!(Some(1) => (7, 1)), lit!(Some(2) => (10, 1))],
+                        close_paren_sym: Span::from((11, 1)),
+                    },
+                    Span::from((6, 6)),
+                )
+            ) => (0, 12)
+        );
+
+        let source1 = "0 between (1 .. 2)";
+        let expected1 = bin! (
+            (
+                lit!(Some(0) => (0, 1)),
+                op!(BinaryOp::Between { negated: false } => (2, 7)),
+                MBox::new(
+                    Expression::Range {
+                        start: lit!(Some(1) => (11, 1)),
+                        dot_dot_sym: Span::from((13, 2)),
+                        end: lit!(Some(2) => (16, 1)),
+                    },
+                    Span::from((10, 8)),
+                )
+            ) => (0, 18)
+        );
+
+        assert_eq!(
+            parse_expression(&mut make_input(source0)).unwrap(),
+            expected0
+        );
+        assert_eq!(
+            parse_expression(&mut make_input(source1)).unwrap(),
+            expected1
+        );
+    }
+
     #[test]
     fn parse_expression_respects_associativity() {
         let source0 = "0 + 1 + 2";
@@ -348,4 +695,27 @@ mod tests {
             assert_eq!(parse_expression(&mut make_input(source)).unwrap(), expected);
         }
     }
+
+    #[test]
+    fn parsing_supports_wildcard_arguments() {
+        let source = "arg_max(a, *)";
+        let expected = MBox::new(
+            Expression::FuncCall {
+                name: M::new("arg_max".to_string(), Span::from((0, 7))),
+                open_paren_sym: Span::from((7, 1)),
+                args: vec![
+                    MBox::new(
+                        Expression::Identifier {
+                            name: M::new("a".to_string(), Span::from((8, 1))),
+                        },
+                        Span::from((8, 1)),
+                    ),
+                    MBox::new(Expression::Wildcard, Span::from((11, 1))),
+                ],
+                close_paren_sym: Span::from((12, 1)),
+            },
+            Span::from((0, 13)),
+        );
+        assert_eq!(parse_expression(&mut make_input(source)).unwrap(), expected);
+    }
 }
@@ -1,26 +1,29 @@
 pub mod expression;
 pub mod query;
+pub mod script;
 
 use std::sync::Arc;
 
-use crate::ast::query::Query;
+use crate::ast::script::Script;
 use crate::lexer::Token;
 use crate::spans::{Span, M};
+use crate::suggestions::closest;
 
 use miette::{Diagnostic, NamedSource};
 use thiserror::Error;
 
-use self::query::parse_query;
+use self::script::parse_script;
 
 #[derive(Error, Debug, Diagnostic)]
 pub enum ParserError {
-    #[diagnostic()]
-    #[error("End of input reached")]
+    #[diagnostic(help("expected {expected}"))]
+    #[error("Unexpected end of input")]
     EndOfInput {
         #[source_code]
         src: Arc<NamedSource>,
-        #[label("Here")]
+        #[label("The query ends here")]
         span: Span,
+        expected: String,
     },
     #[diagnostic()]
     #[error("Failed to parse: {message}")]
@@ -31,15 +34,26 @@ pub enum ParserError {
         span: Span,
         message: String,
     },
-    #[diagnostic()]
-    #[error("Unexpected token {token:?} with description '{description}'")]
+    #[diagnostic(help("expected {expected}{suggestion}"))]
+    #[error("Unexpected {token}")]
     UnexpectedToken {
         #[source_code]
         src: Arc<NamedSource>,
         #[label("Here")]
         span: Span,
-        description: String,
         token: Token,
+        expected: String,
+        suggestion: String,
+    },
+    #[diagnostic(help("add a closing parenthesis ')'"))]
+    #[error("Unclosed parenthesis")]
+    UnclosedParenthesis {
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("Expected ')' here")]
+        span: Span,
+        #[related]
+        opened: Vec<ParserNote>,
     },
     #[diagnostic()]
     #[error("Feature {feature} not supported yet")]
@@ -52,16 +66,32 @@ pub enum ParserError {
     },
 }
 
-pub fn parse(src: Arc<NamedSource>, tokens: Vec<M<Token>>) -> Result<Query, ParserError> {
+/// Points out code related to a parser error
+#[derive(Error, Debug, Diagnostic)]
+#[error("{message}")]
+#[diagnostic()]
+pub struct ParserNote {
+    #[source_code]
+    src: Arc<NamedSource>,
+    #[label("Here")]
+    span: Span,
+    message: String,
+}
+
+/// Parses as much of the script as possible, skipping over the statements, operators and
+/// columns which fail to parse, and returns it along with every error found.
+pub fn parse(src: Arc<NamedSource>, tokens: Vec<M<Token>>) -> (Script, Vec<ParserError>) {
     let mut parse_input = ParseInput::new(src, tokens);
-    parse_query(&mut parse_input)
+    let script = parse_script(&mut parse_input);
+    (script, parse_input.errors)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct ParseInput {
     src: Arc<NamedSource>,
     tokens: Vec<M<Token>>,
     index: usize,
+    errors: Vec<ParserError>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -75,34 +105,163 @@ impl ParseInput {
             src,
             tokens,
             index: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Records an error which the parser has recovered from
+    pub fn report(&mut self, error: ParserError) {
+        self.errors.push(error);
+    }
+
+    /// Skips the rest of a construct which failed to parse, which started at `start`.
+    /// Stops before the next pipe, or comma when `at_comma` is set, outside of parentheses,
+    /// or before an unmatched right parenthesis or brace or the end of the statement.
+    /// A separator consumed by the failed parse is given back so that parsing resumes there.
+    pub fn recover(&mut self, start: Checkpoint, at_comma: bool) {
+        let is_separator = |token: &Token| {
+            matches!(token, Token::Pipe | Token::Semicolon) || (at_comma && *token == Token::Comma)
+        };
+
+        self.index = self.index.min(self.tokens.len());
+        if self.index > start.index && is_separator(&self.tokens[self.index - 1].value) {
+            self.index -= 1;
+            return;
+        }
+
+        let mut depth = 0;
+        while let Some(token) = self.tokens.get(self.index) {
+            match token.value {
+                Token::LParen | Token::LBrace => depth += 1,
+                Token::RParen | Token::RBrace if depth == 0 => return,
+                Token::RParen | Token::RBrace => depth -= 1,
+                Token::Semicolon => return,
+                _ if depth == 0 && is_separator(&token.value) => return,
+                _ => {}
+            }
+            self.index += 1;
+        }
+    }
+
+    /// Skips the rest of a statement which failed to parse, which started at `start`, up to
+    /// the next semicolon outside of function bodies. A semicolon consumed by the failed
+    /// parse is given back.
+    pub fn skip_statement(&mut self, start: Checkpoint) {
+        self.index = self.index.min(self.tokens.len());
+        let mut depth = 0;
+        for token in &self.tokens[start.index.min(self.index)..self.index] {
+            match token.value {
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth > 0 => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth == 0
+            && self.index > start.index
+            && self.tokens[self.index - 1].value == Token::Semicolon
+        {
+            self.index -= 1;
+            return;
+        }
+
+        while let Some(token) = self.tokens.get(self.index) {
+            match token.value {
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth > 0 => depth -= 1,
+                Token::Semicolon if depth == 0 => return,
+                _ => {}
+            }
+            self.index += 1;
         }
     }
 
     pub fn general_error(&self, message: &str) -> ParserError {
-        let data = &self.tokens[self.index - 1];
         ParserError::General {
             src: self.src.clone(),
-            span: data.span.clone(),
+            span: self.previous_span(),
             message: message.to_string(),
         }
     }
 
     pub fn unsupported_error(&self, feature: &str) -> ParserError {
-        let data = &self.tokens[self.index - 1];
         ParserError::NotYetSupported {
             src: self.src.clone(),
-            span: data.span.clone(),
+            span: self.previous_span(),
             feature: feature.to_string(),
         }
     }
 
-    pub fn unexpected_token(&self, description: &str) -> ParserError {
-        let data = &self.tokens[self.index - 1];
-        ParserError::UnexpectedToken {
+    /// An error for the most recently consumed token, given what could have been there
+    /// instead. Keywords and symbols are quoted, and keywords are suggested for
+    /// misspelled terms.
+    pub fn unexpected_token(&self, expected: &[&str]) -> ParserError {
+        let expected_list = match expected.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("one of {} or {}", rest.join(", "), last),
+            None => String::from("something else"),
+        };
+        match self.previous() {
+            Some(data) => {
+                let suggestion = match &data.value {
+                    Token::Term(name) => {
+                        let keywords = expected.iter().filter_map(|expected| {
+                            expected
+                                .strip_prefix('\'')
+                                .and_then(|keyword| keyword.strip_suffix('\''))
+                        });
+                        closest(name, keywords)
+                            .map(|keyword| format!("; did you mean '{}'?", keyword))
+                            .unwrap_or_default()
+                    }
+                    _ => String::new(),
+                };
+                ParserError::UnexpectedToken {
+                    src: self.src.clone(),
+                    span: data.span.clone(),
+                    token: data.value.clone(),
+                    expected: expected_list,
+                    suggestion,
+                }
+            }
+            None => ParserError::EndOfInput {
+                src: self.src.clone(),
+                span: self.end_span(),
+                expected: expected_list,
+            },
+        }
+    }
+
+    /// Consumes the right parenthesis closing the one at `open`
+    pub fn assert_closing(&mut self, open: Span) -> Result<Span, ParserError> {
+        if let Some(close) = self.next_if(Token::RParen) {
+            return Ok(close);
+        }
+        let span = match self.peek() {
+            Ok(token) => token.span.clone(),
+            Err(_) => self.end_span(),
+        };
+        Err(ParserError::UnclosedParenthesis {
             src: self.src.clone(),
-            span: data.span.clone(),
-            description: description.to_string(),
-            token: data.value.clone(),
+            span,
+            opened: vec![ParserNote {
+                src: self.src.clone(),
+                span: open,
+                message: String::from("The parenthesis was opened here"),
+            }],
+        })
+    }
+
+    /// The most recently consumed token, which is missing after reading past the end
+    fn previous(&self) -> Option<&M<Token>> {
+        self.index
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+    }
+
+    fn previous_span(&self) -> Span {
+        match self.previous() {
+            Some(data) => data.span.clone(),
+            None => self.end_span(),
         }
     }
 
@@ -126,6 +285,7 @@ impl ParseInput {
         self.tokens.get(self.index).ok_or(ParserError::EndOfInput {
             src: self.src.clone(),
             span: self.end_span(),
+            expected: String::from("the rest of the query"),
         })
     }
 
@@ -135,6 +295,7 @@ impl ParseInput {
         result.ok_or(ParserError::EndOfInput {
             src: self.src.clone(),
             span: self.end_span(),
+            expected: String::from("the rest of the query"),
         })
     }
 
@@ -147,12 +308,11 @@ impl ParseInput {
         Span::from((offset, 0))
     }
 
-    pub fn assert_next(&mut self, token: Token, description: &str) -> Result<Span, ParserError> {
-        let next = self.next()?;
-        if next.value == token {
-            Ok(next.span.clone())
+    pub fn assert_next(&mut self, token: Token) -> Result<Span, ParserError> {
+        if matches!(self.next(), Ok(next) if next.value == token) {
+            Ok(self.previous_span())
         } else {
-            Err(self.unexpected_token(description))
+            Err(self.unexpected_token(&[&token.to_string()]))
         }
     }
 
@@ -181,11 +341,21 @@ impl ParseInput {
     // }
 }
 
+/// Parses a name, either a plain term or a quoted name in brackets (e.g. `['My Column']`)
 fn parse_term(input: &mut ParseInput) -> Result<M<String>, ParserError> {
     let token = input.next()?;
     match token.value.clone() {
         Token::Term(s) => Ok(M::new(s, token.span.clone())),
-        _ => Err(input.unexpected_token("Term expected")),
+        Token::LBracket => {
+            let open_bracket_sym = token.span.clone();
+            let name = match input.next()?.value.clone() {
+                Token::StringLiteral(name) => name,
+                _ => return Err(input.unexpected_token(&["a quoted name"])),
+            };
+            let close_bracket_sym = input.assert_next(Token::RBracket)?;
+            Ok(M::new_range(name, open_bracket_sym, close_bracket_sym))
+        }
+        _ => Err(input.unexpected_token(&["a name"])),
     }
 }
 
@@ -193,7 +363,7 @@ fn parse_dollar_term(input: &mut ParseInput) -> Result<M<String>, ParserError> {
     let token = input.next()?;
     match token.value.clone() {
         Token::DollarTerm(s) => Ok(M::new(s, token.span.clone())),
-        _ => Err(input.unexpected_token("Dollar term expected")),
+        _ => Err(input.unexpected_token(&["'$left'", "'$right'"])),
     }
 }
 
@@ -1,8 +1,11 @@
-use crate::ast::query::{NullsPosition, Query, SortOrder, TabularOperator};
-use crate::ast::{self, ColumnDefinition, Sorting, JoinParams, JoinKind, JoinAttribute};
+use crate::ast::query::{NullsPosition, Query, SortOrder, Source, TabularOperator};
+use crate::ast::{
+    self, ColumnDefinition, Expression, JoinAttribute, JoinKind, JoinParams, Literal, Sorting,
+    UnionKind, UnionParams,
+};
 
 use crate::lexer::Token;
-use crate::spans::{join_spans, span_precedes_span, M};
+use crate::spans::{join_spans, span_precedes_span, Span, M};
 
 use crate::parser::{parse_term, ParseInput, ParserError};
 
@@ -10,24 +13,88 @@ use super::expression::parse_expression;
 use super::parse_dollar_term;
 
 pub fn parse_query(input: &mut ParseInput) -> Result<Query, ParserError> {
-    let table = parse_term(input)?;
-    let operators = parse_operators(input)?;
+    let source = parse_source(input)?;
+    let operators = parse_operators(input);
 
-    Ok(Query { table, operators })
+    Ok(Query { source, operators })
 }
 
-fn parse_operators(
-    input: &mut ParseInput,
-) -> Result<Vec<(M<String>, TabularOperator)>, ParserError> {
+/// A table name or pattern, a parenthesized query or a parameter
+fn starts_union_table(token: &Token) -> bool {
+    matches!(token, Token::Term(_) | Token::Star | Token::LParen)
+}
+
+/// Parses a table name, or a leading union when a table or parameter follows "union"
+fn parse_source(input: &mut ParseInput) -> Result<Source, ParserError> {
+    let table = parse_term(input)?;
+    let followed_by_table = matches!(input.peek(), Ok(token) if starts_union_table(&token.value));
+    if table.value == "union" && followed_by_table {
+        let (params, tables) = parse_union_arguments(input)?;
+        return Ok(Source::Union {
+            union_kwd: table.span,
+            params,
+            tables,
+        });
+    }
+    Ok(Source::Table(table))
+}
+
+/// Parses operators until the end of the statement or an unmatched right parenthesis or brace.
+/// Operators which fail to parse are reported and skipped.
+pub fn parse_operators(input: &mut ParseInput) -> Vec<(M<String>, TabularOperator)> {
     let mut operators = Vec::new();
 
-    while input.next_if(Token::Pipe).is_some() {
-        operators.push(parse_operator(input)?);
+    loop {
+        if input.next_if(Token::Pipe).is_some() {
+            let start = input.checkpoint();
+            match parse_operator(input) {
+                Ok(operator) => operators.push(operator),
+                Err(error) => {
+                    input.report(error);
+                    input.recover(start, false);
+                }
+            }
+        } else if matches!(input.peek(), Ok(token) if !ends_operators(&token.value)) {
+            let start = input.checkpoint();
+            let _ = input.next();
+            let error = input.unexpected_token(&["'|'"]);
+            input.report(error);
+            input.recover(start, false);
+        } else {
+            break;
+        }
     }
 
-    Ok(operators)
+    operators
+}
+
+/// An unmatched right parenthesis or brace, or the end of the statement, ends the operators
+fn ends_operators(token: &Token) -> bool {
+    matches!(token, Token::RParen | Token::RBrace | Token::Semicolon)
 }
 
+/// The names of the tabular operators, as expected after a pipe
+const OPERATORS: &[&str] = &[
+    "'count'",
+    "'distinct'",
+    "'extend'",
+    "'invoke'",
+    "'join'",
+    "'limit'",
+    "'order'",
+    "'project'",
+    "'project-away'",
+    "'project-keep'",
+    "'project-rename'",
+    "'project-reorder'",
+    "'sort'",
+    "'summarize'",
+    "'take'",
+    "'top'",
+    "'union'",
+    "'where'",
+];
+
 fn parse_operator(input: &mut ParseInput) -> Result<(M<String>, TabularOperator), ParserError> {
     let operator_name = parse_kebab_term(input)?;
 
@@ -35,14 +102,24 @@ fn parse_operator(input: &mut ParseInput) -> Result<(M<String>, TabularOperator)
         "count" => TabularOperator::Count,
         "distinct" => parse_distinct(input)?,
         "extend" => parse_extend(input)?,
+        "invoke" => parse_invoke(input)?,
         "join" => parse_join(input)?,
         "limit" | "take" => parse_limit(input)?,
         "project" => parse_project(input)?,
+        "project-away" => TabularOperator::ProjectAway {
+            columns: parse_column_patterns(input)?,
+        },
+        "project-keep" => TabularOperator::ProjectKeep {
+            columns: parse_column_patterns(input)?,
+        },
+        "project-rename" => parse_project_rename(input)?,
+        "project-reorder" => parse_project_reorder(input)?,
         "sort" | "order" => parse_sort(input)?,
         "summarize" => parse_summarize(input)?,
         "top" => parse_top(input)?,
+        "union" => parse_union(input)?,
         "where" => parse_where(input)?,
-        _ => return Err(input.general_error("No tabular operator with this name")),
+        _ => return Err(input.unexpected_token(OPERATORS)),
     };
 
     Ok((operator_name, operator))
@@ -57,9 +134,11 @@ fn parse_kebab_term(input: &mut ParseInput) -> Result<M<String>, ParserError> {
         let checkpoint = input.checkpoint();
         let hyphen = input.next_if(Token::Sub);
 
-        if hyphen.is_some() {
+        if let Some(hyphen) = hyphen {
             if let Ok(term) = parse_term(input) {
-                if span_precedes_span(span.clone(), term.span.clone()) {
+                if span_precedes_span(span.clone(), hyphen.clone())
+                    && span_precedes_span(hyphen, term.span.clone())
+                {
                     span = join_spans(span, term.span.clone());
                     name.push('-');
                     name.push_str(&term.value);
@@ -98,42 +177,46 @@ fn parse_columns(input: &mut ParseInput) -> Result<ast::query::Columns, ParserEr
 }
 
 fn parse_extend(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
-    let mut columns = Vec::new();
+    let columns = parse_column_definitions(input, ColumnNaming::Scalar)?;
 
-    columns.push(parse_column_definition(input)?);
+    Ok(TabularOperator::Extend { columns })
+}
 
-    while input.next_if(Token::Comma).is_some() {
-        columns.push(parse_column_definition(input)?);
+fn parse_invoke(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    let call = parse_expression(input)?;
+    match *call.value {
+        Expression::FuncCall { name, args, .. } => Ok(TabularOperator::Invoke { name, args }),
+        _ => Err(input.general_error("expected a function call after invoke")),
     }
-
-    Ok(TabularOperator::Extend { columns })
 }
 
 fn parse_join(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
-    let checkpoint = input.checkpoint();
+    let kind = parse_join_params(input)?;
 
-    let kind = if input.next_if(Token::LParen).is_some() {
-        input.restore(checkpoint);
-        None
-    } else {
-        Some(parse_join_kind(input)?)
-    };
-        
     let params = JoinParams { kind };
 
-    let lparen = input.next()?;
-    if lparen.value != Token::LParen {
-        return Err(input.unexpected_token("Expected left paranthesis before table name"));
-    };
+    let right_table = if let Some(lparen) = input.next_if(Token::LParen) {
+        let table_query = parse_query(input)?;
 
-    let table_query = parse_query(input)?;
-    let right_table = Box::new(table_query);
+        input.assert_closing(lparen)?;
 
-    let rparen = input.next()?;
-    if rparen.value != Token::RParen {
-        return Err(input.unexpected_token("Expected right paranthesis after table name"));
+        Box::new(table_query)
+    } else {
+        let table = match parse_term(input) {
+            Ok(table) => table,
+            Err(_) => return Err(input.unexpected_token(&["a table name", "'('"])),
+        };
+        Box::new(Query {
+            source: Source::Table(table),
+            operators: Vec::new(),
+        })
     };
 
+    let on_kwd = parse_term(input)?;
+    if on_kwd.value != "on" {
+        return Err(input.unexpected_token(&["'on'"]));
+    }
+
     let mut attributes = Vec::new();
 
     loop {
@@ -149,6 +232,52 @@ fn parse_join(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
     Ok(TabularOperator::Join { params, right_table, attributes })
 }
 
+/// Parses the optional join kind, written either as `kind=inner` or just `inner`.
+fn parse_join_params(input: &mut ParseInput) -> Result<Option<JoinKind>, ParserError> {
+    let checkpoint = input.checkpoint();
+
+    match parse_term(input) {
+        Ok(term) if term.value == "kind" && input.next_if(Token::Assign).is_some() => {
+            Ok(Some(parse_join_kind(input)?))
+        }
+        Ok(_) => {
+            input.restore(checkpoint);
+            if is_join_kind(input) {
+                Ok(Some(parse_join_kind(input)?))
+            } else {
+                Ok(None)
+            }
+        }
+        Err(_) => {
+            input.restore(checkpoint);
+            Ok(None)
+        }
+    }
+}
+
+fn is_join_kind(input: &mut ParseInput) -> bool {
+    let checkpoint = input.checkpoint();
+    let result = parse_join_kind(input).is_ok();
+    input.restore(checkpoint);
+    result
+}
+
+/// The join kinds, as expected after `kind=`
+const JOIN_KINDS: &[&str] = &[
+    "'anti'",
+    "'fullouter'",
+    "'inner'",
+    "'innerunique'",
+    "'leftanti'",
+    "'leftantisemi'",
+    "'leftouter'",
+    "'leftsemi'",
+    "'rightanti'",
+    "'rightantisemi'",
+    "'rightouter'",
+    "'rightsemi'",
+];
+
 fn parse_join_kind(input: &mut ParseInput) -> Result<JoinKind, ParserError> {
     let term = parse_term(input)?;
     match term.value.as_str() {
@@ -157,11 +286,11 @@ fn parse_join_kind(input: &mut ParseInput) -> Result<JoinKind, ParserError> {
         "leftouter" => Ok(JoinKind::LeftOuter),
         "rightouter" => Ok(JoinKind::RightOuter),
         "fullouter" => Ok(JoinKind::FullOuter),
-        "leftanti" => Ok(JoinKind::LeftAnti),
-        "rightanti" => Ok(JoinKind::RightAnti),
+        "leftanti" | "anti" | "leftantisemi" => Ok(JoinKind::LeftAnti),
+        "rightanti" | "rightantisemi" => Ok(JoinKind::RightAnti),
         "leftsemi" => Ok(JoinKind::LeftSemi),
         "rightsemi" => Ok(JoinKind::RightSemi),
-        _ => return Err(input.unexpected_token("Expected join parameter or '(table_name)'")),
+        _ => Err(input.unexpected_token(JOIN_KINDS)),
     }
 }
 
@@ -173,81 +302,212 @@ fn parse_join_attribute(input: &mut ParseInput) -> Result<JoinAttribute, ParserE
             let name = M::new(s, token.span.clone());
             JoinAttribute::Matching{ name }
         },
+        Token::LBracket => {
+            input.restore(checkpoint);
+            JoinAttribute::Matching{ name: parse_term(input)? }
+        },
         Token::DollarTerm(_s) => {
             input.restore(checkpoint);
             let dollar_term = parse_dollar_term(input)?;
             if dollar_term.value.as_str() != "left" {
-                return Err(input.unexpected_token("'left' keyword expected"));
+                return Err(input.unexpected_token(&["'$left'"]));
             }
 
             let span = dollar_term.span.clone();
-            let dot = input.next()?;
-            if dot.value != Token::Dot {
-                return Err(input.unexpected_token("Dot expected"));
-            };
+            input.assert_next(Token::Dot)?;
             let left_kwd = span;
             let left_name = parse_term(input)?;
 
-            let eq = input.next()?;
-            if eq.value != Token::EQ {
-                return Err(input.unexpected_token("'==' expected"));
-            };
+            input.assert_next(Token::EQ)?;
 
             let dollar_term = parse_dollar_term(input)?;
             if dollar_term.value.as_str() != "right" {
-                return Err(input.unexpected_token("'right' keyword expected"));
+                return Err(input.unexpected_token(&["'$right'"]));
             }
             let right_kwd = dollar_term.span.clone();
 
-            let dot = input.next()?;
-            if dot.value != Token::Dot {
-                return Err(input.unexpected_token("Dot expected"));
-            };
+            input.assert_next(Token::Dot)?;
 
             let right_name = parse_term(input)?;
             JoinAttribute::NonMatching { left_kwd, left_name, right_kwd, right_name }
         },
-        _ => return Err(input.unexpected_token("Term expected")),
+        _ => return Err(input.unexpected_token(&["a column name", "'$left'"])),
     };
     Ok(attribute)
 }
 
 fn parse_limit(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    Ok(TabularOperator::Limit {
+        limit: parse_row_count(input)?,
+    })
+}
+
+fn parse_row_count(input: &mut ParseInput) -> Result<M<i64>, ParserError> {
     let token = input.next()?;
     let amount = match token.value {
         Token::IntLiteral(value) => value as i64,
         Token::LongLiteral(value) => value,
-        _ => return Err(input.unexpected_token("Expected number literal for limit argument")),
+        _ => return Err(input.unexpected_token(&["a number of rows"])),
     };
-    Ok(TabularOperator::Limit {
-        limit: M::new(amount, token.span.clone()),
-    })
+    Ok(M::new(amount, token.span.clone()))
 }
 
 fn parse_project(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
-    let mut columns = Vec::new();
-
     if input.peek()?.value == Token::Pipe {
-        return Ok(TabularOperator::Project { columns });
+        return Ok(TabularOperator::Project {
+            columns: Vec::new(),
+        });
     }
 
-    columns.push(parse_column_definition(input)?);
+    let columns = parse_column_definitions(input, ColumnNaming::Scalar)?;
 
+    Ok(TabularOperator::Project { columns })
+}
+
+/// Parses comma-separated column names, which may contain wildcards
+fn parse_column_patterns(input: &mut ParseInput) -> Result<Vec<M<String>>, ParserError> {
+    let mut columns = vec![parse_pattern(input, &["a column name"])?];
     while input.next_if(Token::Comma).is_some() {
-        columns.push(parse_column_definition(input)?);
+        columns.push(parse_pattern(input, &["a column name"])?);
     }
+    Ok(columns)
+}
 
-    Ok(TabularOperator::Project { columns })
+/// Parses comma-separated `NewName = ExistingName` pairs
+fn parse_project_rename(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    let mut columns = Vec::new();
+    loop {
+        let new_name = parse_term(input)?;
+        input.assert_next(Token::Assign)?;
+        columns.push((new_name, parse_term(input)?));
+
+        if input.next_if(Token::Comma).is_none() {
+            return Ok(TabularOperator::ProjectRename { columns });
+        }
+    }
 }
 
-fn parse_column_definition(input: &mut ParseInput) -> Result<ColumnDefinition, ParserError> {
-    let column = parse_term(input)?;
-    let expr = if input.next_if(Token::Assign).is_some() {
-        Some(parse_expression(input)?)
-    } else {
-        None
+/// Parses comma-separated column names or wildcards, each with an optional order
+fn parse_project_reorder(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    let mut columns = Vec::new();
+    loop {
+        let pattern = parse_pattern(input, &["a column name"])?;
+        columns.push((pattern, parse_sort_order(input)));
+
+        if input.next_if(Token::Comma).is_none() {
+            return Ok(TabularOperator::ProjectReorder { columns });
+        }
+    }
+}
+
+/// The rules an operator uses to name columns defined without `Name =`
+#[derive(Debug, Clone, Copy)]
+enum ColumnNaming {
+    /// `extend` and `project`: unnamed expressions become `Column1`, `Column2`, ...
+    Scalar,
+    /// `summarize` aggregates: `count()` becomes `count_`, `sum(Price)` becomes `sum_Price`
+    Aggregate,
+    /// `summarize ... by`: `bin(Timestamp, 1h)` keeps the name `Timestamp`
+    Grouping,
+}
+
+fn parse_column_definitions(
+    input: &mut ParseInput,
+    naming: ColumnNaming,
+) -> Result<Vec<ColumnDefinition>, ParserError> {
+    let mut columns = Vec::new();
+    let mut unnamed = 0;
+
+    loop {
+        let start = input.checkpoint();
+        match parse_column_definition(input, naming, &mut unnamed) {
+            Ok(column) => columns.push(column),
+            Err(error) => {
+                input.report(error);
+                input.recover(start, true);
+            }
+        }
+
+        if input.next_if(Token::Comma).is_none() {
+            break;
+        }
+    }
+
+    Ok(columns)
+}
+
+fn parse_column_definition(
+    input: &mut ParseInput,
+    naming: ColumnNaming,
+    unnamed: &mut usize,
+) -> Result<ColumnDefinition, ParserError> {
+    let checkpoint = input.checkpoint();
+    if let Ok(column) = parse_term(input) {
+        if input.next_if(Token::Assign).is_some() {
+            let expr = parse_expression(input)?;
+            return Ok(ColumnDefinition {
+                column,
+                expr: Some(expr),
+            });
+        }
+    }
+    input.restore(checkpoint);
+
+    let expr = parse_expression(input)?;
+    if let Expression::Identifier { name } = &*expr.value {
+        return Ok(ColumnDefinition {
+            column: name.clone(),
+            expr: None,
+        });
+    }
+
+    let name = default_column_name(&expr.value, naming).unwrap_or_else(|| {
+        *unnamed += 1;
+        format!("Column{}", unnamed)
+    });
+    Ok(ColumnDefinition {
+        column: M::new(name, expr.span.clone()),
+        expr: Some(expr),
+    })
+}
+
+/// Derives the name Kusto gives an unnamed column expression, if it has one.
+fn default_column_name(expr: &Expression, naming: ColumnNaming) -> Option<String> {
+    let (name, args) = match expr {
+        Expression::FuncCall { name, args, .. } => (name.value.as_str(), args),
+        _ => return None,
+    };
+    let first_column = match args.first().map(|arg| &*arg.value) {
+        Some(Expression::Identifier { name }) => Some(name.value.as_str()),
+        _ => None,
     };
-    Ok(ColumnDefinition { column, expr })
+
+    match naming {
+        ColumnNaming::Scalar => None,
+        ColumnNaming::Aggregate => match name {
+            "arg_max" | "arg_min" => first_column.map(String::from),
+            _ => {
+                let prefix = name.strip_prefix("make_").unwrap_or(name);
+                let mut column = format!("{}_{}", prefix, first_column.unwrap_or(""));
+                for arg in args.iter().skip(1) {
+                    match &*arg.value {
+                        Expression::Literal {
+                            value: Literal::Long(Some(value)),
+                        } => column.push_str(&format!("_{}", value)),
+                        Expression::Literal {
+                            value: Literal::Int(Some(value)),
+                        } => column.push_str(&format!("_{}", value)),
+                        _ => {}
+                    }
+                }
+                Some(column)
+            }
+        },
+        ColumnNaming::Grouping => match name {
+            "bin" | "floor" => first_column.map(String::from),
+            _ => None,
+        },
+    }
 }
 
 fn parse_sort(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
@@ -255,49 +515,16 @@ fn parse_sort(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
     let first_term = parse_term(input)?;
     let by_kwd = match first_term.value.as_str() {
         "by" => first_term.span.clone(),
-        _ => return Err(input.unexpected_token("Expected 'by' keyword")),
+        _ => return Err(input.unexpected_token(&["'by'"])),
     };
     loop {
         let column = match parse_term(input) {
             Ok(column) => column,
-            Err(_) => return Err(input.unexpected_token("Expected column name")),
+            Err(_) => return Err(input.unexpected_token(&["a column name"])),
         };
 
-        let checkpoint = input.checkpoint();
-
-        let order_term = parse_term(input)?;
-        let order = match order_term.value.as_str() {
-            "asc" => Some(M::new(SortOrder::Ascending, order_term.span.clone())),
-            "desc" => Some(M::new(SortOrder::Descending, order_term.span.clone())),
-            _ => {
-                input.restore(checkpoint);
-                None
-            }
-        };
-
-        let checkpoint = input.checkpoint();
-
-        let nulls_kwd = parse_term(input)?;
-        let nulls = match nulls_kwd.value.as_str() {
-            "nulls" => {
-                let nulls_pos = parse_term(input)?;
-                match nulls_pos.value.as_str() {
-                    "first" => Some((
-                        nulls_kwd.span.clone(),
-                        M::new(NullsPosition::First, nulls_pos.span.clone()),
-                    )),
-                    "last" => Some((
-                        nulls_kwd.span.clone(),
-                        M::new(NullsPosition::Last, nulls_pos.span.clone()),
-                    )),
-                    _ => return Err(input.unexpected_token("Expected nulls position")),
-                }
-            }
-            _ => {
-                input.restore(checkpoint);
-                None
-            }
-        };
+        let order = parse_sort_order(input);
+        let nulls = parse_nulls_position(input)?;
 
         let sorting = Sorting {
             column,
@@ -315,27 +542,63 @@ fn parse_sort(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
     Ok(TabularOperator::Sort { by_kwd, sortings })
 }
 
-fn parse_summarize(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
-    let mut result_columns: Vec<ColumnDefinition> = Vec::new();
-
-    result_columns.push(parse_column_definition(input)?);
+/// Parses an optional `asc` or `desc` keyword.
+fn parse_sort_order(input: &mut ParseInput) -> Option<M<SortOrder>> {
+    let checkpoint = input.checkpoint();
 
-    while input.next_if(Token::Comma).is_some() {
-        result_columns.push(parse_column_definition(input)?);
+    if let Ok(order_term) = parse_term(input) {
+        match order_term.value.as_str() {
+            "asc" => return Some(M::new(SortOrder::Ascending, order_term.span.clone())),
+            "desc" => return Some(M::new(SortOrder::Descending, order_term.span.clone())),
+            _ => {}
+        }
     }
 
-    let next_term = parse_term(input)?;
-    let by_kwd = match next_term.value.as_str() {
-        "by" => next_term.span.clone(),
-        _ => return Err(input.unexpected_token("Expected 'by' keyword")),
-    };
+    input.restore(checkpoint);
+    None
+}
 
-    let mut grouping_columns: Vec<ColumnDefinition> = Vec::new();
+/// Parses an optional `nulls first` or `nulls last` clause.
+fn parse_nulls_position(
+    input: &mut ParseInput,
+) -> Result<Option<(Span, M<NullsPosition>)>, ParserError> {
+    let checkpoint = input.checkpoint();
 
-    grouping_columns.push(parse_column_definition(input)?);
-    while input.next_if(Token::Comma).is_some() {
-        grouping_columns.push(parse_column_definition(input)?);
+    match parse_term(input) {
+        Ok(nulls_kwd) if nulls_kwd.value == "nulls" => {
+            let nulls_pos = parse_term(input)?;
+            match nulls_pos.value.as_str() {
+                "first" => Ok(Some((
+                    nulls_kwd.span.clone(),
+                    M::new(NullsPosition::First, nulls_pos.span.clone()),
+                ))),
+                "last" => Ok(Some((
+                    nulls_kwd.span.clone(),
+                    M::new(NullsPosition::Last, nulls_pos.span.clone()),
+                ))),
+                _ => Err(input.unexpected_token(&["'first'", "'last'"])),
+            }
+        }
+        _ => {
+            input.restore(checkpoint);
+            Ok(None)
+        }
     }
+}
+
+fn parse_summarize(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    let (result_columns, by_kwd) = match parse_by_keyword(input) {
+        Some(by_kwd) => (Vec::new(), Some(by_kwd)),
+        None => (
+            parse_column_definitions(input, ColumnNaming::Aggregate)?,
+            parse_by_keyword(input),
+        ),
+    };
+
+    let grouping_columns = match by_kwd {
+        Some(_) => parse_column_definitions(input, ColumnNaming::Grouping)?,
+        None => Vec::new(),
+    };
 
     Ok(TabularOperator::Summarize {
         result_columns,
@@ -344,8 +607,156 @@ fn parse_summarize(input: &mut ParseInput) -> Result<TabularOperator, ParserErro
     })
 }
 
+/// Parses an optional `by` keyword.
+fn parse_by_keyword(input: &mut ParseInput) -> Option<Span> {
+    let checkpoint = input.checkpoint();
+
+    match parse_term(input) {
+        Ok(term) if term.value == "by" => Some(term.span.clone()),
+        _ => {
+            input.restore(checkpoint);
+            None
+        }
+    }
+}
+
 fn parse_top(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
-    Err(input.unsupported_error("top operator"))
+    let limit = parse_row_count(input)?;
+
+    let by_term = parse_term(input)?;
+    let by_kwd = match by_term.value.as_str() {
+        "by" => by_term.span.clone(),
+        _ => return Err(input.unexpected_token(&["'by'"])),
+    };
+
+    let expr = parse_expression(input)?;
+    let order = parse_sort_order(input);
+    let nulls = parse_nulls_position(input)?;
+
+    Ok(TabularOperator::Top {
+        limit,
+        by_kwd,
+        expr,
+        order,
+        nulls,
+    })
+}
+
+fn parse_union(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
+    let (params, tables) = parse_union_arguments(input)?;
+    Ok(TabularOperator::Union { params, tables })
+}
+
+/// Parses the parameters and comma-separated tables of a union
+fn parse_union_arguments(
+    input: &mut ParseInput,
+) -> Result<(UnionParams, Vec<Query>), ParserError> {
+    let params = parse_union_params(input)?;
+
+    let mut tables = vec![parse_union_table(input)?];
+    while input.next_if(Token::Comma).is_some() {
+        tables.push(parse_union_table(input)?);
+    }
+
+    Ok((params, tables))
+}
+
+/// Parses `kind=`, `withsource=` and `isfuzzy=` in any order
+fn parse_union_params(input: &mut ParseInput) -> Result<UnionParams, ParserError> {
+    let mut params = UnionParams::default();
+
+    loop {
+        let checkpoint = input.checkpoint();
+        let name = match parse_term(input) {
+            Ok(name) if input.next_if(Token::Assign).is_some() => name,
+            _ => {
+                input.restore(checkpoint);
+                return Ok(params);
+            }
+        };
+
+        match name.value.as_str() {
+            "kind" => {
+                let kind = parse_term(input)?;
+                let value = match kind.value.as_str() {
+                    "inner" => UnionKind::Inner,
+                    "outer" => UnionKind::Outer,
+                    _ => return Err(input.unexpected_token(&["'inner'", "'outer'"])),
+                };
+                params.kind = Some(M::new(value, kind.span));
+            }
+            "withsource" => params.with_source = Some(parse_term(input)?),
+            "isfuzzy" => {
+                let token = input.next()?;
+                match token.value {
+                    Token::BoolLiteral(value) => {
+                        params.is_fuzzy = Some(M::new(value, token.span.clone()))
+                    }
+                    _ => return Err(input.unexpected_token(&["'true'", "'false'"])),
+                }
+            }
+            _ => {
+                input.restore(checkpoint);
+                let _ = input.next();
+                return Err(input.unexpected_token(&["'kind'", "'withsource'", "'isfuzzy'"]));
+            }
+        }
+    }
+}
+
+/// Parses a table name or pattern, or a parenthesized query
+fn parse_union_table(input: &mut ParseInput) -> Result<Query, ParserError> {
+    if let Some(lparen) = input.next_if(Token::LParen) {
+        let query = parse_query(input)?;
+        input.assert_closing(lparen)?;
+        return Ok(query);
+    }
+
+    Ok(Query {
+        source: Source::Table(parse_pattern(input, &["a table name", "'('"])?),
+        operators: Vec::new(),
+    })
+}
+
+/// Parses a name in which `*` may stand for any text (e.g. `Events*`)
+fn parse_pattern(input: &mut ParseInput, expected: &[&str]) -> Result<M<String>, ParserError> {
+    let mut pattern: Option<M<String>> = None;
+
+    loop {
+        let checkpoint = input.checkpoint();
+        let token = match input.next() {
+            Ok(token) => token.clone(),
+            Err(_) => break,
+        };
+        let part = match &token.value {
+            Token::Term(name) => name.as_str(),
+            Token::Star => "*",
+            _ if pattern.is_none() => return Err(input.unexpected_token(expected)),
+            _ => {
+                input.restore(checkpoint);
+                break;
+            }
+        };
+
+        pattern = match pattern {
+            None => Some(M::new(part.to_string(), token.span.clone())),
+            Some(pattern) if span_precedes_span(pattern.span.clone(), token.span.clone()) => {
+                Some(M::new(
+                    pattern.value + part,
+                    join_spans(pattern.span, token.span.clone()),
+                ))
+            }
+            Some(pattern) => {
+                input.restore(checkpoint);
+                return Ok(pattern);
+            }
+        };
+    }
+
+    match pattern {
+        Some(pattern) => Ok(pattern),
+        None => Err(input.unexpected_token(expected)),
+    }
 }
 
 fn parse_where(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
@@ -355,10 +766,48 @@ fn parse_where(input: &mut ParseInput) -> Result<TabularOperator, ParserError> {
 
 #[cfg(test)]
 mod tests {
-    use miette::Report;
+    use miette::{NamedSource, Report};
+    use std::sync::Arc;
 
     use super::*;
-    use crate::parser::tests::make_input;
+    use crate::{ast::Statement, lexer::tokenize, parser::parse, parser::tests::make_input};
+
+    #[test]
+    fn parse_recovers_at_pipes_and_commas() {
+        let source = "T | where | extend a = , b = 1 | take 5 | frob x";
+        let src = Arc::new(NamedSource::new("test", source.to_string()));
+        let tokens = tokenize(src.clone(), source.to_string()).unwrap();
+        let (script, errors) = parse(src, tokens);
+        let query = match script.statements.into_iter().next() {
+            Some(Statement::Query(query)) => query,
+            _ => panic!(),
+        };
+
+        let names: Vec<&str> = query
+            .operators
+            .iter()
+            .map(|(name, _)| name.value.as_str())
+            .collect();
+        assert_eq!(names, vec!["extend", "take"]);
+        match &query.operators[0].1 {
+            TabularOperator::Extend { columns } => assert_eq!(columns.len(), 1),
+            _ => panic!(),
+        }
+        assert_eq!(errors.len(), 3);
+    }
+
+    #[test]
+    fn parse_operator_suggests_known_operators() {
+        match parse_operator(&mut make_input("sumarize count()")) {
+            Err(ParserError::UnexpectedToken {
+                token, suggestion, ..
+            }) => {
+                assert_eq!(token, Token::Term(String::from("sumarize")));
+                assert_eq!(suggestion, "; did you mean 'summarize'?");
+            }
+            _ => panic!(),
+        }
+    }
 
     #[test]
     fn parse_summarize_supports_groupings() {
@@ -373,6 +822,97 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_summarize_names_unnamed_columns() {
+        let source = "count(), sum(Price), percentile(Duration, 95), Total=sum(Price) + 1 by bin(Size, 10), Size * 2";
+        let result = parse_summarize(&mut make_input(source));
+        match result {
+            Ok(TabularOperator::Summarize {
+                result_columns,
+                by_kwd,
+                grouping_columns,
+            }) => {
+                let names = |columns: Vec<ColumnDefinition>| -> Vec<String> {
+                    columns.into_iter().map(|col| col.column.value).collect()
+                };
+                assert!(by_kwd.is_some());
+                assert_eq!(
+                    names(result_columns),
+                    vec!["count_", "sum_Price", "percentile_Duration_95", "Total"]
+                );
+                assert_eq!(names(grouping_columns), vec!["Size", "Column1"]);
+            }
+            Ok(_) => panic!(),
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_summarize_supports_missing_groupings() {
+        let cases = [("count()", 1, false), ("by Fruit", 0, true)];
+        for (source, result_count, has_by) in cases {
+            match parse_summarize(&mut make_input(source)) {
+                Ok(TabularOperator::Summarize {
+                    result_columns,
+                    by_kwd,
+                    ..
+                }) => {
+                    assert_eq!(result_columns.len(), result_count);
+                    assert_eq!(by_kwd.is_some(), has_by);
+                }
+                Ok(_) => panic!(),
+                Err(error) => {
+                    println!("{:?}", Report::new(error));
+                    panic!();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_sort_supports_orderings() {
+        let source = "by a desc nulls last, b asc, c nulls first, d";
+        let result = parse_sort(&mut make_input(source));
+        match result {
+            Ok(TabularOperator::Sort { sortings, .. }) => assert_eq!(sortings.len(), 4),
+            Ok(_) => panic!(),
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_top_supports_ordering() {
+        let source = "5 by Size * 2 asc nulls first";
+        let mut input = make_input(source);
+        match parse_top(&mut input) {
+            Ok(TabularOperator::Top {
+                limit,
+                order,
+                nulls,
+                ..
+            }) => {
+                assert_eq!(limit.value, 5);
+                assert_eq!(order.map(|order| order.value), Some(SortOrder::Ascending));
+                assert_eq!(
+                    nulls.map(|(_, nulls)| nulls.value),
+                    Some(NullsPosition::First)
+                );
+                assert!(input.done());
+            }
+            Ok(_) => panic!(),
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
     #[test]
     fn parse_join_supports_attributes() {
         let source = "(Table2) on CommonColumn, $left.Col1 == $right.Col2";
@@ -386,6 +926,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_query_supports_bracketed_names() {
+        let source = "['My Table'] | project [\"Total Cost\"] = ['a.b'] | join (T) on ['a.b']";
+        let query = parse_query(&mut make_input(source)).unwrap();
+        assert_eq!(query.table().unwrap().value, "My Table");
+        match &query.operators[..] {
+            [
+                (_, TabularOperator::Project { columns }),
+                (_, TabularOperator::Join { attributes, .. }),
+            ] => {
+                assert_eq!(columns[0].column.value, "Total Cost");
+                assert!(matches!(
+                    columns[0].expr.as_ref().map(|expr| &*expr.value),
+                    Some(Expression::Identifier { name }) if name.value == "a.b"
+                ));
+                assert!(matches!(
+                    &attributes[..],
+                    [JoinAttribute::Matching { name }] if name.value == "a.b"
+                ));
+            }
+            operators => panic!("{:?}", operators),
+        }
+    }
+
     #[test]
     fn parse_join_supports_kind() {
         let source = "rightouter (Table) on $left.Col1 == $right.Col2";
@@ -398,4 +962,119 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn parse_union_supports_parameters_and_patterns() {
+        let source = "withsource=Source kind=inner isfuzzy=true Logs*, (Other | take 5), Audit_2022*Old";
+        let mut input = make_input(source);
+        match parse_union(&mut input) {
+            Ok(TabularOperator::Union { params, tables }) => {
+                assert_eq!(params.kind.map(|kind| kind.value), Some(UnionKind::Inner));
+                assert_eq!(params.with_source.map(|name| name.value), Some("Source".into()));
+                assert_eq!(params.is_fuzzy.map(|fuzzy| fuzzy.value), Some(true));
+                let names: Vec<&str> = tables
+                    .iter()
+                    .map(|table| table.table().unwrap().value.as_str())
+                    .collect();
+                assert_eq!(names, vec!["Logs*", "Other", "Audit_2022*Old"]);
+                assert_eq!(tables[1].operators.len(), 1);
+                assert!(input.done());
+            }
+            Ok(_) => panic!(),
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_query_supports_project_variants() {
+        let source = "T | project-away Temp*, Id | project-rename New = Old, B = A \
+            | project-reorder *Time desc, Name | project-keep Name";
+        let mut input = make_input(source);
+        match parse_query(&mut input) {
+            Ok(Query { operators, .. }) => {
+                let names: Vec<&str> =
+                    operators.iter().map(|(name, _)| name.value.as_str()).collect();
+                assert_eq!(
+                    names,
+                    vec!["project-away", "project-rename", "project-reorder", "project-keep"]
+                );
+                match &operators[0].1 {
+                    TabularOperator::ProjectAway { columns } => {
+                        let columns: Vec<&str> =
+                            columns.iter().map(|column| column.value.as_str()).collect();
+                        assert_eq!(columns, vec!["Temp*", "Id"]);
+                    }
+                    operator => panic!("{:?}", operator),
+                }
+                assert!(matches!(
+                    &operators[1].1,
+                    TabularOperator::ProjectRename { columns } if columns.len() == 2
+                ));
+                match &operators[2].1 {
+                    TabularOperator::ProjectReorder { columns } => {
+                        assert_eq!(columns[0].0.value, "*Time");
+                        assert_eq!(
+                            columns[0].1.as_ref().map(|order| &order.value),
+                            Some(&SortOrder::Descending)
+                        );
+                        assert!(columns[1].1.is_none());
+                    }
+                    operator => panic!("{:?}", operator),
+                }
+                assert!(input.done());
+            }
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_query_supports_leading_union() {
+        let mut input = make_input("union T1, T2* | where a > 1");
+        match parse_query(&mut input) {
+            Ok(Query {
+                source: Source::Union { tables, .. },
+                operators,
+            }) => {
+                assert_eq!(tables.len(), 2);
+                assert_eq!(operators.len(), 1);
+            }
+            Ok(_) => panic!(),
+            Err(error) => {
+                println!("{:?}", Report::new(error));
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_join_supports_kind_parameter() {
+        let cases = [
+            ("kind=leftanti (Table) on Col", Some(JoinKind::LeftAnti), 1),
+            ("kind=inner Table on Col1, Col2", Some(JoinKind::Inner), 2),
+            ("Table on Col", None, 1),
+        ];
+        for (source, expected_kind, attribute_count) in cases {
+            let mut input = make_input(source);
+            match parse_join(&mut input) {
+                Ok(TabularOperator::Join {
+                    params, attributes, ..
+                }) => {
+                    assert_eq!(params.kind, expected_kind);
+                    assert_eq!(attributes.len(), attribute_count);
+                    assert!(input.done());
+                }
+                Ok(_) => panic!(),
+                Err(error) => {
+                    println!("{:?}", Report::new(error));
+                    panic!();
+                }
+            }
+        }
+    }
 }
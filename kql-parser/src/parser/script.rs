@@ -0,0 +1,317 @@
+use crate::ast::script::{
+    Function, FunctionBody, LetValue, Parameter, ParameterKind, Script, Statement,
+};
+use crate::ast::Query;
+use crate::lexer::Token;
+use crate::spans::{Span, M};
+
+use crate::parser::{parse_term, ParseInput, ParserError};
+
+use super::expression::parse_expression;
+use super::query::{parse_operators, parse_query};
+
+/// Parses semicolon-separated statements until the end of the input.
+/// Statements which fail to parse are reported and skipped.
+pub fn parse_script(input: &mut ParseInput) -> Script {
+    let mut statements = Vec::new();
+
+    while !input.done() {
+        if input.next_if(Token::Semicolon).is_some() {
+            continue;
+        }
+
+        let start = input.checkpoint();
+        match parse_statement(input) {
+            Ok(statement) => statements.push(statement),
+            Err(error) => {
+                input.report(error);
+                input.skip_statement(start);
+                continue;
+            }
+        }
+
+        // only an unmatched right parenthesis, or a token following a scalar expression,
+        // stops a statement before the next semicolon
+        while matches!(input.peek(), Ok(token) if token.value != Token::Semicolon) {
+            let start = input.checkpoint();
+            let _ = input.next();
+            match statements.last_mut().and_then(tabular_value) {
+                Some(query) => {
+                    let error = input.unexpected_token(&["'|'", "';'"]);
+                    input.report(error);
+                    query.operators.extend(parse_operators(input));
+                }
+                None => {
+                    let error = input.unexpected_token(&["';'"]);
+                    input.report(error);
+                    input.skip_statement(start);
+                }
+            }
+        }
+    }
+
+    Script { statements }
+}
+
+/// The query of a statement, which operators can be added to
+fn tabular_value(statement: &mut Statement) -> Option<&mut Query> {
+    match statement {
+        Statement::Query(query) => Some(query),
+        Statement::Let {
+            value: LetValue::Tabular(query),
+            ..
+        } => Some(query),
+        Statement::Let { .. } => None,
+    }
+}
+
+fn parse_statement(input: &mut ParseInput) -> Result<Statement, ParserError> {
+    match parse_let_keyword(input) {
+        Some(let_kwd) => parse_let(input, let_kwd),
+        None => Ok(Statement::Query(parse_query(input)?)),
+    }
+}
+
+/// "let" is only a keyword when a name follows it, otherwise it is a table name
+fn parse_let_keyword(input: &mut ParseInput) -> Option<Span> {
+    let start = input.checkpoint();
+    if let Ok(keyword) = parse_term(input) {
+        if keyword.value == "let"
+            && matches!(input.peek(), Ok(token) if matches!(token.value, Token::Term(_)))
+        {
+            return Some(keyword.span);
+        }
+    }
+    input.restore(start);
+    None
+}
+
+fn parse_let(input: &mut ParseInput, let_kwd: Span) -> Result<Statement, ParserError> {
+    let name = parse_term(input)?;
+    input.assert_next(Token::Assign)?;
+
+    let value = if is_function(input) {
+        LetValue::Function(parse_function(input)?)
+    } else {
+        parse_value(input)?
+    };
+
+    Ok(Statement::Let {
+        let_kwd,
+        name,
+        value,
+    })
+}
+
+fn parse_value(input: &mut ParseInput) -> Result<LetValue, ParserError> {
+    if is_tabular(input) {
+        Ok(LetValue::Tabular(parse_query(input)?))
+    } else {
+        Ok(LetValue::Scalar(parse_expression(input)?))
+    }
+}
+
+/// A table name followed by a pipe starts a tabular expression
+fn is_tabular(input: &mut ParseInput) -> bool {
+    let start = input.checkpoint();
+    let tabular = parse_term(input).is_ok() && input.next_if(Token::Pipe).is_some();
+    input.restore(start);
+    tabular
+}
+
+/// A parameter list, `()` followed by a body or `(name:`, starts a function
+fn is_function(input: &mut ParseInput) -> bool {
+    let start = input.checkpoint();
+    let function = match input.next_if(Token::LParen) {
+        None => false,
+        Some(_) if input.next_if(Token::RParen).is_some() => input.next_if(Token::LBrace).is_some(),
+        Some(_) => parse_term(input).is_ok() && input.next_if(Token::Colon).is_some(),
+    };
+    input.restore(start);
+    function
+}
+
+fn parse_function(input: &mut ParseInput) -> Result<Function, ParserError> {
+    let open_paren_sym = input.assert_next(Token::LParen)?;
+    let mut params = Vec::new();
+    if input.peek()?.value != Token::RParen {
+        params.push(parse_parameter(input)?);
+        while input.next_if(Token::Comma).is_some() {
+            params.push(parse_parameter(input)?);
+        }
+    }
+    let close_paren_sym = input.assert_closing(open_paren_sym.clone())?;
+    let body = parse_function_body(input)?;
+
+    Ok(Function {
+        open_paren_sym,
+        params,
+        close_paren_sym,
+        body,
+    })
+}
+
+/// Parses `name:type`, `name:(*)` or `name:(column:type, ...)`, with an optional default
+fn parse_parameter(input: &mut ParseInput) -> Result<Parameter, ParserError> {
+    let name = parse_term(input)?;
+    input.assert_next(Token::Colon)?;
+
+    let kind = if let Some(open_paren_sym) = input.next_if(Token::LParen) {
+        let columns = if input.next_if(Token::Star).is_some() {
+            None
+        } else {
+            let mut columns = vec![parse_typed_name(input)?];
+            while input.next_if(Token::Comma).is_some() {
+                columns.push(parse_typed_name(input)?);
+            }
+            Some(columns)
+        };
+        input.assert_closing(open_paren_sym)?;
+        ParameterKind::Tabular { columns }
+    } else {
+        ParameterKind::Scalar {
+            data_type: parse_term(input)?,
+        }
+    };
+
+    let default = match input.next_if(Token::Assign) {
+        Some(_) => Some(parse_expression(input)?),
+        None => None,
+    };
+
+    Ok(Parameter {
+        name,
+        kind,
+        default,
+    })
+}
+
+fn parse_typed_name(input: &mut ParseInput) -> Result<(M<String>, M<String>), ParserError> {
+    let name = parse_term(input)?;
+    input.assert_next(Token::Colon)?;
+    let data_type = parse_term(input)?;
+    Ok((name, data_type))
+}
+
+/// Parses `{ let ...; ...; value }`
+fn parse_function_body(input: &mut ParseInput) -> Result<FunctionBody, ParserError> {
+    let open_brace_sym = input.assert_next(Token::LBrace)?;
+
+    let mut statements = Vec::new();
+    while let Some(let_kwd) = parse_let_keyword(input) {
+        statements.push(parse_let(input, let_kwd)?);
+        input.assert_next(Token::Semicolon)?;
+    }
+    let value = Box::new(parse_value(input)?);
+    input.next_if(Token::Semicolon);
+
+    let close_brace_sym = input.assert_next(Token::RBrace)?;
+
+    Ok(FunctionBody {
+        open_brace_sym,
+        statements,
+        value,
+        close_brace_sym,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::NamedSource;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        ast::{Expression, TabularOperator},
+        lexer::tokenize,
+        parser::parse,
+        parser::tests::make_input,
+    };
+
+    #[test]
+    fn parse_script_supports_let_statements() {
+        let source = "let threshold = 100;\nlet Recent = Events | where Damage > threshold;\nRecent | take 5";
+        let script = parse_script(&mut make_input(source));
+
+        match &script.statements[..] {
+            [Statement::Let {
+                name: scalar_name,
+                value: LetValue::Scalar(scalar),
+                ..
+            }, Statement::Let {
+                name: tabular_name,
+                value: LetValue::Tabular(tabular),
+                ..
+            }, Statement::Query(query)] => {
+                assert_eq!(scalar_name.value, "threshold");
+                assert!(matches!(*scalar.value, Expression::Literal { .. }));
+                assert_eq!(tabular_name.value, "Recent");
+                assert_eq!(tabular.table().unwrap().value, "Events");
+                assert_eq!(tabular.operators.len(), 1);
+                assert_eq!(query.table().unwrap().value, "Recent");
+            }
+            statements => panic!("{:?}", statements),
+        }
+    }
+
+    #[test]
+    fn parse_let_supports_functions() {
+        let source = "let f = (T:(*), n:long, s:string = 'x') { let m = n * 2; T | where a > m };\nEvents | invoke f(1)";
+        let script = parse_script(&mut make_input(source));
+
+        match &script.statements[..] {
+            [Statement::Let {
+                value: LetValue::Function(function),
+                ..
+            }, Statement::Query(query)] => {
+                let kinds: Vec<&ParameterKind> =
+                    function.params.iter().map(|param| &param.kind).collect();
+                assert!(matches!(
+                    kinds[..],
+                    [
+                        ParameterKind::Tabular { columns: None },
+                        ParameterKind::Scalar { .. },
+                        ParameterKind::Scalar { .. }
+                    ]
+                ));
+                assert!(function.params[2].default.is_some());
+                assert_eq!(function.body.statements.len(), 1);
+                assert!(matches!(*function.body.value, LetValue::Tabular(_)));
+                assert!(matches!(
+                    &query.operators[0].1,
+                    TabularOperator::Invoke { args, .. } if args.len() == 1
+                ));
+            }
+            statements => panic!("{:?}", statements),
+        }
+    }
+
+    #[test]
+    fn parse_let_distinguishes_functions_from_parentheses() {
+        let source = "let a = (1 + 2); let b = () { 3 }; T";
+        let script = parse_script(&mut make_input(source));
+
+        match &script.statements[..] {
+            [Statement::Let {
+                value: LetValue::Scalar(_),
+                ..
+            }, Statement::Let {
+                value: LetValue::Function(_),
+                ..
+            }, Statement::Query(_)] => {}
+            statements => panic!("{:?}", statements),
+        }
+    }
+
+    #[test]
+    fn parse_recovers_at_semicolons() {
+        let source = "let x = ; let y = 1 2; T | where a > y";
+        let src = Arc::new(NamedSource::new("test", source.to_string()));
+        let tokens = tokenize(src.clone(), source.to_string()).unwrap();
+        let (script, errors) = parse(src, tokens);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(script.statements.len(), 2);
+        assert!(matches!(script.statements[1], Statement::Query(_)));
+    }
+}
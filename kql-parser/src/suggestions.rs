@@ -0,0 +1,51 @@
+/// Finds the candidate closest to a misspelled name, if one is close enough to be a likely typo
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance, ignoring case
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(substitution.min(insertion).min(deletion));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("summarize", "summarize"), 0);
+        assert_eq!(edit_distance("sumarize", "summarize"), 1);
+        assert_eq!(edit_distance("Where", "where"), 0);
+        assert_eq!(edit_distance("take", "top"), 3);
+        assert_eq!(edit_distance("", "by"), 2);
+    }
+
+    #[test]
+    fn closest_ignores_distant_candidates() {
+        let operators = ["summarize", "sort", "where", "take"];
+        assert_eq!(closest("sumarize", operators), Some("summarize"));
+        assert_eq!(closest("wher", operators), Some("where"));
+        assert_eq!(closest("frobnicate", operators), None);
+    }
+}
@@ -1,46 +1,86 @@
 use std::fmt;
 
 pub struct SelectStatement {
+    /// Common table expressions, named queries which the rest of the statement can read
+    pub with: Vec<CommonTableExpression>,
     pub modifier: Option<Modifier>,
     pub select: SelectList,
     pub from: TableReference,
     pub where_: Option<Box<SearchCondition>>,
+    pub group_by: Option<GroupByClause>,
     pub order_by: Option<OrderByClause>,
     pub limit: Option<i64>,
 }
 
 impl SelectStatement {
     pub fn simple(table: String) -> Self {
+        SelectStatement::selecting_from(TableReference::TableName {
+            name: table,
+            alias: None,
+        })
+    }
+
+    pub fn simple_wrapping(other: Self) -> Self {
+        SelectStatement::selecting_from(TableReference::InnerStatement {
+            value: Box::new(other),
+            alias: None,
+        })
+    }
+
+    pub fn selecting_from(from: TableReference) -> Self {
         SelectStatement {
+            with: vec![],
             modifier: None,
             select: SelectList {
                 wildcard: true,
                 columns: vec![],
             },
-            from: TableReference::TableName { name: table },
+            from,
             where_: None,
+            group_by: None,
             order_by: None,
             limit: None,
         }
     }
 
-    pub fn simple_wrapping(other: Self) -> Self {
-        SelectStatement {
-            modifier: None,
-            select: SelectList {
-                wildcard: true,
-                columns: vec![],
-            },
-            from: TableReference::InnerStatement {
-                value: Box::new(other),
+    /// Whether the statement does nothing besides reading all of its source
+    pub fn is_plain(&self) -> bool {
+        self.with.is_empty()
+            && self.modifier.is_none()
+            && self.select.wildcard
+            && self.select.columns.is_empty()
+            && self.where_.is_none()
+            && self.group_by.is_none()
+            && self.order_by.is_none()
+            && self.limit.is_none()
+    }
+
+    /// Turns the statement into an aliased table reference, using the table
+    /// directly when the statement does nothing besides reading it.
+    pub fn into_table_reference(self, alias: String) -> TableReference {
+        let is_plain = self.is_plain();
+
+        match self.from {
+            TableReference::TableName { name, alias: None } if is_plain => {
+                TableReference::TableName {
+                    name,
+                    alias: Some(alias),
+                }
+            }
+            from => TableReference::InnerStatement {
+                value: Box::new(SelectStatement { from, ..self }),
+                alias: Some(alias),
             },
-            where_: None,
-            order_by: None,
-            limit: None,
         }
     }
 }
 
+/// A named query in a WITH clause, `name AS (query)`
+pub struct CommonTableExpression {
+    pub name: String,
+    pub query: SelectStatement,
+}
+
 pub enum Modifier {
     All,
     Distinct,
@@ -51,14 +91,85 @@ pub struct SelectList {
     pub columns: Vec<SelectColumn>,
 }
 
+impl SelectList {
+    /// The names of the selected columns, if they are all known
+    pub fn column_names(&self) -> Option<Vec<String>> {
+        if self.wildcard {
+            return None;
+        }
+
+        self.columns
+            .iter()
+            .map(|column| match (&column.alias, &*column.value) {
+                (Some(alias), _) => Some(alias.clone()),
+                (None, ValueExpression::Column { name }) => Some(name.clone()),
+                (None, ValueExpression::QualifiedColumn { name, .. }) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 pub struct SelectColumn {
     pub value: Box<ValueExpression>,
     pub alias: Option<String>,
 }
 
 pub enum TableReference {
-    TableName { name: String },
-    InnerStatement { value: Box<SelectStatement> },
+    TableName {
+        name: String,
+        alias: Option<String>,
+    },
+    InnerStatement {
+        value: Box<SelectStatement>,
+        alias: Option<String>,
+    },
+    Join {
+        left: Box<TableReference>,
+        join_type: JoinType,
+        right: Box<TableReference>,
+        on: Box<SearchCondition>,
+    },
+    /// The rows of several statements combined by a set operation
+    SetOperation {
+        op: SetOperator,
+        statements: Vec<SelectStatement>,
+        alias: Option<String>,
+    },
+}
+
+pub enum SetOperator {
+    /// Keeps every row, including duplicates
+    UnionAll,
+    /// Keeps distinct rows
+    Union,
+}
+
+impl fmt::Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetOperator::UnionAll => write!(f, "UNION ALL"),
+            SetOperator::Union => write!(f, "UNION"),
+        }
+    }
+}
+
+pub enum JoinType {
+    Inner,
+    LeftOuter,
+    RightOuter,
+    FullOuter,
+}
+
+impl fmt::Display for JoinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinType::Inner => write!(f, "INNER JOIN"),
+            JoinType::LeftOuter => write!(f, "LEFT OUTER JOIN"),
+            JoinType::RightOuter => write!(f, "RIGHT OUTER JOIN"),
+            JoinType::FullOuter => write!(f, "FULL OUTER JOIN"),
+        }
+    }
 }
 
 pub enum SearchCondition {
@@ -72,6 +183,45 @@ pub enum SearchCondition {
         op: ComparisonOperator,
         right: Box<ValueExpression>,
     },
+    /// `EXISTS (subquery)` or `NOT EXISTS (subquery)`
+    Exists {
+        negated: bool,
+        query: Box<SelectStatement>,
+    },
+    /// `value IS NULL` or `value IS NOT NULL`
+    IsNull {
+        value: Box<ValueExpression>,
+        negated: bool,
+    },
+    Not {
+        condition: Box<SearchCondition>,
+    },
+    /// `value [NOT] LIKE pattern`, using ILIKE when case-insensitive
+    Like {
+        value: Box<ValueExpression>,
+        pattern: Box<ValueExpression>,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    /// `value [NOT] RLIKE pattern`
+    Regex {
+        value: Box<ValueExpression>,
+        pattern: Box<ValueExpression>,
+        negated: bool,
+    },
+    /// `value [NOT] IN (list...)`
+    In {
+        value: Box<ValueExpression>,
+        list: Vec<Box<ValueExpression>>,
+        negated: bool,
+    },
+    /// `value [NOT] BETWEEN low AND high`
+    Between {
+        value: Box<ValueExpression>,
+        low: Box<ValueExpression>,
+        high: Box<ValueExpression>,
+        negated: bool,
+    },
 }
 
 impl SearchCondition {
@@ -83,6 +233,49 @@ impl SearchCondition {
             SearchCondition::ComparisonExpr { left, op, right } => {
                 left.depends_on_any(columns) || right.depends_on_any(columns)
             }
+            SearchCondition::Exists { .. } => false,
+            SearchCondition::IsNull { value, .. } => value.depends_on_any(columns),
+            SearchCondition::Not { condition } => condition.depends_on_any(columns),
+            SearchCondition::Like { value, pattern, .. }
+            | SearchCondition::Regex { value, pattern, .. } => {
+                value.depends_on_any(columns) || pattern.depends_on_any(columns)
+            }
+            SearchCondition::In { value, list, .. } => {
+                value.depends_on_any(columns) || list.iter().any(|v| v.depends_on_any(columns))
+            }
+            SearchCondition::Between {
+                value, low, high, ..
+            } => {
+                value.depends_on_any(columns)
+                    || low.depends_on_any(columns)
+                    || high.depends_on_any(columns)
+            }
+        }
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            SearchCondition::BoolExpr { left, op, right } => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            SearchCondition::ComparisonExpr { left, op, right } => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            SearchCondition::Exists { .. } => false,
+            SearchCondition::IsNull { value, .. } => value.contains_aggregate(),
+            SearchCondition::Not { condition } => condition.contains_aggregate(),
+            SearchCondition::Like { value, pattern, .. }
+            | SearchCondition::Regex { value, pattern, .. } => {
+                value.contains_aggregate() || pattern.contains_aggregate()
+            }
+            SearchCondition::In { value, list, .. } => {
+                value.contains_aggregate() || list.iter().any(|v| v.contains_aggregate())
+            }
+            SearchCondition::Between {
+                value, low, high, ..
+            } => {
+                value.contains_aggregate() || low.contains_aggregate() || high.contains_aggregate()
+            }
         }
     }
 }
@@ -127,6 +320,11 @@ pub enum ValueExpression {
     Column {
         name: String,
     },
+    /// Column of a specific table in the FROM clause
+    QualifiedColumn {
+        table: String,
+        name: String,
+    },
     FuncCall {
         name: String,
         args: Vec<Box<ValueExpression>>,
@@ -136,15 +334,54 @@ pub enum ValueExpression {
         op: ArithmeticOperator,
         right: Box<ValueExpression>,
     },
+    /// `-value` or `+value`
+    UnaryExpr {
+        op: UnaryOperator,
+        value: Box<ValueExpression>,
+    },
     Literal {
         value: Literal,
     },
+    /// Aggregate function call, an empty argument list is printed as `*`
+    Aggregate {
+        func: AggregateFunction,
+        distinct: bool,
+        args: Vec<Box<ValueExpression>>,
+        within_group: Option<OrderByClause>,
+    },
+    /// Searched CASE expression
+    Case {
+        branches: Vec<WhenClause>,
+        else_: Option<Box<ValueExpression>>,
+    },
+    /// ROW_NUMBER() window function
+    RowNumber {
+        partition_by: Vec<Box<ValueExpression>>,
+        order_by: OrderByClause,
+    },
+    /// The time at which the query is run
+    CurrentTimestamp,
+    /// `CAST(value AS data_type)`
+    Cast {
+        value: Box<ValueExpression>,
+        data_type: DataType,
+    },
+    /// A condition used as a boolean value
+    Condition {
+        condition: Box<SearchCondition>,
+    },
+    /// A string which is compared case-sensitively, under a binary collation in
+    /// dialects whose default collation ignores case
+    CaseSensitive {
+        value: Box<ValueExpression>,
+    },
 }
 
 impl ValueExpression {
     pub fn depends_on_any(&self, columns: &Vec<String>) -> bool {
         match self {
             ValueExpression::Column { name } => columns.contains(name),
+            ValueExpression::QualifiedColumn { name, .. } => columns.contains(name),
             ValueExpression::FuncCall { name, args } => {
                 args.iter().any(|arg| arg.depends_on_any(columns))
             }
@@ -152,6 +389,97 @@ impl ValueExpression {
                 left.depends_on_any(columns) || right.depends_on_any(columns)
             }
             ValueExpression::Literal { value } => false,
+            ValueExpression::CurrentTimestamp => false,
+            ValueExpression::Cast { value, .. } => value.depends_on_any(columns),
+            ValueExpression::UnaryExpr { value, .. } => value.depends_on_any(columns),
+            ValueExpression::Condition { condition } => condition.depends_on_any(columns),
+            ValueExpression::CaseSensitive { value } => value.depends_on_any(columns),
+            ValueExpression::Aggregate {
+                args, within_group, ..
+            } => {
+                args.iter().any(|arg| arg.depends_on_any(columns))
+                    || within_group
+                        .as_ref()
+                        .is_some_and(|order_by| order_by.depends_on_any(columns))
+            }
+            ValueExpression::Case { branches, else_ } => {
+                branches.iter().any(|branch| {
+                    branch.condition.depends_on_any(columns)
+                        || branch.result.depends_on_any(columns)
+                }) || else_
+                    .as_ref()
+                    .is_some_and(|value| value.depends_on_any(columns))
+            }
+            ValueExpression::RowNumber {
+                partition_by,
+                order_by,
+            } => {
+                partition_by
+                    .iter()
+                    .any(|value| value.depends_on_any(columns))
+                    || order_by.depends_on_any(columns)
+            }
+        }
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            ValueExpression::Column { .. }
+            | ValueExpression::QualifiedColumn { .. }
+            | ValueExpression::Literal { .. }
+            | ValueExpression::CurrentTimestamp => false,
+            ValueExpression::FuncCall { name, args } => {
+                args.iter().any(|arg| arg.contains_aggregate())
+            }
+            ValueExpression::ArithmeticExpr { left, op, right } => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            ValueExpression::Aggregate { .. } => true,
+            ValueExpression::Case { branches, else_ } => {
+                branches.iter().any(|branch| {
+                    branch.condition.contains_aggregate() || branch.result.contains_aggregate()
+                }) || else_
+                    .as_ref()
+                    .is_some_and(|value| value.contains_aggregate())
+            }
+            ValueExpression::RowNumber { .. } => false,
+            ValueExpression::Cast { value, .. } => value.contains_aggregate(),
+            ValueExpression::UnaryExpr { value, .. } => value.contains_aggregate(),
+            ValueExpression::Condition { condition } => condition.contains_aggregate(),
+            ValueExpression::CaseSensitive { value } => value.contains_aggregate(),
+        }
+    }
+}
+
+pub struct WhenClause {
+    pub condition: Box<SearchCondition>,
+    pub result: Box<ValueExpression>,
+}
+
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    StddevSamp,
+    VarSamp,
+    PercentileDisc,
+    JsonAgg,
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "COUNT"),
+            AggregateFunction::Sum => write!(f, "SUM"),
+            AggregateFunction::Avg => write!(f, "AVG"),
+            AggregateFunction::Min => write!(f, "MIN"),
+            AggregateFunction::Max => write!(f, "MAX"),
+            AggregateFunction::StddevSamp => write!(f, "STDDEV_SAMP"),
+            AggregateFunction::VarSamp => write!(f, "VAR_SAMP"),
+            AggregateFunction::PercentileDisc => write!(f, "PERCENTILE_DISC"),
+            AggregateFunction::JsonAgg => write!(f, "JSON_AGG"),
         }
     }
 }
@@ -161,6 +489,22 @@ pub enum ArithmeticOperator {
     Sub,
     Mul,
     Div,
+    /// Division of integers, discarding the fraction
+    IntDiv,
+    Mod,
+}
+
+impl ArithmeticOperator {
+    /// Operators with higher precedence bind more tightly
+    pub fn precedence(&self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Sub => 1,
+            ArithmeticOperator::Mul
+            | ArithmeticOperator::Div
+            | ArithmeticOperator::IntDiv
+            | ArithmeticOperator::Mod => 2,
+        }
+    }
 }
 
 impl fmt::Display for ArithmeticOperator {
@@ -170,17 +514,46 @@ impl fmt::Display for ArithmeticOperator {
             ArithmeticOperator::Sub => write!(f, "-"),
             ArithmeticOperator::Mul => write!(f, "*"),
             ArithmeticOperator::Div => write!(f, "/"),
+            ArithmeticOperator::IntDiv => write!(f, "DIV"),
+            ArithmeticOperator::Mod => write!(f, "%"),
+        }
+    }
+}
+
+pub enum UnaryOperator {
+    Neg,
+    Plus,
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOperator::Neg => write!(f, "-"),
+            UnaryOperator::Plus => write!(f, "+"),
         }
     }
 }
 
+pub struct GroupByClause {
+    pub columns: Vec<Box<ValueExpression>>,
+}
+
 pub struct OrderByClause {
     pub specs: Vec<SortSpecification>,
 }
 
+impl OrderByClause {
+    pub fn depends_on_any(&self, columns: &Vec<String>) -> bool {
+        self.specs
+            .iter()
+            .any(|spec| spec.value.depends_on_any(columns))
+    }
+}
+
 pub struct SortSpecification {
-    pub column_name: String,
+    pub value: Box<ValueExpression>,
     pub order: SortOrder,
+    pub nulls: Option<NullsPosition>,
 }
 
 pub enum SortOrder {
@@ -188,9 +561,94 @@ pub enum SortOrder {
     Descending,
 }
 
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortOrder::Ascending => write!(f, "ASC"),
+            SortOrder::Descending => write!(f, "DESC"),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub enum NullsPosition {
+    First,
+    Last,
+}
+
+impl fmt::Display for NullsPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NullsPosition::First => write!(f, "NULLS FIRST"),
+            NullsPosition::Last => write!(f, "NULLS LAST"),
+        }
+    }
+}
+
 pub enum Literal {
+    Null,
     Bool(bool),
     Integer(i64),
     Real(f64),
+    /// Exact numeric value, as its digits
+    Decimal(String),
     String(String),
+    /// JSON document text
+    Json(String),
+    /// Timestamp in `YYYY-MM-DD HH:MM:SS[.fraction]` format
+    Timestamp(String),
+    Interval {
+        value: i64,
+        unit: IntervalUnit,
+    },
+}
+
+pub enum DataType {
+    Boolean,
+    Int,
+    BigInt,
+    Double,
+    Decimal,
+    Varchar,
+    Timestamp,
+    Interval,
+    Json,
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Int => write!(f, "INT"),
+            DataType::BigInt => write!(f, "BIGINT"),
+            DataType::Double => write!(f, "DOUBLE PRECISION"),
+            DataType::Decimal => write!(f, "DECIMAL"),
+            DataType::Varchar => write!(f, "VARCHAR"),
+            DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Interval => write!(f, "INTERVAL"),
+            DataType::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+pub enum IntervalUnit {
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+}
+
+impl fmt::Display for IntervalUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntervalUnit::Day => write!(f, "DAY"),
+            IntervalUnit::Hour => write!(f, "HOUR"),
+            IntervalUnit::Minute => write!(f, "MINUTE"),
+            IntervalUnit::Second => write!(f, "SECOND"),
+            IntervalUnit::Millisecond => write!(f, "MILLISECOND"),
+            IntervalUnit::Microsecond => write!(f, "MICROSECOND"),
+        }
+    }
 }
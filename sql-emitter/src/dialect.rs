@@ -0,0 +1,719 @@
+use crate::ast;
+
+/// Words which cannot be used as names without quotes in at least one dialect, in order
+const RESERVED_WORDS: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLUMN",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DATABASE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DIV",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "ILIKE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MOD",
+    "NATURAL",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PRIMARY",
+    "RANGE",
+    "REFERENCES",
+    "REGEXP",
+    "RIGHT",
+    "RLIKE",
+    "ROW",
+    "ROWS",
+    "SCHEMA",
+    "SELECT",
+    "SET",
+    "SOME",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TOP",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USER",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "XOR",
+];
+
+/// The syntax used to limit the number of rows of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitSyntax {
+    /// `LIMIT n` after the other clauses
+    Limit,
+    /// `SELECT TOP n ...`
+    Top,
+    /// `FETCH FIRST n ROWS ONLY` after the other clauses
+    Fetch,
+}
+
+/// How a dialect writes a function call of the SQL which the converter produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionSyntax<'a> {
+    /// A call to the named function with the same arguments
+    Call(&'a str),
+    /// An expression in which `{0}`, `{1}`, ... stand for the arguments
+    Template(&'static str),
+    /// The dialect has no equivalent
+    Unsupported,
+}
+
+/// The differences between the SQL of the database engines which the printer targets.
+/// The defaults follow the SQL standard.
+pub trait Dialect {
+    /// Quotes an identifier which is not a plain word, such as `"My Column"`
+    fn quote_identifier(&self, name: &str) -> String {
+        quote(name, '"')
+    }
+
+    /// Whether a word is reserved, so that it must be quoted to be used as a name
+    fn is_reserved(&self, word: &str) -> bool {
+        RESERVED_WORDS
+            .binary_search(&word.to_ascii_uppercase().as_str())
+            .is_ok()
+    }
+
+    /// Whether every identifier is quoted, rather than only those which need it
+    fn quotes_all_identifiers(&self) -> bool {
+        false
+    }
+
+    /// Whether the case of a name would be lost if it were not quoted. Unquoted names
+    /// are folded to upper case, and names in a single case are taken to be meant to
+    /// match regardless of case.
+    fn folds_case(&self, name: &str) -> bool {
+        name.chars().any(|c| c.is_ascii_lowercase()) && name.chars().any(|c| c.is_ascii_uppercase())
+    }
+
+    /// Quotes a string literal, escaping its contents
+    fn quote_string(&self, value: &str) -> String {
+        quote(value, '\'')
+    }
+
+    fn boolean(&self, value: bool) -> &'static str {
+        if value {
+            "TRUE"
+        } else {
+            "FALSE"
+        }
+    }
+
+    fn comparison_operator(&self, op: &ast::ComparisonOperator) -> &'static str {
+        match op {
+            ast::ComparisonOperator::LT => "<",
+            ast::ComparisonOperator::GT => ">",
+            ast::ComparisonOperator::LTE => "<=",
+            ast::ComparisonOperator::GTE => ">=",
+            ast::ComparisonOperator::EQ => "=",
+            ast::ComparisonOperator::NEQ => "<>",
+        }
+    }
+
+    /// Integers are divided with `/`, which discards the fraction in most engines
+    fn arithmetic_operator(&self, op: &ast::ArithmeticOperator) -> &'static str {
+        match op {
+            ast::ArithmeticOperator::Add => "+",
+            ast::ArithmeticOperator::Sub => "-",
+            ast::ArithmeticOperator::Mul => "*",
+            ast::ArithmeticOperator::Div | ast::ArithmeticOperator::IntDiv => "/",
+            ast::ArithmeticOperator::Mod => "%",
+        }
+    }
+
+    fn limit_syntax(&self) -> LimitSyntax {
+        LimitSyntax::Fetch
+    }
+
+    /// Whether ORDER BY accepts NULLS FIRST and NULLS LAST.
+    /// Otherwise the position is emulated by sorting on `value IS NULL` first.
+    fn supports_nulls_position(&self) -> bool {
+        true
+    }
+
+    /// Where ORDER BY puts NULLs when no position is given, if that is known.
+    /// A position which matches it is left out.
+    fn default_nulls_position(&self, _order: &ast::SortOrder) -> Option<ast::NullsPosition> {
+        None
+    }
+
+    /// The case-insensitive LIKE operator, if any.
+    /// Otherwise both sides are lowercased and compared with LIKE.
+    fn case_insensitive_like(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The collation which compares strings case-sensitively, if the default
+    /// collation ignores case
+    fn case_sensitive_collation(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether LIKE ignores case even without ILIKE, so that case-sensitive
+    /// patterns are matched with GLOB instead
+    fn like_ignores_case(&self) -> bool {
+        false
+    }
+
+    /// Whether LIKE treats `\` as an escape character without an ESCAPE clause
+    fn backslash_escapes_like(&self) -> bool {
+        false
+    }
+
+    /// The regular expression match operator
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        if negated {
+            "NOT LIKE_REGEX"
+        } else {
+            "LIKE_REGEX"
+        }
+    }
+
+    /// How a function is called in this dialect, given its name in the SQL which the
+    /// converter produces
+    fn function<'a>(&self, name: &'a str) -> FunctionSyntax<'a> {
+        match name {
+            "LOCATE" => FunctionSyntax::Template("POSITION({0} IN {1})"),
+            "RIGHT" => FunctionSyntax::Template("SUBSTRING({0} FROM CHAR_LENGTH({0}) - ({1}) + 1)"),
+            "TRUNCATE" => {
+                FunctionSyntax::Template("CASE WHEN {0} < 0 THEN CEILING({0}) ELSE FLOOR({0}) END")
+            }
+            "UNIX_TIMESTAMP" | "FROM_UNIXTIME" => FunctionSyntax::Unsupported,
+            name => FunctionSyntax::Call(name),
+        }
+    }
+
+    fn data_type(&self, data_type: &ast::DataType) -> String {
+        data_type.to_string()
+    }
+
+    /// A timestamp literal, given in `YYYY-MM-DD HH:MM:SS[.fraction]` format
+    fn timestamp_literal(&self, value: &str) -> String {
+        format!("TIMESTAMP {}", self.quote_string(value))
+    }
+
+    /// NaN or an infinity, or None when the dialect has no such value
+    fn special_real(&self, value: f64) -> Option<String> {
+        let double = self.data_type(&ast::DataType::Double);
+        if value.is_nan() {
+            Some(format!("CAST('NaN' AS {})", double))
+        } else {
+            let sign = if value < 0.0 { "-" } else { "" };
+            Some(format!("CAST('{}Infinity' AS {})", sign, double))
+        }
+    }
+
+    /// An interval literal, or None when the dialect has no intervals.
+    /// Fractions of a second are written as decimal seconds.
+    fn interval_literal(&self, value: i64, unit: &ast::IntervalUnit) -> Option<String> {
+        match unit {
+            ast::IntervalUnit::Millisecond | ast::IntervalUnit::Microsecond => Some(format!(
+                "INTERVAL '{}' SECOND",
+                decimal_seconds(value, unit)
+            )),
+            unit => Some(format!("INTERVAL '{}' {}", value, unit)),
+        }
+    }
+
+    /// A timestamp shifted by an interval, for dialects without interval literals.
+    /// None when the dialect cannot shift timestamps either.
+    fn shift_timestamp(
+        &self,
+        timestamp: &str,
+        value: i64,
+        unit: &ast::IntervalUnit,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// Wraps text in quotes, doubling the quotes inside it
+fn quote(text: &str, quote: char) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push(quote);
+    for c in text.chars() {
+        if c == quote {
+            quoted.push(quote);
+        }
+        quoted.push(c);
+    }
+    quoted.push(quote);
+    quoted
+}
+
+/// A number of milliseconds or microseconds as decimal seconds, such as `-0.250`
+fn decimal_seconds(value: i64, unit: &ast::IntervalUnit) -> String {
+    let (per_second, digits) = match unit {
+        ast::IntervalUnit::Millisecond => (1_000, 3),
+        ast::IntervalUnit::Microsecond => (1_000_000, 6),
+        _ => return value.to_string(),
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    let count = value.unsigned_abs();
+    format!(
+        "{}{}.{:0digits$}",
+        sign,
+        count / per_second,
+        count % per_second,
+        digits = digits
+    )
+}
+
+/// A dialect which quotes every identifier, so that no name can be mistaken for a keyword
+pub struct Strict<'a>(pub &'a dyn Dialect);
+
+impl Dialect for Strict<'_> {
+    fn quote_identifier(&self, name: &str) -> String {
+        self.0.quote_identifier(name)
+    }
+
+    fn is_reserved(&self, word: &str) -> bool {
+        self.0.is_reserved(word)
+    }
+
+    fn quotes_all_identifiers(&self) -> bool {
+        true
+    }
+
+    fn folds_case(&self, name: &str) -> bool {
+        self.0.folds_case(name)
+    }
+
+    fn quote_string(&self, value: &str) -> String {
+        self.0.quote_string(value)
+    }
+
+    fn boolean(&self, value: bool) -> &'static str {
+        self.0.boolean(value)
+    }
+
+    fn comparison_operator(&self, op: &ast::ComparisonOperator) -> &'static str {
+        self.0.comparison_operator(op)
+    }
+
+    fn arithmetic_operator(&self, op: &ast::ArithmeticOperator) -> &'static str {
+        self.0.arithmetic_operator(op)
+    }
+
+    fn limit_syntax(&self) -> LimitSyntax {
+        self.0.limit_syntax()
+    }
+
+    fn supports_nulls_position(&self) -> bool {
+        self.0.supports_nulls_position()
+    }
+
+    fn default_nulls_position(&self, order: &ast::SortOrder) -> Option<ast::NullsPosition> {
+        self.0.default_nulls_position(order)
+    }
+
+    fn case_insensitive_like(&self) -> Option<&'static str> {
+        self.0.case_insensitive_like()
+    }
+
+    fn case_sensitive_collation(&self) -> Option<&'static str> {
+        self.0.case_sensitive_collation()
+    }
+
+    fn like_ignores_case(&self) -> bool {
+        self.0.like_ignores_case()
+    }
+
+    fn backslash_escapes_like(&self) -> bool {
+        self.0.backslash_escapes_like()
+    }
+
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        self.0.regex_operator(negated)
+    }
+
+    fn function<'a>(&self, name: &'a str) -> FunctionSyntax<'a> {
+        self.0.function(name)
+    }
+
+    fn data_type(&self, data_type: &ast::DataType) -> String {
+        self.0.data_type(data_type)
+    }
+
+    fn timestamp_literal(&self, value: &str) -> String {
+        self.0.timestamp_literal(value)
+    }
+
+    fn special_real(&self, value: f64) -> Option<String> {
+        self.0.special_real(value)
+    }
+
+    fn interval_literal(&self, value: i64, unit: &ast::IntervalUnit) -> Option<String> {
+        self.0.interval_literal(value, unit)
+    }
+
+    fn shift_timestamp(
+        &self,
+        timestamp: &str,
+        value: i64,
+        unit: &ast::IntervalUnit,
+    ) -> Option<String> {
+        self.0.shift_timestamp(timestamp, value, unit)
+    }
+}
+
+/// Looks up a dialect by name, e.g. for a command line option
+pub fn dialect_by_name(name: &str) -> Option<Box<dyn Dialect>> {
+    let dialect: Box<dyn Dialect> = match name.to_lowercase().as_str() {
+        "singlestore" => Box::new(SingleStore),
+        "postgres" | "postgresql" => Box::new(PostgreSql),
+        "sqlite" => Box::new(Sqlite),
+        "ansi" => Box::new(Ansi),
+        _ => return None,
+    };
+    Some(dialect)
+}
+
+/// Standard SQL
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ansi;
+
+impl Dialect for Ansi {}
+
+/// SingleStore, whose syntax largely follows MySQL
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SingleStore;
+
+impl Dialect for SingleStore {
+    fn quote_identifier(&self, name: &str) -> String {
+        quote(name, '`')
+    }
+
+    /// Names keep their case whether or not they are quoted
+    fn folds_case(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// Backslashes start escape sequences unless NO_BACKSLASH_ESCAPES is set.
+    /// NUL is escaped too, since some clients end strings at it.
+    fn quote_string(&self, value: &str) -> String {
+        let escaped = value.replace('\\', "\\\\").replace('\0', "\\0");
+        quote(&escaped, '\'')
+    }
+
+    fn comparison_operator(&self, op: &ast::ComparisonOperator) -> &'static str {
+        match op {
+            ast::ComparisonOperator::NEQ => "!=",
+            op => Ansi.comparison_operator(op),
+        }
+    }
+
+    fn arithmetic_operator(&self, op: &ast::ArithmeticOperator) -> &'static str {
+        match op {
+            ast::ArithmeticOperator::IntDiv => "DIV",
+            op => Ansi.arithmetic_operator(op),
+        }
+    }
+
+    fn limit_syntax(&self) -> LimitSyntax {
+        LimitSyntax::Limit
+    }
+
+    fn supports_nulls_position(&self) -> bool {
+        false
+    }
+
+    /// NULLs are smaller than any other value
+    fn default_nulls_position(&self, order: &ast::SortOrder) -> Option<ast::NullsPosition> {
+        Some(match order {
+            ast::SortOrder::Ascending => ast::NullsPosition::First,
+            ast::SortOrder::Descending => ast::NullsPosition::Last,
+        })
+    }
+
+    fn case_insensitive_like(&self) -> Option<&'static str> {
+        Some("ILIKE")
+    }
+
+    /// The default collations ignore case, in `=` and LOCATE as well as LIKE
+    fn case_sensitive_collation(&self) -> Option<&'static str> {
+        Some("utf8mb4_bin")
+    }
+
+    fn backslash_escapes_like(&self) -> bool {
+        true
+    }
+
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        if negated {
+            "NOT RLIKE"
+        } else {
+            "RLIKE"
+        }
+    }
+
+    /// DOUBLE cannot hold NaN or an infinity
+    fn special_real(&self, _value: f64) -> Option<String> {
+        None
+    }
+
+    /// There is no MILLISECOND unit, so milliseconds are counted in microseconds
+    fn interval_literal(&self, value: i64, unit: &ast::IntervalUnit) -> Option<String> {
+        match unit {
+            // a count of milliseconds is at most a thousandth of the ticks it came from
+            ast::IntervalUnit::Millisecond => {
+                Some(format!("INTERVAL '{}' MICROSECOND", value * 1_000))
+            }
+            unit => Some(format!("INTERVAL '{}' {}", value, unit)),
+        }
+    }
+
+    /// The converter produces the functions of SingleStore
+    fn function<'a>(&self, name: &'a str) -> FunctionSyntax<'a> {
+        FunctionSyntax::Call(name)
+    }
+
+    /// CAST only accepts the MySQL conversion types, which have no booleans or intervals
+    fn data_type(&self, data_type: &ast::DataType) -> String {
+        let name = match data_type {
+            ast::DataType::Boolean | ast::DataType::Int | ast::DataType::BigInt => "SIGNED",
+            ast::DataType::Double => "DOUBLE",
+            ast::DataType::Decimal => "DECIMAL",
+            ast::DataType::Varchar => "CHAR",
+            ast::DataType::Timestamp => "DATETIME",
+            ast::DataType::Interval => "TIME",
+            ast::DataType::Json => "JSON",
+        };
+        name.to_string()
+    }
+}
+
+/// PostgreSQL
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostgreSql;
+
+impl Dialect for PostgreSql {
+    /// Unquoted names are folded to lower case
+    fn folds_case(&self, name: &str) -> bool {
+        name.chars().any(|c| c.is_ascii_uppercase())
+    }
+
+    fn comparison_operator(&self, op: &ast::ComparisonOperator) -> &'static str {
+        match op {
+            ast::ComparisonOperator::NEQ => "!=",
+            op => Ansi.comparison_operator(op),
+        }
+    }
+
+    fn limit_syntax(&self) -> LimitSyntax {
+        LimitSyntax::Limit
+    }
+
+    /// NULLs are larger than any other value
+    fn default_nulls_position(&self, order: &ast::SortOrder) -> Option<ast::NullsPosition> {
+        Some(match order {
+            ast::SortOrder::Ascending => ast::NullsPosition::Last,
+            ast::SortOrder::Descending => ast::NullsPosition::First,
+        })
+    }
+
+    fn case_insensitive_like(&self) -> Option<&'static str> {
+        Some("ILIKE")
+    }
+
+    fn backslash_escapes_like(&self) -> bool {
+        true
+    }
+
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        if negated {
+            "!~"
+        } else {
+            "~"
+        }
+    }
+
+    fn function<'a>(&self, name: &'a str) -> FunctionSyntax<'a> {
+        match name {
+            "LOCATE" => FunctionSyntax::Template("STRPOS({1}, {0})"),
+            "UNIX_TIMESTAMP" => FunctionSyntax::Template("EXTRACT(EPOCH FROM {0})"),
+            "FROM_UNIXTIME" => FunctionSyntax::Call("TO_TIMESTAMP"),
+            "SPLIT" => FunctionSyntax::Call("STRING_TO_ARRAY"),
+            "TRUNCATE" => FunctionSyntax::Template("TRUNC({0})"),
+            name => FunctionSyntax::Call(name),
+        }
+    }
+}
+
+/// SQLite, which stores dates, times and JSON as text
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sqlite;
+
+impl Dialect for Sqlite {
+    /// Names keep their case whether or not they are quoted
+    fn folds_case(&self, _name: &str) -> bool {
+        false
+    }
+
+    fn boolean(&self, value: bool) -> &'static str {
+        if value {
+            "1"
+        } else {
+            "0"
+        }
+    }
+
+    fn comparison_operator(&self, op: &ast::ComparisonOperator) -> &'static str {
+        match op {
+            ast::ComparisonOperator::NEQ => "!=",
+            op => Ansi.comparison_operator(op),
+        }
+    }
+
+    fn limit_syntax(&self) -> LimitSyntax {
+        LimitSyntax::Limit
+    }
+
+    /// NULLs are smaller than any other value
+    fn default_nulls_position(&self, order: &ast::SortOrder) -> Option<ast::NullsPosition> {
+        SingleStore.default_nulls_position(order)
+    }
+
+    /// LIKE ignores the case of ASCII letters by default
+    fn case_insensitive_like(&self) -> Option<&'static str> {
+        Some("LIKE")
+    }
+
+    fn like_ignores_case(&self) -> bool {
+        true
+    }
+
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        if negated {
+            "NOT REGEXP"
+        } else {
+            "REGEXP"
+        }
+    }
+
+    /// Dates are text, converted to and from seconds since the epoch by STRFTIME and DATETIME
+    fn function<'a>(&self, name: &'a str) -> FunctionSyntax<'a> {
+        match name {
+            "CHAR_LENGTH" => FunctionSyntax::Call("LENGTH"),
+            "GREATEST" => FunctionSyntax::Call("MAX"),
+            "LEAST" => FunctionSyntax::Call("MIN"),
+            "JSON_AGG" => FunctionSyntax::Call("JSON_GROUP_ARRAY"),
+            "LOCATE" => FunctionSyntax::Template("INSTR({1}, {0})"),
+            "RIGHT" => FunctionSyntax::Template("SUBSTR({0}, LENGTH({0}) - ({1}) + 1)"),
+            "UNIX_TIMESTAMP" => FunctionSyntax::Template("CAST(STRFTIME('%s', {0}) AS INTEGER)"),
+            "FROM_UNIXTIME" => FunctionSyntax::Template("DATETIME({0}, 'unixepoch')"),
+            "TRUNCATE" => FunctionSyntax::Template("CAST({0} AS INTEGER)"),
+            name => FunctionSyntax::Call(name),
+        }
+    }
+
+    /// Casts pick the type affinity of the value
+    fn data_type(&self, data_type: &ast::DataType) -> String {
+        let name = match data_type {
+            ast::DataType::Boolean | ast::DataType::Int | ast::DataType::BigInt => "INTEGER",
+            ast::DataType::Double => "REAL",
+            ast::DataType::Decimal => "NUMERIC",
+            ast::DataType::Varchar
+            | ast::DataType::Timestamp
+            | ast::DataType::Interval
+            | ast::DataType::Json => "TEXT",
+        };
+        name.to_string()
+    }
+    /// Timestamps are text in the same format
+    fn timestamp_literal(&self, value: &str) -> String {
+        self.quote_string(value)
+    }
+
+    /// A literal too large for a REAL is read as an infinity, while NaN becomes NULL
+    fn special_real(&self, value: f64) -> Option<String> {
+        if value.is_nan() {
+            None
+        } else if value < 0.0 {
+            Some(String::from("-9e999"))
+        } else {
+            Some(String::from("9e999"))
+        }
+    }
+
+    fn interval_literal(&self, value: i64, unit: &ast::IntervalUnit) -> Option<String> {
+        None
+    }
+
+    /// DATETIME applies a modifier such as `'+5 minutes'`, which counts whole days, hours
+    /// and minutes, or seconds with a fraction
+    fn shift_timestamp(
+        &self,
+        timestamp: &str,
+        value: i64,
+        unit: &ast::IntervalUnit,
+    ) -> Option<String> {
+        let sign = if value < 0 { "" } else { "+" };
+        let modifier = match unit {
+            ast::IntervalUnit::Day => format!("{}{} days", sign, value),
+            ast::IntervalUnit::Hour => format!("{}{} hours", sign, value),
+            ast::IntervalUnit::Minute => format!("{}{} minutes", sign, value),
+            unit => format!("{}{} seconds", sign, decimal_seconds(value, unit)),
+        };
+        Some(format!("DATETIME({}, '{}')", timestamp, modifier))
+    }
+}
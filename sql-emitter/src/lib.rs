@@ -2,25 +2,52 @@
 #![allow(unused_variables)]
 
 pub mod ast;
+/// Differences between the SQL of database engines
+pub mod dialect;
 
 use std::fmt::{Result as FResult, Write};
 
-pub fn emit(select_stmt: &ast::SelectStatement) -> Result<String, String> {
-    let mut printer = Printer::default();
+pub use dialect::{
+    dialect_by_name, Ansi, Dialect, FunctionSyntax, LimitSyntax, PostgreSql, SingleStore, Sqlite,
+    Strict,
+};
+
+pub fn emit(select_stmt: &ast::SelectStatement, dialect: &dyn Dialect) -> Result<String, String> {
+    let mut printer = Printer::new(dialect);
     if printer.print_query(&select_stmt).is_err() {
-        Err(String::from("Failed to format SQL output"))
+        match printer.unsupported {
+            Some(feature) => Err(format!("{} is not supported by the SQL dialect", feature)),
+            None => Err(String::from("Failed to format SQL output")),
+        }
     } else {
         Ok(String::from(printer))
     }
 }
 
-#[derive(Default, Debug)]
-pub struct Printer {
+pub struct Printer<'a> {
     output: String,
     indent: u32,
+    dialect: &'a dyn Dialect,
+    /// What the dialect cannot express, when printing stopped because of it
+    unsupported: Option<String>,
 }
 
-impl Printer {
+impl<'a> Printer<'a> {
+    pub fn new(dialect: &'a dyn Dialect) -> Self {
+        Printer {
+            output: String::new(),
+            indent: 0,
+            dialect,
+            unsupported: None,
+        }
+    }
+
+    /// Stops printing because the dialect has no equivalent of a feature
+    fn unsupported(&mut self, feature: String) -> FResult {
+        self.unsupported = Some(feature);
+        Err(std::fmt::Error)
+    }
+
     fn indent(&mut self) {
         self.indent += 1;
     }
@@ -41,16 +68,52 @@ impl Printer {
     }
 
     pub fn print_query(&mut self, select_stmt: &ast::SelectStatement) -> FResult {
-        self.print_select(&select_stmt.modifier, &select_stmt.select)?;
+        if !select_stmt.with.is_empty() {
+            self.print_with(&select_stmt.with)?;
+        }
+        let limit_syntax = self.dialect.limit_syntax();
+        let top = select_stmt
+            .limit
+            .filter(|_| limit_syntax == LimitSyntax::Top);
+        self.print_select(&select_stmt.modifier, top, &select_stmt.select)?;
         self.print_from(&select_stmt.from)?;
         if let Some(cond) = &select_stmt.where_ {
             self.print_where(&cond)?;
         }
+        if let Some(group_by) = &select_stmt.group_by {
+            self.print_group_by(&group_by)?;
+        }
         if let Some(order) = &select_stmt.order_by {
             self.print_order_by(&order)?;
         }
-        if let Some(limit) = &select_stmt.limit {
-            self.print_limit(*limit)?;
+        match select_stmt.limit {
+            Some(limit) if limit_syntax != LimitSyntax::Top => self.print_limit(limit)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn print_with(&mut self, ctes: &[ast::CommonTableExpression]) -> FResult {
+        for (index, cte) in ctes.iter().enumerate() {
+            self.start_line();
+            if index == 0 {
+                write!(self.output, "WITH ")?;
+            }
+            self.print_identifier(&cte.name)?;
+            write!(self.output, " AS (")?;
+            self.end_line();
+
+            self.indent();
+            self.print_query(&cte.query)?;
+            self.dedent();
+
+            self.start_line();
+            if index + 1 < ctes.len() {
+                write!(self.output, "),")?;
+            } else {
+                write!(self.output, ")")?;
+            }
+            self.end_line();
         }
         Ok(())
     }
@@ -58,6 +121,7 @@ impl Printer {
     fn print_select(
         &mut self,
         modifier: &Option<ast::Modifier>,
+        top: Option<i64>,
         select: &ast::SelectList,
     ) -> FResult {
         self.start_line();
@@ -66,6 +130,9 @@ impl Printer {
             Some(ast::Modifier::All) => write!(self.output, "SELECT ALL")?,
             None => write!(self.output, "SELECT")?,
         };
+        if let Some(top) = top {
+            write!(self.output, " TOP {}", top)?;
+        }
         let mut first = true;
         if select.wildcard {
             write!(self.output, " *")?;
@@ -78,9 +145,7 @@ impl Printer {
                 write!(self.output, ", ")?;
             }
             self.print_val_expr(&field.value)?;
-            if let Some(alias) = &field.alias {
-                write!(self.output, " as {}", alias)?;
-            }
+            self.print_table_alias(&field.alias)?;
             first = false;
         }
         self.end_line();
@@ -88,15 +153,22 @@ impl Printer {
     }
 
     fn print_from(&mut self, table_refs: &ast::TableReference) -> FResult {
-        match table_refs {
-            ast::TableReference::TableName { name } => {
+        self.print_table_reference("FROM", table_refs)
+    }
+
+    /// Prints a table reference on lines starting with `keyword` (e.g. FROM or INNER JOIN)
+    fn print_table_reference(&mut self, keyword: &str, table_ref: &ast::TableReference) -> FResult {
+        match table_ref {
+            ast::TableReference::TableName { name, alias } => {
                 self.start_line();
-                write!(self.output, "FROM {}", name)?;
+                write!(self.output, "{} ", keyword)?;
+                self.print_identifier(name)?;
+                self.print_table_alias(alias)?;
                 self.end_line();
             }
-            ast::TableReference::InnerStatement { value } => {
+            ast::TableReference::InnerStatement { value, alias } => {
                 self.start_line();
-                write!(self.output, "FROM (")?;
+                write!(self.output, "{} (", keyword)?;
                 self.end_line();
 
                 self.indent();
@@ -105,12 +177,79 @@ impl Printer {
 
                 self.start_line();
                 write!(self.output, ")")?;
+                self.print_table_alias(alias)?;
+                self.end_line();
+            }
+            ast::TableReference::Join {
+                left,
+                join_type,
+                right,
+                on,
+            } => {
+                self.print_table_reference(keyword, left)?;
+                self.print_table_reference(&join_type.to_string(), right)?;
+
+                self.start_line();
+                write!(self.output, "ON ")?;
+                self.print_search_condition(on)?;
+                self.end_line();
+            }
+            ast::TableReference::SetOperation {
+                op,
+                statements,
+                alias,
+            } => {
+                self.start_line();
+                write!(self.output, "{} (", keyword)?;
+                self.end_line();
+
+                self.indent();
+                for (index, statement) in statements.iter().enumerate() {
+                    if index > 0 {
+                        self.start_line();
+                        write!(self.output, "{}", op)?;
+                        self.end_line();
+                    }
+                    self.print_query(statement)?;
+                }
+                self.dedent();
+
+                self.start_line();
+                write!(self.output, ")")?;
+                self.print_table_alias(alias)?;
                 self.end_line();
             }
         }
         Ok(())
     }
 
+    /// Prints ` as alias`, for a column or a table
+    fn print_table_alias(&mut self, alias: &Option<String>) -> FResult {
+        if let Some(alias) = alias {
+            write!(self.output, " as ")?;
+            self.print_identifier(alias)?;
+        }
+        Ok(())
+    }
+
+    /// Prints a name as it is when it is a plain word whose case is kept, and quoted otherwise
+    fn print_identifier(&mut self, name: &str) -> FResult {
+        let mut chars = name.chars();
+        let is_plain = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_plain
+            && !self.dialect.is_reserved(name)
+            && !self.dialect.folds_case(name)
+            && !self.dialect.quotes_all_identifiers()
+        {
+            write!(self.output, "{}", name)
+        } else {
+            write!(self.output, "{}", self.dialect.quote_identifier(name))
+        }
+    }
+
     fn print_where(&mut self, cond: &ast::SearchCondition) -> FResult {
         self.start_line();
         write!(self.output, "WHERE ")?;
@@ -119,48 +258,329 @@ impl Printer {
         Ok(())
     }
 
-    fn print_order_by(&mut self, cond: &ast::OrderByClause) -> FResult {
-        todo!()
+    fn print_group_by(&mut self, group_by: &ast::GroupByClause) -> FResult {
+        self.start_line();
+        write!(self.output, "GROUP BY ")?;
+        self.print_val_exprs(&group_by.columns)?;
+        self.end_line();
+        Ok(())
+    }
+
+    fn print_order_by(&mut self, order_by: &ast::OrderByClause) -> FResult {
+        self.start_line();
+        self.print_sort_specs(order_by)?;
+        self.end_line();
+        Ok(())
+    }
+
+    fn print_sort_specs(&mut self, order_by: &ast::OrderByClause) -> FResult {
+        write!(self.output, "ORDER BY ")?;
+        let mut first = true;
+        for spec in order_by.specs.iter() {
+            if !first {
+                write!(self.output, ", ")?;
+            }
+            // a position which the dialect uses anyway is left out
+            let nulls = spec.nulls.as_ref().filter(|nulls| {
+                self.dialect.default_nulls_position(&spec.order).as_ref() != Some(nulls)
+            });
+            match nulls {
+                // sorting on whether the value is NULL puts NULLs first or last
+                Some(nulls) if !self.dialect.supports_nulls_position() => {
+                    write!(self.output, "(")?;
+                    self.print_val_expr(&spec.value)?;
+                    match nulls {
+                        ast::NullsPosition::First => write!(self.output, " IS NULL) DESC, ")?,
+                        ast::NullsPosition::Last => write!(self.output, " IS NULL) ASC, ")?,
+                    }
+                    self.print_val_expr(&spec.value)?;
+                    write!(self.output, " {}", spec.order)?;
+                }
+                Some(nulls) => {
+                    self.print_val_expr(&spec.value)?;
+                    write!(self.output, " {} {}", spec.order, nulls)?;
+                }
+                None => {
+                    self.print_val_expr(&spec.value)?;
+                    write!(self.output, " {}", spec.order)?;
+                }
+            }
+            first = false;
+        }
+        Ok(())
     }
 
     fn print_limit(&mut self, limit: i64) -> FResult {
         self.start_line();
-        write!(self.output, "LIMIT {}", limit)?;
+        match self.dialect.limit_syntax() {
+            LimitSyntax::Fetch => write!(self.output, "FETCH FIRST {} ROWS ONLY", limit)?,
+            _ => write!(self.output, "LIMIT {}", limit)?,
+        }
         self.end_line();
         Ok(())
     }
 
     fn print_val_expr(&mut self, expr: &ast::ValueExpression) -> FResult {
         match expr {
-            ast::ValueExpression::Column { name } => write!(self.output, "{}", name),
-            ast::ValueExpression::FuncCall { name, args } => {
-                write!(self.output, "{}(", name)?;
-                let mut first = true;
-                for arg in args.iter() {
-                    if !first {
-                        write!(self.output, ", ")?;
-                    }
-                    self.print_val_expr(arg)?;
-                    first = false;
-                }
-                write!(self.output, ")")?;
-                Ok(())
+            ast::ValueExpression::Column { name } => self.print_identifier(name),
+            ast::ValueExpression::QualifiedColumn { table, name } => {
+                self.print_identifier(table)?;
+                write!(self.output, ".")?;
+                self.print_identifier(name)
             }
+            ast::ValueExpression::FuncCall { name, args } => match self.dialect.function(name) {
+                FunctionSyntax::Call(name) => {
+                    write!(self.output, "{}(", name)?;
+                    self.print_val_exprs(args)?;
+                    write!(self.output, ")")?;
+                    Ok(())
+                }
+                FunctionSyntax::Template(template) => self.print_template(template, args),
+                FunctionSyntax::Unsupported => self.unsupported(format!("The function {}", name)),
+            },
             ast::ValueExpression::ArithmeticExpr { left, op, right } => {
-                self.print_val_expr(left)?;
-                write!(self.output, " {} ", op)?;
-                self.print_val_expr(right)?;
+                if let Some((timestamp, value, unit)) = self.timestamp_shift(left, op, right) {
+                    return self.print_timestamp_shift(timestamp, value, unit);
+                }
+                self.print_operand(left, op, false)?;
+                write!(self.output, " {} ", self.dialect.arithmetic_operator(op))?;
+                self.print_operand(right, op, true)?;
                 Ok(())
             }
             ast::ValueExpression::Literal { value } => match value {
-                ast::Literal::Bool(v) => write!(self.output, "{}", v),
+                ast::Literal::Null => write!(self.output, "NULL"),
+                ast::Literal::Bool(v) => write!(self.output, "{}", self.dialect.boolean(*v)),
                 ast::Literal::Integer(v) => write!(self.output, "{}", v),
-                ast::Literal::Real(v) => write!(self.output, "{}", v),
-                ast::Literal::String(v) => write!(self.output, "\"{}\"", v),
+                ast::Literal::Real(v) => self.print_real(*v),
+                ast::Literal::Decimal(v) => write!(self.output, "{}", v),
+                ast::Literal::String(v) => write!(self.output, "{}", self.dialect.quote_string(v)),
+                ast::Literal::Json(v) => write!(
+                    self.output,
+                    "CAST({} AS {})",
+                    self.dialect.quote_string(v),
+                    self.dialect.data_type(&ast::DataType::Json)
+                ),
+                ast::Literal::Timestamp(v) => {
+                    write!(self.output, "{}", self.dialect.timestamp_literal(v))
+                }
+                ast::Literal::Interval { value, unit } => {
+                    match self.dialect.interval_literal(*value, unit) {
+                        Some(interval) => write!(self.output, "{}", interval),
+                        None => self.unsupported(String::from("An interval")),
+                    }
+                }
             },
+            ast::ValueExpression::Aggregate {
+                func,
+                distinct,
+                args,
+                within_group,
+            } => {
+                let name = func.to_string();
+                match self.dialect.function(&name) {
+                    FunctionSyntax::Call(name) => write!(self.output, "{}(", name)?,
+                    _ => return self.unsupported(format!("The aggregate {}", name)),
+                }
+                if *distinct {
+                    write!(self.output, "DISTINCT ")?;
+                }
+                if args.is_empty() {
+                    write!(self.output, "*")?;
+                } else {
+                    self.print_val_exprs(args)?;
+                }
+                write!(self.output, ")")?;
+                if let Some(order_by) = within_group {
+                    write!(self.output, " WITHIN GROUP (")?;
+                    self.print_sort_specs(order_by)?;
+                    write!(self.output, ")")?;
+                }
+                Ok(())
+            }
+            ast::ValueExpression::Case { branches, else_ } => {
+                write!(self.output, "CASE")?;
+                for branch in branches.iter() {
+                    write!(self.output, " WHEN ")?;
+                    self.print_search_condition(&branch.condition)?;
+                    write!(self.output, " THEN ")?;
+                    self.print_val_expr(&branch.result)?;
+                }
+                if let Some(value) = else_ {
+                    write!(self.output, " ELSE ")?;
+                    self.print_val_expr(value)?;
+                }
+                write!(self.output, " END")?;
+                Ok(())
+            }
+            ast::ValueExpression::RowNumber {
+                partition_by,
+                order_by,
+            } => {
+                write!(self.output, "ROW_NUMBER() OVER (")?;
+                if !partition_by.is_empty() {
+                    write!(self.output, "PARTITION BY ")?;
+                    self.print_val_exprs(partition_by)?;
+                    write!(self.output, " ")?;
+                }
+                self.print_sort_specs(order_by)?;
+                write!(self.output, ")")?;
+                Ok(())
+            }
+            ast::ValueExpression::CurrentTimestamp => write!(self.output, "CURRENT_TIMESTAMP"),
+            ast::ValueExpression::Cast { value, data_type } => {
+                write!(self.output, "CAST(")?;
+                self.print_val_expr(value)?;
+                write!(self.output, " AS {})", self.dialect.data_type(data_type))
+            }
+            ast::ValueExpression::Condition { condition } => self.print_search_condition(condition),
+            ast::ValueExpression::CaseSensitive { value } => {
+                match self.dialect.case_sensitive_collation() {
+                    Some(collation) => {
+                        // COLLATE binds more tightly than any operator
+                        let needs_parens = matches!(
+                            **value,
+                            ast::ValueExpression::ArithmeticExpr { .. }
+                                | ast::ValueExpression::UnaryExpr { .. }
+                        );
+                        if needs_parens {
+                            write!(self.output, "(")?;
+                            self.print_val_expr(value)?;
+                            write!(self.output, ")")?;
+                        } else {
+                            self.print_val_expr(value)?;
+                        }
+                        write!(self.output, " COLLATE {}", collation)
+                    }
+                    None => self.print_val_expr(value),
+                }
+            }
+            ast::ValueExpression::UnaryExpr { op, value } => {
+                write!(self.output, "{}", op)?;
+                // Also keeps `- -x` from printing as a `--` comment
+                let needs_parens = matches!(
+                    **value,
+                    ast::ValueExpression::ArithmeticExpr { .. }
+                        | ast::ValueExpression::UnaryExpr { .. }
+                );
+                if needs_parens {
+                    write!(self.output, "(")?;
+                    self.print_val_expr(value)?;
+                    write!(self.output, ")")
+                } else {
+                    self.print_val_expr(value)
+                }
+            }
         }
     }
 
+    /// The timestamp, count and unit of an interval added to or subtracted from a timestamp,
+    /// when the dialect has no interval literals
+    fn timestamp_shift<'e>(
+        &self,
+        left: &'e ast::ValueExpression,
+        op: &ast::ArithmeticOperator,
+        right: &'e ast::ValueExpression,
+    ) -> Option<(&'e ast::ValueExpression, i64, &'e ast::IntervalUnit)> {
+        let interval = |expr: &'e ast::ValueExpression| match expr {
+            ast::ValueExpression::Literal {
+                value: ast::Literal::Interval { value, unit },
+            } if self.dialect.interval_literal(*value, unit).is_none() => Some((*value, unit)),
+            _ => None,
+        };
+        match (op, interval(left), interval(right)) {
+            (ast::ArithmeticOperator::Add, None, Some((value, unit))) => Some((left, value, unit)),
+            (ast::ArithmeticOperator::Add, Some((value, unit)), None) => Some((right, value, unit)),
+            (ast::ArithmeticOperator::Sub, None, Some((value, unit))) => {
+                Some((left, value.checked_neg()?, unit))
+            }
+            _ => None,
+        }
+    }
+
+    fn print_timestamp_shift(
+        &mut self,
+        timestamp: &ast::ValueExpression,
+        value: i64,
+        unit: &ast::IntervalUnit,
+    ) -> FResult {
+        let start = self.output.len();
+        self.print_val_expr(timestamp)?;
+        let timestamp = self.output.split_off(start);
+        match self.dialect.shift_timestamp(&timestamp, value, unit) {
+            Some(shifted) => write!(self.output, "{}", shifted),
+            None => self.unsupported(String::from("Timestamp arithmetic")),
+        }
+    }
+
+    /// Prints a function template, replacing `{0}`, `{1}`, ... by the arguments
+    fn print_template(&mut self, template: &str, args: &[Box<ast::ValueExpression>]) -> FResult {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            write!(self.output, "{}", &rest[..start])?;
+            let end = start + rest[start..].find('}').ok_or(std::fmt::Error)?;
+            let index: usize = rest[start + 1..end].parse().map_err(|_| std::fmt::Error)?;
+            self.print_val_expr(args.get(index).ok_or(std::fmt::Error)?)?;
+            rest = &rest[end + 1..];
+        }
+        write!(self.output, "{}", rest)
+    }
+
+    /// Prints the shortest representation which reads back as exactly the same value,
+    /// always including a decimal point or exponent so that it is not read as an integer.
+    /// SQL has no literals for the special values, so the dialect decides how to write those.
+    fn print_real(&mut self, value: f64) -> FResult {
+        if value.is_finite() {
+            return write!(self.output, "{:?}", value);
+        }
+        match self.dialect.special_real(value) {
+            Some(special) => write!(self.output, "{}", special),
+            None => {
+                let name = match value {
+                    value if value.is_nan() => "NaN",
+                    value if value < 0.0 => "-Infinity",
+                    _ => "Infinity",
+                };
+                self.unsupported(format!("The real value {}", name))
+            }
+        }
+    }
+
+    /// Prints an operand of an arithmetic expression, parenthesizing it when
+    /// it would otherwise bind to the wrong operator.
+    fn print_operand(
+        &mut self,
+        operand: &ast::ValueExpression,
+        parent: &ast::ArithmeticOperator,
+        is_right: bool,
+    ) -> FResult {
+        let needs_parens = match operand {
+            ast::ValueExpression::ArithmeticExpr { op, .. } => {
+                op.precedence() < parent.precedence()
+                    || (is_right && op.precedence() == parent.precedence())
+            }
+            _ => false,
+        };
+        if needs_parens {
+            write!(self.output, "(")?;
+            self.print_val_expr(operand)?;
+            write!(self.output, ")")
+        } else {
+            self.print_val_expr(operand)
+        }
+    }
+
+    fn print_val_exprs(&mut self, exprs: &[Box<ast::ValueExpression>]) -> FResult {
+        let mut first = true;
+        for expr in exprs.iter() {
+            if !first {
+                write!(self.output, ", ")?;
+            }
+            self.print_val_expr(expr)?;
+            first = false;
+        }
+        Ok(())
+    }
+
     fn print_search_condition(&mut self, cond: &ast::SearchCondition) -> FResult {
         match cond {
             ast::SearchCondition::BoolExpr { left, op, right } => {
@@ -173,16 +593,186 @@ impl Printer {
             ast::SearchCondition::ComparisonExpr { left, op, right } => {
                 write!(self.output, "(")?;
                 self.print_val_expr(left)?;
-                write!(self.output, " {} ", op)?;
+                write!(self.output, " {} ", self.dialect.comparison_operator(op))?;
                 self.print_val_expr(right)?;
                 write!(self.output, ")")?;
             }
+            ast::SearchCondition::Exists { negated, query } => {
+                if *negated {
+                    write!(self.output, "NOT ")?;
+                }
+                write!(self.output, "EXISTS (")?;
+                self.end_line();
+
+                self.indent();
+                self.print_query(query)?;
+                self.dedent();
+
+                self.start_line();
+                write!(self.output, ")")?;
+            }
+            ast::SearchCondition::IsNull { value, negated } => {
+                write!(self.output, "(")?;
+                self.print_val_expr(value)?;
+                if *negated {
+                    write!(self.output, " IS NOT NULL)")?;
+                } else {
+                    write!(self.output, " IS NULL)")?;
+                }
+            }
+            ast::SearchCondition::Not { condition } => {
+                write!(self.output, "NOT ")?;
+                let is_parenthesized = matches!(
+                    **condition,
+                    ast::SearchCondition::BoolExpr { .. }
+                        | ast::SearchCondition::ComparisonExpr { .. }
+                        | ast::SearchCondition::IsNull { .. }
+                        | ast::SearchCondition::Like { .. }
+                        | ast::SearchCondition::Regex { .. }
+                        | ast::SearchCondition::In { .. }
+                        | ast::SearchCondition::Between { .. }
+                );
+                if is_parenthesized {
+                    self.print_search_condition(condition)?;
+                } else {
+                    write!(self.output, "(")?;
+                    self.print_search_condition(condition)?;
+                    write!(self.output, ")")?;
+                }
+            }
+            ast::SearchCondition::Like {
+                value,
+                pattern,
+                case_insensitive,
+                negated,
+            } => {
+                let not = if *negated { " NOT" } else { "" };
+                if !*case_insensitive && self.dialect.like_ignores_case() {
+                    let glob = match &**pattern {
+                        ast::ValueExpression::Literal {
+                            value: ast::Literal::String(pattern),
+                        } => like_to_glob(pattern),
+                        _ => return self.unsupported(String::from("Case-sensitive LIKE")),
+                    };
+                    write!(self.output, "(")?;
+                    self.print_val_expr(value)?;
+                    write!(
+                        self.output,
+                        "{} GLOB {})",
+                        not,
+                        self.dialect.quote_string(&glob)
+                    )?;
+                    return Ok(());
+                }
+
+                let operator = if *case_insensitive {
+                    self.dialect.case_insensitive_like()
+                } else {
+                    Some("LIKE")
+                };
+                write!(self.output, "(")?;
+                match operator {
+                    Some(operator) => {
+                        self.print_val_expr(value)?;
+                        write!(self.output, "{} {} ", not, operator)?;
+                        self.print_val_expr(pattern)?;
+                    }
+                    // both sides are lowercased instead
+                    None => {
+                        write!(self.output, "LOWER(")?;
+                        self.print_val_expr(value)?;
+                        write!(self.output, "){} LIKE LOWER(", not)?;
+                        self.print_val_expr(pattern)?;
+                        write!(self.output, ")")?;
+                    }
+                }
+                if !self.dialect.backslash_escapes_like() {
+                    write!(self.output, " ESCAPE {}", self.dialect.quote_string("\\"))?;
+                }
+                write!(self.output, ")")?;
+            }
+            ast::SearchCondition::Regex {
+                value,
+                pattern,
+                negated,
+            } => {
+                write!(self.output, "(")?;
+                self.print_val_expr(value)?;
+                write!(self.output, " {} ", self.dialect.regex_operator(*negated))?;
+                self.print_val_expr(pattern)?;
+                write!(self.output, ")")?;
+            }
+            ast::SearchCondition::In {
+                value,
+                list,
+                negated,
+            } => {
+                write!(self.output, "(")?;
+                self.print_val_expr(value)?;
+                if *negated {
+                    write!(self.output, " NOT")?;
+                }
+                write!(self.output, " IN (")?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.output, ", ")?;
+                    }
+                    self.print_val_expr(item)?;
+                }
+                write!(self.output, "))")?;
+            }
+            ast::SearchCondition::Between {
+                value,
+                low,
+                high,
+                negated,
+            } => {
+                write!(self.output, "(")?;
+                self.print_val_expr(value)?;
+                if *negated {
+                    write!(self.output, " NOT")?;
+                }
+                write!(self.output, " BETWEEN ")?;
+                self.print_val_expr(low)?;
+                write!(self.output, " AND ")?;
+                self.print_val_expr(high)?;
+                write!(self.output, ")")?;
+            }
         }
         Ok(())
     }
 }
 
-impl From<Printer> for String {
+/// Translates a LIKE pattern escaped with `\` into a GLOB pattern. GLOB has no escape
+/// character, so its own wildcards are matched literally by putting them in brackets.
+fn like_to_glob(pattern: &str) -> String {
+    let mut glob = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => glob.push('*'),
+            '_' => glob.push('?'),
+            '\\' => match chars.next() {
+                Some(escaped) => push_glob_literal(&mut glob, escaped),
+                None => glob.push('\\'),
+            },
+            c => push_glob_literal(&mut glob, c),
+        }
+    }
+    glob
+}
+
+fn push_glob_literal(glob: &mut String, c: char) {
+    if matches!(c, '*' | '?' | '[') {
+        glob.push('[');
+        glob.push(c);
+        glob.push(']');
+    } else {
+        glob.push(c);
+    }
+}
+
+impl From<Printer<'_>> for String {
     fn from(p: Printer) -> Self {
         p.output
     }
@@ -191,10 +781,34 @@ impl From<Printer> for String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    /// Reads back a string literal printed by a dialect, undoing its escapes
+    fn unquote_string(literal: &str, backslash_escapes: bool) -> String {
+        let inner = &literal[1..literal.len() - 1];
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    assert_eq!(chars.next(), Some('\''), "unescaped quote in {}", literal);
+                    value.push('\'');
+                }
+                '\\' if backslash_escapes => match chars.next() {
+                    Some('0') => value.push('\0'),
+                    Some(escaped) => value.push(escaped),
+                    None => panic!("dangling backslash in {}", literal),
+                },
+                c => value.push(c),
+            }
+        }
+        value
+    }
 
     #[test]
     fn test_simple() {
         let query = ast::SelectStatement {
+            with: vec![],
             modifier: None,
             select: ast::SelectList {
                 wildcard: true,
@@ -202,17 +816,276 @@ mod tests {
             },
             from: ast::TableReference::TableName {
                 name: String::from("users"),
+                alias: None,
             },
             where_: None,
+            group_by: None,
             order_by: None,
             limit: None,
         };
 
-        let mut printer = Printer::default();
+        let mut printer = Printer::new(&PostgreSql);
         assert!(printer.print_query(&query).is_ok());
         assert_eq!(
             String::from("SELECT *\nFROM users\n"),
             String::from(printer)
         );
     }
+
+    #[test]
+    fn test_with() {
+        let cte = |name: &str, table: &str| ast::CommonTableExpression {
+            name: String::from(name),
+            query: ast::SelectStatement::simple(String::from(table)),
+        };
+        let mut query = ast::SelectStatement::simple(String::from("b"));
+        query.with = vec![cte("a", "users"), cte("b", "a")];
+
+        let mut printer = Printer::new(&PostgreSql);
+        assert!(printer.print_query(&query).is_ok());
+        assert_eq!(
+            String::from(
+                "WITH a AS (\n    SELECT *\n    FROM users\n),\nb AS (\n    SELECT *\n    FROM a\n)\nSELECT *\nFROM b\n"
+            ),
+            String::from(printer)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        let column = |name: &str| {
+            Box::new(ast::ValueExpression::Column {
+                name: String::from(name),
+            })
+        };
+        let arithmetic =
+            |left, op, right| Box::new(ast::ValueExpression::ArithmeticExpr { left, op, right });
+        let expr = arithmetic(
+            arithmetic(column("a"), ast::ArithmeticOperator::Add, column("b")),
+            ast::ArithmeticOperator::Mul,
+            arithmetic(
+                column("c"),
+                ast::ArithmeticOperator::Sub,
+                arithmetic(column("d"), ast::ArithmeticOperator::Sub, column("e")),
+            ),
+        );
+
+        let mut printer = Printer::new(&PostgreSql);
+        assert!(printer.print_val_expr(&expr).is_ok());
+        assert_eq!(
+            String::from("(a + b) * (c - (d - e))"),
+            String::from(printer)
+        );
+    }
+
+    #[test]
+    fn test_order_by() {
+        let query = ast::SelectStatement {
+            with: vec![],
+            modifier: None,
+            select: ast::SelectList {
+                wildcard: true,
+                columns: vec![],
+            },
+            from: ast::TableReference::TableName {
+                name: String::from("users"),
+                alias: None,
+            },
+            where_: None,
+            group_by: None,
+            order_by: Some(ast::OrderByClause {
+                specs: vec![
+                    ast::SortSpecification {
+                        value: Box::new(ast::ValueExpression::Column {
+                            name: String::from("age"),
+                        }),
+                        order: ast::SortOrder::Descending,
+                        nulls: Some(ast::NullsPosition::Last),
+                    },
+                    ast::SortSpecification {
+                        value: Box::new(ast::ValueExpression::Column {
+                            name: String::from("name"),
+                        }),
+                        order: ast::SortOrder::Ascending,
+                        nulls: None,
+                    },
+                ],
+            }),
+            limit: Some(10),
+        };
+
+        let mut printer = Printer::new(&PostgreSql);
+        assert!(printer.print_query(&query).is_ok());
+        assert_eq!(
+            String::from(
+                "SELECT *\nFROM users\nORDER BY age DESC NULLS LAST, name ASC\nLIMIT 10\n"
+            ),
+            String::from(printer)
+        );
+    }
+
+    #[test]
+    fn test_dialects() {
+        let column = |name: &str| {
+            Box::new(ast::ValueExpression::Column {
+                name: String::from(name),
+            })
+        };
+        let mut query = ast::SelectStatement::simple(String::from("My Table"));
+        query.where_ = Some(Box::new(ast::SearchCondition::BoolExpr {
+            left: Box::new(ast::SearchCondition::ComparisonExpr {
+                left: column("name"),
+                op: ast::ComparisonOperator::EQ,
+                right: Box::new(ast::ValueExpression::Literal {
+                    value: ast::Literal::String(String::from("it's")),
+                }),
+            }),
+            op: ast::BoolOperator::AND,
+            right: Box::new(ast::SearchCondition::ComparisonExpr {
+                left: column("active"),
+                op: ast::ComparisonOperator::NEQ,
+                right: Box::new(ast::ValueExpression::Literal {
+                    value: ast::Literal::Bool(false),
+                }),
+            }),
+        }));
+        query.order_by = Some(ast::OrderByClause {
+            specs: vec![ast::SortSpecification {
+                value: column("age"),
+                order: ast::SortOrder::Ascending,
+                nulls: Some(ast::NullsPosition::Last),
+            }],
+        });
+        query.limit = Some(5);
+
+        let print = |dialect: &dyn Dialect| emit(&query, dialect).unwrap();
+        assert_eq!(
+            print(&SingleStore),
+            "SELECT *\nFROM `My Table`\nWHERE ((name = 'it''s') AND (active != FALSE))\nORDER BY (age IS NULL) ASC, age ASC\nLIMIT 5\n"
+        );
+        assert_eq!(
+            print(&Ansi),
+            "SELECT *\nFROM \"My Table\"\nWHERE ((name = 'it''s') AND (active <> FALSE))\nORDER BY age ASC NULLS LAST\nFETCH FIRST 5 ROWS ONLY\n"
+        );
+        assert_eq!(
+            print(&Sqlite),
+            "SELECT *\nFROM \"My Table\"\nWHERE ((name = 'it''s') AND (active != 0))\nORDER BY age ASC NULLS LAST\nLIMIT 5\n"
+        );
+    }
+
+    /// A dialect which limits rows like SQL Server
+    struct TopDialect;
+
+    impl Dialect for TopDialect {
+        fn limit_syntax(&self) -> LimitSyntax {
+            LimitSyntax::Top
+        }
+    }
+
+    #[test]
+    fn test_top() {
+        let mut query = ast::SelectStatement::simple(String::from("users"));
+        query.modifier = Some(ast::Modifier::Distinct);
+        query.order_by = Some(ast::OrderByClause {
+            specs: vec![ast::SortSpecification {
+                value: Box::new(ast::ValueExpression::Column {
+                    name: String::from("age"),
+                }),
+                order: ast::SortOrder::Descending,
+                nulls: None,
+            }],
+        });
+        query.limit = Some(5);
+
+        assert_eq!(
+            emit(&query, &TopDialect).unwrap(),
+            "SELECT DISTINCT TOP 5 *\nFROM users\nORDER BY age DESC\n"
+        );
+    }
+
+    #[test]
+    fn test_identifier_quoting() {
+        let column = |name: &str| ast::SelectColumn {
+            value: Box::new(ast::ValueExpression::Column {
+                name: String::from(name),
+            }),
+            alias: None,
+        };
+        let mut query = ast::SelectStatement::simple(String::from("events"));
+        query.select = ast::SelectList {
+            wildcard: false,
+            columns: vec![column("name"), column("order"), column("My `Column`")],
+        };
+
+        let print = |dialect: &dyn Dialect| emit(&query, dialect).unwrap();
+        assert_eq!(
+            print(&SingleStore),
+            "SELECT name, `order`, `My ``Column```\nFROM events\n"
+        );
+        assert_eq!(
+            print(&Strict(&PostgreSql)),
+            "SELECT \"name\", \"order\", \"My `Column`\"\nFROM \"events\"\n"
+        );
+    }
+
+    #[test]
+    fn test_function_syntax() {
+        let column = |name: &str| {
+            Box::new(ast::ValueExpression::Column {
+                name: String::from(name),
+            })
+        };
+        let locate = ast::ValueExpression::FuncCall {
+            name: String::from("LOCATE"),
+            args: vec![column("needle"), column("haystack")],
+        };
+        let epoch = ast::ValueExpression::FuncCall {
+            name: String::from("UNIX_TIMESTAMP"),
+            args: vec![column("time")],
+        };
+
+        let print = |dialect: &dyn Dialect, expr: &ast::ValueExpression| {
+            let mut printer = Printer::new(dialect);
+            printer.print_val_expr(expr).map(|_| String::from(printer))
+        };
+        assert_eq!(
+            print(&SingleStore, &locate).unwrap(),
+            "LOCATE(needle, haystack)"
+        );
+        assert_eq!(
+            print(&PostgreSql, &locate).unwrap(),
+            "STRPOS(haystack, needle)"
+        );
+        assert_eq!(print(&Sqlite, &locate).unwrap(), "INSTR(haystack, needle)");
+        assert_eq!(
+            print(&Ansi, &locate).unwrap(),
+            "POSITION(needle IN haystack)"
+        );
+        assert_eq!(
+            print(&PostgreSql, &epoch).unwrap(),
+            "EXTRACT(EPOCH FROM time)"
+        );
+        assert!(print(&Ansi, &epoch).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_string_literals_round_trip(value in any::<String>()) {
+            let literal = ast::ValueExpression::Literal {
+                value: ast::Literal::String(value.clone()),
+            };
+            let dialects: [(&dyn Dialect, bool); 4] = [
+                (&SingleStore, true),
+                (&PostgreSql, false),
+                (&Sqlite, false),
+                (&Ansi, false),
+            ];
+            for (dialect, backslash_escapes) in dialects {
+                let mut printer = Printer::new(dialect);
+                printer.print_val_expr(&literal).unwrap();
+                let printed = String::from(printer);
+                prop_assert_eq!(&unquote_string(&printed, backslash_escapes), &value);
+            }
+        }
+    }
 }